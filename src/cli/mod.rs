@@ -1,17 +1,175 @@
 //! CLI argument parsing and validation interfaces
 
+use std::path::Path;
+use crate::png::PngFile;
+use crate::polyglot::EmbeddingMethod;
+use crate::utils::FileFormat;
+use crate::{PolyglotError, PolyglotResult};
+
+/// Process exit codes; scripts may rely on these staying stable
+pub const EXIT_SUCCESS: i32 = 0;
+/// Input the command can't use, failed creation and size overflows, which the scheme has no code for
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid arguments or combinations of options (clap also exits with this code)
+pub const EXIT_INVALID_USAGE: i32 = 2;
+/// The file was read but is not a valid polyglot, or the expected payload is missing
+pub const EXIT_VALIDATION_FAILED: i32 = 3;
+/// A file could not be read or written
+pub const EXIT_IO_ERROR: i32 = 4;
+/// A file could not be parsed or decoded
+pub const EXIT_PARSE_ERROR: i32 = 5;
+/// The command needs a Cargo feature this build was compiled without
+pub const EXIT_FEATURE_DISABLED: i32 = 6;
 
 /// Validation result for polyglot files
 #[derive(Debug, PartialEq)]
 pub enum ValidationResult {
     /// File is a valid PNG/ZIP polyglot
     Valid,
+    /// File is a valid PNG/WAV or WAV/PNG polyglot
+    ValidWavPng,
     /// Invalid PNG with error message
     InvalidPng(String),
     /// Invalid ZIP with error message
     InvalidZip(String),
     /// Both PNG and ZIP are invalid
     InvalidBoth(String, String),
+    /// The WAV side doesn't decode, with error message
+    InvalidWav(String),
+    /// Neither the WAV nor the PNG side is valid
+    InvalidWavAndPng(String, String),
+}
+
+impl ValidationResult {
+    /// Whether the file passed, whichever pair of formats it holds
+    pub fn is_valid(&self) -> bool {
+        matches!(self, ValidationResult::Valid | ValidationResult::ValidWavPng)
+    }
+}
+
+/// Dominant format implied by an output file name, with warnings about misleading extensions
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputNameCheck {
+    pub dominant: Option<FileFormat>, // From the final extension, which decides how the file opens
+    pub warnings: Vec<String>,
+}
+
+/// Infer dominance from the whole file name, warning when extensions contradict each other or the method
+///
+/// `out.png.zip` opens as a ZIP whatever the `.png` in the middle suggests, so only the
+/// final extension counts; the earlier ones are reported instead of silently ignored.
+pub fn check_output_name(path: &Path, method: Option<EmbeddingMethod>) -> OutputNameCheck {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let extensions: Vec<(&str, FileFormat)> = file_name.split('.')
+        .skip(1) // The stem
+        .filter_map(|extension| FileFormat::from_extension(extension).map(|format| (extension, format)))
+        .collect();
+
+    let final_extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+    let dominant = FileFormat::from_extension(&final_extension);
+    let mut warnings = Vec::new();
+
+    if let Some(dominant) = dominant {
+        for (extension, format) in &extensions {
+            if *format != dominant {
+                warnings.push(format!(
+                    "{} also has a .{} extension, but it ends in .{} and will be opened as a {}",
+                    file_name, extension, final_extension, dominant.name()
+                ));
+            }
+        }
+
+        if let Some(method) = method
+            && let Some(expected) = method.dominant_format()
+            && expected != dominant {
+            warnings.push(format!(
+                "--method {} writes a {}-dominant file, but {} ends in .{}",
+                method.name(), expected.name(), file_name, final_extension
+            ));
+        }
+    }
+
+    OutputNameCheck { dominant, warnings }
+}
+
+/// Describe every embedding method, one per line, for `methods`
+pub fn format_method_list() -> String {
+    let mut output = String::new();
+    for method in EmbeddingMethod::ALL {
+        output.push_str(&format!(
+            "{:<14} formats: {:<24} limit: {:<40} overhead: {:<5} carrier: {}\n",
+            method.name(),
+            method.formats(),
+            method.size_limit(),
+            method.overhead_bytes(),
+            method.carrier_behaviour(),
+        ));
+    }
+    output
+}
+
+/// Describe a FLAC's stream parameters and metadata blocks for `info`
+pub fn format_flac_info(flac: &crate::flac::FlacFile) -> String {
+    let streaminfo = &flac.structure.streaminfo;
+    let mut output = format!("Format: FLAC, {} bytes\n", flac.raw_data.len());
+    output.push_str(&format!(
+        "Stream: {} Hz, {} channels, {} bits per sample, {} samples\n",
+        streaminfo.sample_rate, streaminfo.channels, streaminfo.bits_per_sample, streaminfo.total_samples
+    ));
+    output.push_str("Metadata blocks:\n");
+    for block in &flac.structure.metadata_blocks {
+        output.push_str(&format!("  {:<14} type: {:<3} length: {}\n", block.name(), block.block_type(), block.len()));
+    }
+    output
+}
+
+/// Describe a PNG's chunk layout and metadata for `info`
+pub fn format_png_info(png: &PngFile) -> PolyglotResult<String> {
+    let mut output = format!("Format: PNG, {} bytes\n", png.raw_data.len());
+    if let Some(time) = png.time_chunk()? {
+        output.push_str(&format!("Last modified: {}\n", time));
+    }
+    if let Some(phys) = png.get_phys()? {
+        output.push_str(&format!("Physical size: {}\n", phys));
+    }
+    if let Ok(capacity) = png.lsb_capacity() {
+        output.push_str(&format!("LSB capacity: {} bytes (1 bit per channel)\n", capacity));
+    }
+    let overheads: Vec<String> = EmbeddingMethod::ALL.iter()
+        .map(|method| format!("{} {}", method.name(), method.overhead_bytes_for(png).unwrap_or_else(|_| method.overhead_bytes())))
+        .collect();
+    output.push_str(&format!("Embedding overhead (bytes): {}\n", overheads.join(", ")));
+    output.push_str("Chunks:\n");
+    for chunk in &png.parsed.chunks {
+        output.push_str(&format!(
+            "  {} length: {:<10} offset: {}\n",
+            String::from_utf8_lossy(&chunk.chunk_type),
+            chunk.length,
+            chunk.start_offset(),
+        ));
+    }
+    Ok(output)
+}
+
+/// Exit code for an error, following the `EXIT_*` scheme
+pub fn exit_code(error: &PolyglotError) -> i32 {
+    match error {
+        PolyglotError::Usage(_) => EXIT_INVALID_USAGE,
+        PolyglotError::ValidationFailed(_) => EXIT_VALIDATION_FAILED,
+        PolyglotError::InputFile(_) => EXIT_IO_ERROR,
+        PolyglotError::PngParse(_)
+        | PolyglotError::ZipParse(_)
+        | PolyglotError::WavParse(_)
+        | PolyglotError::GifParse(_)
+        | PolyglotError::PdfParse(_)
+        | PolyglotError::CrcMismatch(_)
+        | PolyglotError::NoIdatChunk
+        | PolyglotError::InvalidRiffHeader
+        | PolyglotError::ChunkNotFound(_)
+        | PolyglotError::DecodeError(_) => EXIT_PARSE_ERROR,
+        PolyglotError::InvalidInput(_) | PolyglotError::SizeOverflow | PolyglotError::CreationFailed(_) => EXIT_FAILURE,
+        PolyglotError::FeatureDisabled(_) => EXIT_FEATURE_DISABLED,
+    }
 }
 
 // Additional CLI-related functions can be added here
@@ -28,4 +186,45 @@ mod tests {
         let invalid_png = ValidationResult::InvalidPng("test".to_string());
         assert!(matches!(invalid_png, ValidationResult::InvalidPng(_)));
     }
+
+    #[test]
+    fn test_double_extension_warns_and_final_extension_wins() {
+        let check = check_output_name(Path::new("out/out.png.zip"), Some(EmbeddingMethod::Text));
+        assert_eq!(check.dominant, Some(FileFormat::Zip));
+        assert_eq!(check.warnings.len(), 2, "{:?}", check.warnings);
+        assert!(check.warnings[0].contains(".png extension"));
+        assert!(check.warnings[1].starts_with("--method text writes a PNG-dominant file"));
+
+        // The method agrees with the final extension: only the stray .png is reported
+        let check = check_output_name(Path::new("out.png.zip"), Some(EmbeddingMethod::Zip));
+        assert_eq!(check.warnings.len(), 1);
+
+        let check = check_output_name(Path::new("out.v2.png"), Some(EmbeddingMethod::Text));
+        assert_eq!(check, OutputNameCheck { dominant: Some(FileFormat::Png), warnings: Vec::new() });
+
+        // Extensions match whatever their case
+        let check = check_output_name(Path::new("OUT.Png"), Some(EmbeddingMethod::Text));
+        assert_eq!(check, OutputNameCheck { dominant: Some(FileFormat::Png), warnings: Vec::new() });
+    }
+
+    #[test]
+    fn test_method_list_covers_all_methods() {
+        let output = format_method_list();
+
+        for name in ["idat", "text", "zip", "bidirectional"] {
+            assert!(output.lines().any(|line| line.starts_with(name)), "missing method {}", name);
+        }
+        assert!(output.lines().any(|line| line.starts_with("text") && line.contains("overhead: 24")));
+    }
+
+    #[test]
+    fn test_exit_code_per_error_kind() {
+        assert_eq!(exit_code(&PolyglotError::Usage("--log needs --zip".to_string())), EXIT_INVALID_USAGE);
+        assert_eq!(exit_code(&PolyglotError::InvalidInput("not a polyglot".to_string())), EXIT_FAILURE);
+        assert_eq!(exit_code(&PolyglotError::feature_disabled("compression")), EXIT_FEATURE_DISABLED);
+        assert_eq!(exit_code(&PolyglotError::CreationFailed("write".to_string())), EXIT_FAILURE);
+        assert_eq!(exit_code(&PolyglotError::ValidationFailed("no payload".to_string())), EXIT_VALIDATION_FAILED);
+        assert_eq!(exit_code(&PolyglotError::InputFile(std::io::ErrorKind::NotFound.into())), EXIT_IO_ERROR);
+        assert_eq!(exit_code(&PolyglotError::PngParse("signature".to_string())), EXIT_PARSE_ERROR);
+    }
 }
@@ -1,19 +1,39 @@
 //! Polyglot validation and extraction functionality
 
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
 use std::fs;
-use crate::zip::ZipArchive;
+use crate::png::PngFile;
+use crate::polyglot::EmbeddingMethod;
+use crate::zip::{offsets, ZipArchive};
 use crate::cli::ValidationResult;
+use crate::index::{read_trailing_index, IndexEntry};
+use crate::utils::{FileFormat, PNG_SIGNATURE, RIFF_SIGNATURE, ZIP_CENTRAL_DIRECTORY_SIGNATURE, ZIP64_EOCD_SIGNATURE, ZIP_EOCD_SIGNATURE, ZIP_LOCAL_SIGNATURE};
 use crate::{PolyglotError, PolyglotResult};
 
-/// Validate that a file is a valid ZIP/PNG polyglot
+/// Validate that a file is a valid ZIP/PNG or WAV/PNG polyglot
+///
+/// WAV sides are checked by decoding the audio with `WavFile::verify_decodes`.
 pub fn validate_polyglot(path: &Path) -> PolyglotResult<ValidationResult> {
     let data = fs::read(path)?;
 
     // Determine dominant format by checking first signature
     let is_png_first = crate::utils::is_png_signature(&data);
 
-    if is_png_first {
+    if data.starts_with(&RIFF_SIGNATURE) {
+        // WAV-dominant: the audio must decode and the PNG chunk must parse
+        let wav_result = crate::wav::WavFile::from_data(data.clone()).and_then(|wav| wav.verify_decodes());
+        let png_result = crate::wav::WavFile::extract_png_from_wav_polyglot(&data)
+            .ok_or_else(|| PolyglotError::ValidationFailed("no PNG chunk in WAV".to_string()))
+            .and_then(|png| validate_as_png(&png));
+        Ok(wav_png_result(wav_result.map(|_| ()), png_result))
+    } else if is_png_first && find_zip_signature(&data[8..]).is_none() && find_riff_signature(&data[8..]).is_some() {
+        // PNG-dominant with a WAV payload
+        let wav_result = extract_wav_from_png_data(&data)
+            .and_then(crate::wav::WavFile::from_data)
+            .and_then(|wav| wav.verify_decodes());
+        Ok(wav_png_result(wav_result.map(|_| ()), validate_as_png(&data)))
+    } else if is_png_first {
         // PNG-dominant: validate PNG first, then ZIP within PNG
         let png_result = validate_as_png(&data);
         let zip_result = validate_zip_within_png(&data);
@@ -44,6 +64,383 @@ pub fn validate_polyglot(path: &Path) -> PolyglotResult<ValidationResult> {
     }
 }
 
+/// Combine the checks of a polyglot's WAV and PNG sides
+fn wav_png_result(wav_result: PolyglotResult<()>, png_result: PolyglotResult<()>) -> ValidationResult {
+    match (wav_result, png_result) {
+        (Ok(_), Ok(_)) => ValidationResult::ValidWavPng,
+        (Err(wav_err), Ok(_)) => ValidationResult::InvalidWav(wav_err.to_string()),
+        (Ok(_), Err(png_err)) => ValidationResult::InvalidPng(png_err.to_string()),
+        (Err(wav_err), Err(png_err)) => ValidationResult::InvalidWavAndPng(wav_err.to_string(), png_err.to_string()),
+    }
+}
+
+/// Bytes left between the end of the ZIP's EOCD record (with its comment) and the end of its container
+///
+/// The container is the file for a ZIP-dominant polyglot and the PNG chunk holding the EOCD
+/// for a PNG-dominant one, so a ZIP in tEXt reports 0 although IEND follows it. Anything
+/// else there is stacked data or tampering, and some ZIP tools refuse such files.
+/// Returns `None` when there is no EOCD to measure from.
+pub fn trailing_bytes_after_eocd(data: &[u8]) -> Option<usize> {
+    let eocd_offset = offsets::find_eocd_offset(data).ok()?;
+    let eocd_end = eocd_offset + 22 + offsets::read_eocd(data, eocd_offset).comment_length as usize;
+
+    let container_end = if crate::utils::is_png_signature(data) {
+        let parsed = crate::png::parser::parse_png_chunks_lenient(data).ok()?;
+        match parsed.chunk_at_offset(eocd_offset) {
+            Some((chunk, _)) => chunk.data_offset + chunk.data.len(),
+            None => data.len(), // Appended after the PNG
+        }
+    } else {
+        data.len()
+    };
+
+    Some(container_end.saturating_sub(eocd_end))
+}
+
+/// First structural problem in a would-be polyglot, as a file offset and description
+///
+/// Used to point the verbose validation report at the bytes that broke it: a truncated
+/// or corrupt PNG chunk, or a missing ZIP end of central directory record.
+pub fn locate_anomaly(data: &[u8]) -> Option<(usize, String)> {
+    if crate::utils::is_png_signature(data) {
+        let mut offset = 8;
+        loop {
+            if offset + 8 > data.len() {
+                return Some((offset, "PNG ends before IEND (truncated chunk header)".to_string()));
+            }
+            let length = crate::utils::read_u32_be(data, offset) as usize;
+            let chunk_type = String::from_utf8_lossy(&data[offset + 4..offset + 8]).into_owned();
+            let crc_offset = offset + 8 + length;
+            if crc_offset + 4 > data.len() {
+                let hint = match suggest_swapped_chunk_length(data, offset) {
+                    Some(swapped) => format!(
+                        " - length {} looks byte-swapped (written little-endian?), {} would fit{}",
+                        length, swapped, if swapped_length_crc_matches(data, offset, swapped) { " and matches the CRC" } else { "" }
+                    ),
+                    None => String::new(),
+                };
+                return Some((offset, format!("{} chunk is truncated{}", chunk_type, hint)));
+            }
+            let crc = crate::utils::read_u32_be(data, crc_offset);
+            if crc != crate::utils::calculate_crc32(&data[offset + 4..crc_offset]) {
+                return Some((crc_offset, format!("bad CRC in {} chunk", chunk_type)));
+            }
+            if chunk_type == "IEND" {
+                break;
+            }
+            offset = crc_offset + 4;
+        }
+    }
+
+    if offsets::find_eocd_offset(data).is_err() {
+        return Some((data.len().saturating_sub(1), "ZIP end of central directory record not found".to_string()));
+    }
+
+    None
+}
+
+/// Likely intended length of the PNG chunk at `offset`, if its stored length was written little-endian
+///
+/// PNG lengths are big-endian while ZIP's are little-endian, so hand-built polyglots
+/// sometimes mix them up. A length that overruns the file or PNG's 2^31-1 limit but
+/// fits once byte-swapped is reported; this is a diagnostic and changes nothing.
+pub fn suggest_swapped_chunk_length(data: &[u8], offset: usize) -> Option<u32> {
+    if offset + 8 > data.len() {
+        return None;
+    }
+    let length = crate::utils::read_u32_be(data, offset);
+    let swapped = length.swap_bytes();
+    let fits = |length: u32| length <= i32::MAX as u32 && offset + 12 + length as usize <= data.len();
+
+    (!fits(length) && fits(swapped)).then_some(swapped)
+}
+
+/// Whether the chunk at `offset` has a valid CRC when read with `length`
+fn swapped_length_crc_matches(data: &[u8], offset: usize, length: u32) -> bool {
+    let crc_offset = offset + 8 + length as usize;
+    crate::utils::read_u32_be(data, crc_offset) == crate::utils::calculate_crc32(&data[offset + 4..crc_offset])
+}
+
+/// Payload located inside a polyglot carrier
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedPayload {
+    pub format: &'static str, // "zip", "wav" or "png"
+    pub offset: usize,        // Offset of the payload signature within the file
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of `detect_payloads` scans on this thread, so tests can tell when scanning was skipped
+    static SIGNATURE_SCANS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Detect embedded payloads by their signatures, skipping the carrier's own signature
+///
+/// Payloads are returned in file order, so a position in the list names the same payload every time.
+pub fn detect_payloads(data: &[u8]) -> Vec<DetectedPayload> {
+    #[cfg(test)]
+    SIGNATURE_SCANS.with(|scans| scans.set(scans.get() + 1));
+
+    if data.len() < 4 {
+        return Vec::new();
+    }
+
+    let search_start = 4; // Carrier signature occupies at least the first 4 bytes
+    let search_data = &data[search_start..];
+
+    let mut payloads = Vec::new();
+    if let Some(pos) = find_zip_signature(search_data) {
+        payloads.push(DetectedPayload { format: "zip", offset: search_start + pos });
+    }
+    if let Some(pos) = find_riff_signature(search_data) {
+        payloads.push(DetectedPayload { format: "wav", offset: search_start + pos });
+    }
+    if let Some(pos) = search_data.windows(8).position(|w| w == PNG_SIGNATURE) {
+        payloads.push(DetectedPayload { format: "png", offset: search_start + pos });
+    }
+
+    payloads.sort_by_key(|payload| payload.offset);
+    payloads
+}
+
+/// Embedding method that produced a polyglot, judged from where its payload sits
+///
+/// Returns `None` for plain files and for layouts no method produces, such as a ZIP
+/// appended after IEND by the PNG+ZIP+PDF builder.
+pub fn detect_embedding_method(data: &[u8]) -> Option<EmbeddingMethod> {
+    // ZIP-dominant layouts carry the PNG as the payload
+    if data.starts_with(&ZIP_EOCD_SIGNATURE) {
+        return crate::utils::is_png_signature(data.get(22..)?).then_some(EmbeddingMethod::Comment);
+    }
+    if data.starts_with(&ZIP_LOCAL_SIGNATURE) {
+        return detect_payloads(data).iter().any(|payload| payload.format == "png").then_some(EmbeddingMethod::Zip);
+    }
+    if !crate::utils::is_png_signature(data) {
+        return None;
+    }
+
+    let payload = detect_payloads(data).into_iter().find(|payload| payload.format != "png")?;
+    let parsed = crate::png::parser::parse_png_chunks(data).ok()?;
+    let (chunk, position) = parsed.chunk_at_offset(payload.offset)?;
+    match &chunk.chunk_type {
+        // The bidirectional builder makes the WAV the entire image data
+        b"IDAT" if position == 8 && payload.format == "wav" => Some(EmbeddingMethod::Bidirectional),
+        b"IDAT" => Some(EmbeddingMethod::Idat),
+        b"tEXt" => Some(EmbeddingMethod::Text),
+        chunk_type if *chunk_type == crate::png::PAYLOAD_CHUNK_TYPE => Some(EmbeddingMethod::Chunk),
+        _ => None,
+    }
+}
+
+/// Format of the payload `extract` writes for a polyglot: the first one found that isn't the carrier's format
+///
+/// A PNG carrier falls back to an embedded PNG only when nothing else is detected.
+pub fn detect_payload_format(data: &[u8]) -> Option<&'static str> {
+    let payloads = detect_payloads(data);
+    if crate::utils::is_png_signature(data) {
+        payloads.iter().find(|payload| payload.format != "png").or(payloads.first()).map(|payload| payload.format)
+    } else {
+        payloads.iter().find(|payload| payload.format == "png").map(|payload| payload.format)
+    }
+}
+
+/// Output path in `out_dir` for the payload of `input`, named after it with the payload's extension
+///
+/// `carrier.png` holding a ZIP becomes `carrier.zip`. A payload of the carrier's own format
+/// gets a `_payload` suffix so the input is never overwritten.
+pub fn derive_output_path(input: &Path, data: &[u8], out_dir: &Path) -> PolyglotResult<PathBuf> {
+    let format = detect_payload_format(data)
+        .ok_or_else(|| PolyglotError::ValidationFailed(format!("no payload detected in {}", input.display())))?;
+    let stem = input.file_stem()
+        .ok_or_else(|| PolyglotError::InvalidInput(format!("cannot derive a file name from {}", input.display())))?
+        .to_string_lossy();
+
+    let same_extension = input.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(format));
+    let file_name = if same_extension { format!("{}_payload.{}", stem, format) } else { format!("{}.{}", stem, format) };
+    Ok(out_dir.join(file_name))
+}
+
+/// Number of leading bytes `quick_classify` looks at
+pub const QUICK_CLASSIFY_LEN: usize = 64;
+
+/// Coarse classification from leading magic bytes alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickClass {
+    DefinitelyPng,
+    DefinitelyZip,
+    RiffWav,
+    PossiblePolyglot, // A second format's signature shows up within the header
+    Unknown,
+}
+
+/// Classify a file from its first `QUICK_CLASSIFY_LEN` bytes without parsing it
+///
+/// A cheap pre-filter for batch scans before `validate_polyglot`. Payloads placed
+/// further into the file (e.g. after IEND) are not visible here, so "definitely"
+/// only describes the leading format.
+pub fn quick_classify(header: &[u8]) -> QuickClass {
+    let header = &header[..header.len().min(QUICK_CLASSIFY_LEN)];
+
+    let leading = match crate::utils::sniff_format(header) {
+        FileFormat::Png => QuickClass::DefinitelyPng,
+        FileFormat::Zip => QuickClass::DefinitelyZip,
+        FileFormat::Wav => QuickClass::RiffWav,
+        _ => return QuickClass::Unknown,
+    };
+
+    let embedded_pdf = header.windows(5).skip(1).any(|w| w == b"%PDF-");
+    if !detect_payloads(header).is_empty() || embedded_pdf {
+        QuickClass::PossiblePolyglot
+    } else {
+        leading
+    }
+}
+
+/// Check that the embedded ZIP's central directory points at real local headers within the file
+///
+/// Stored offsets are resolved the way ZIP readers do, relative to where the archive
+/// starts if data precedes it, so both rebased (IDAT) and unrebased (text) payloads pass.
+pub fn verify_embedded_zip_offsets(polyglot: &[u8]) -> PolyglotResult<()> {
+    let eocd_offset = offsets::find_eocd_offset(polyglot)?;
+    let eocd = offsets::read_eocd(polyglot, eocd_offset);
+    let base = offsets::calculate_base_offset(polyglot, eocd_offset, &eocd);
+
+    let cd_start = base + eocd.cd_offset as usize;
+    if cd_start + 4 > eocd_offset || !polyglot[cd_start..].starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE) {
+        return Err(PolyglotError::ValidationFailed(format!(
+            "central directory offset {} does not point at a central directory header", cd_start
+        )));
+    }
+
+    let local_offsets = offsets::read_local_header_offsets(polyglot, cd_start);
+    if local_offsets.len() != eocd.num_entries_total as usize {
+        return Err(PolyglotError::ValidationFailed(format!(
+            "central directory has {} entries, EOCD declares {}", local_offsets.len(), eocd.num_entries_total
+        )));
+    }
+
+    for (entry, local_offset) in local_offsets.into_iter().enumerate() {
+        let header = base + local_offset as usize;
+        if header + 4 > polyglot.len() || polyglot[header..header + 4] != ZIP_LOCAL_SIGNATURE {
+            return Err(PolyglotError::ValidationFailed(format!(
+                "entry {} local header offset {} does not point at a local file header", entry, header
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract every payload, using the trailing payload index when there is one
+///
+/// Without an index this falls back to a signature scan, which only finds ZIP and WAV
+/// payloads and names them by format.
+pub fn extract_payloads(data: &[u8]) -> PolyglotResult<Vec<(IndexEntry, Vec<u8>)>> {
+    if let Some(index) = read_trailing_index(data)? {
+        let payloads = index.payloads(data)?;
+        return Ok(index.entries.into_iter().zip(payloads).map(|(entry, payload)| (entry, payload.to_vec())).collect());
+    }
+
+    detect_payloads(data).iter()
+        .filter(|detected| detected.format != "png")
+        .map(|detected| extract_detected_payload(data, detected))
+        .collect()
+}
+
+/// Extract one payload found by `detect_payloads`
+///
+/// A PNG is taken from wherever `payload_range` finds it; a PNG signature it can't place
+/// (e.g. inside a ZIP entry) is an error rather than a guess.
+fn extract_detected_payload(data: &[u8], detected: &DetectedPayload) -> PolyglotResult<(IndexEntry, Vec<u8>)> {
+    let (format, payload) = match detected.format {
+        "zip" => (FileFormat::Zip, extract_zip_from_png_data(data)?),
+        "wav" => (FileFormat::Wav, extract_wav_from_png_data(data)?),
+        _ => match payload_range(data)? {
+            (FileFormat::Png, range) => (FileFormat::Png, data[range].to_vec()),
+            _ => return Err(PolyglotError::ValidationFailed(format!(
+                "the PNG signature at offset {} is not an extractable payload", detected.offset
+            ))),
+        },
+    };
+    let entry = IndexEntry {
+        offset: detected.offset as u64,
+        length: payload.len() as u64,
+        format,
+        name: detected.format.to_string(),
+    };
+    Ok((entry, payload))
+}
+
+/// Extract the payload at `index` in file order, as `detect_payloads` lists them
+///
+/// A trailing index, when there is one, takes the place of the detected list.
+pub fn extract_payload_at(data: &[u8], index: usize) -> PolyglotResult<(IndexEntry, Vec<u8>)> {
+    let out_of_range = |count: usize| PolyglotError::ValidationFailed(format!(
+        "payload index {} is out of range; found {} payload(s)", index, count
+    ));
+
+    if read_trailing_index(data)?.is_some() {
+        let mut payloads = extract_payloads(data)?;
+        payloads.sort_by_key(|(entry, _)| entry.offset);
+        if index >= payloads.len() {
+            return Err(out_of_range(payloads.len()));
+        }
+        return Ok(payloads.swap_remove(index));
+    }
+
+    let detected = detect_payloads(data);
+    let payload = detected.get(index).ok_or_else(|| out_of_range(detected.len()))?;
+    extract_detected_payload(data, payload)
+}
+
+/// Format of a payload located by `payload_range`
+pub type PayloadFormat = FileFormat;
+
+/// Format and byte range of the embedded payload, for slicing it out of `data` without a copy
+///
+/// Covers the layouts the extractors handle: a ZIP or WAV in a PNG (whichever comes first),
+/// a PNG in a `plZP` chunk, and a PNG in a ZIP, a ZIP comment or a WAV. The range sliced
+/// from `data` is what the matching `extract_*_data` function returns.
+pub fn payload_range(data: &[u8]) -> PolyglotResult<(PayloadFormat, Range<usize>)> {
+    if crate::utils::is_png_signature(data) {
+        let parsed = crate::png::parser::parse_png_chunks(data)?;
+        let inner_png = parsed.chunks.iter()
+            .find(|chunk| chunk.chunk_type == crate::png::PAYLOAD_CHUNK_TYPE && crate::utils::is_png_signature(&chunk.data));
+        if let Some(chunk) = inner_png {
+            return Ok((FileFormat::Png, chunk.data_offset..chunk.data_offset + chunk.data.len()));
+        }
+
+        match (find_zip_signature(&data[8..]), find_riff_signature(&data[8..])) {
+            (Some(zip_pos), Some(riff_pos)) if riff_pos < zip_pos => Ok((FileFormat::Wav, wav_range(data)?)),
+            (Some(_), _) => Ok((FileFormat::Zip, zip_range_in_png(data)?)),
+            (None, Some(_)) => Ok((FileFormat::Wav, wav_range(data)?)),
+            (None, None) => Err(PolyglotError::ValidationFailed("No embedded payload found in PNG polyglot".to_string())),
+        }
+    } else if data.starts_with(&ZIP_EOCD_SIGNATURE) {
+        Ok((FileFormat::Png, png_range_in_zip_comment(data)?))
+    } else if data.starts_with(&ZIP_LOCAL_SIGNATURE) || data.starts_with(&RIFF_SIGNATURE) {
+        Ok((FileFormat::Png, embedded_png_range(data)?))
+    } else {
+        Err(PolyglotError::InvalidInput("not a PNG, ZIP or WAV polyglot".to_string()))
+    }
+}
+
+/// Extract the payload of a GIF- or FLAC-dominant polyglot, with its format
+///
+/// GIFs carry a ZIP in a comment or application extension; FLACs carry a PNG in a
+/// PADDING, PICTURE or APPLICATION block.
+pub fn extract_from_carrier(data: &[u8]) -> PolyglotResult<(PayloadFormat, Vec<u8>)> {
+    match crate::utils::sniff_format(data) {
+        FileFormat::Gif => crate::gif::extract_embedded_zip(data)?
+            .map(|zip| (FileFormat::Zip, zip))
+            .ok_or_else(|| PolyglotError::ValidationFailed("No ZIP found in GIF comment or application extensions".to_string())),
+        FileFormat::Flac => crate::flac::FlacFile::from_data(data.to_vec())?.extract_embedded_png()?
+            .map(|png| (FileFormat::Png, png))
+            .ok_or_else(|| PolyglotError::ValidationFailed("No PNG found in FLAC metadata blocks".to_string())),
+        other => Err(PolyglotError::InvalidInput(format!("extraction from a {} carrier is not supported", other.name()))),
+    }
+}
+
 /// Extract the embedded archive from a PNG/ZIP polyglot file
 pub fn extract_zip_from_png(polyglot_path: &Path, output_path: &Path) -> PolyglotResult<()> {
     let data = fs::read(polyglot_path)?;
@@ -51,20 +448,73 @@ pub fn extract_zip_from_png(polyglot_path: &Path, output_path: &Path) -> Polyglo
     // Determine format by checking first signature
     let is_png_first = crate::utils::is_png_signature(&data);
 
-    if is_png_first {
+    let range = if is_png_first {
         // PNG-dominant: extract ZIP from within PNG
-        extract_zip_from_png_file(&data, output_path)
+        zip_range_in_png(&data)?
     } else {
         // ZIP-dominant: extract PNG from within ZIP (legacy)
-        extract_png_from_zip_file(&data, output_path)
+        embedded_png_range(&data)?
+    };
+    fs::write(output_path, &data[range])?;
+
+    Ok(())
+}
+
+/// Split a PNG-dominant polyglot file into a clean carrier PNG and its payload
+pub fn split_polyglot(polyglot_path: &Path, carrier_path: &Path, payload_path: &Path) -> PolyglotResult<()> {
+    let data = fs::read(polyglot_path)?;
+    let (carrier, payload) = split_polyglot_data(&data)?;
+
+    fs::write(carrier_path, carrier)?;
+    fs::write(payload_path, payload)?;
+    Ok(())
+}
+
+/// Split in-memory PNG-dominant polyglot data into a clean carrier PNG and its payload
+pub fn split_polyglot_data(data: &[u8]) -> PolyglotResult<(Vec<u8>, Vec<u8>)> {
+    let mut png = PngFile::from_data(data.to_vec())?;
+
+    // Text method: the payload lives in a dedicated tEXt chunk
+    let text_payload = png.parsed.chunks.iter()
+        .find(|chunk| chunk.chunk_type == *b"tEXt" && chunk.data.starts_with(b"ZIP Archive\0"))
+        .map(|chunk| chunk.data[b"ZIP Archive\0".len()..].to_vec());
+    if let Some(payload) = text_payload {
+        png.remove_text_chunk(b"ZIP Archive")?;
+        return Ok((png.raw_data, payload));
+    }
+
+    // IDAT method: the payload follows the image's zlib stream in the IDAT data, which
+    // `append_to_idat_with_limit` may have split over several chunks
+    let payload_start = png.compute_idat_zlib_end()?;
+    if payload_start == png.idat_data().len() {
+        return Err(PolyglotError::ValidationFailed("No embedded payload found in PNG polyglot".to_string()));
     }
+    let payload = png.strip_appended_idat_payload(payload_start)?;
+
+    Ok((png.raw_data, payload))
 }
 
 /// Extract embedded WAV data from a PNG+WAV or WAV+PNG polyglot file
 pub fn extract_wav_from_png(polyglot_path: &Path, output_path: &Path) -> PolyglotResult<()> {
     let data = fs::read(polyglot_path)?;
+    fs::write(output_path, &data[wav_range(&data)?])?;
 
-    if crate::utils::is_png_signature(&data) {
+    Ok(())
+}
+
+/// Extract embedded WAV data from in-memory PNG+WAV or WAV+PNG polyglot data
+pub fn extract_wav_from_png_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    Ok(data[wav_range(data)?].to_vec())
+}
+
+/// Byte range of the WAV in a PNG+WAV polyglot, or the whole file for a WAV+PNG one
+fn wav_range(data: &[u8]) -> PolyglotResult<Range<usize>> {
+    // Smaller than any RIFF header, and too short for the signature checks below
+    if data.len() < 12 {
+        return Err(PolyglotError::InvalidInput("file too short".to_string()));
+    }
+
+    if crate::utils::is_png_signature(data) {
         // PNG-dominant polyglot (PNG with embedded WAV) - find WAV within PNG
         let riff_start = match find_riff_signature(&data[8..]) { // Skip PNG signature
             Some(pos) => 8 + pos,
@@ -86,73 +536,382 @@ pub fn extract_wav_from_png(polyglot_path: &Path, output_path: &Path) -> Polyglo
         }
 
         // Extract only the WAV data (RIFF header + specified file size)
-        let wav_data = &data[riff_start..riff_start + total_wav_size];
-        fs::write(output_path, wav_data)?;
+        Ok(riff_start..riff_start + total_wav_size)
 
-    } else if &data[0..4] == b"RIFF" {
+    } else if data.starts_with(&RIFF_SIGNATURE) {
         // WAV-dominant polyglot (WAV with embedded PNG) - this IS the WAV file
         // Just copy the entire file as it's already a valid WAV
-        fs::write(output_path, &data)?;
+        Ok(0..data.len())
     } else {
-        return Err(PolyglotError::ValidationFailed(
+        Err(PolyglotError::ValidationFailed(
             "File is neither PNG nor WAV format".to_string()
-        ));
+        ))
     }
+}
+
+/// Extract embedded WAV data as a clean WAV, without the PNG chunks a WAV-dominant polyglot adds
+pub fn extract_clean_wav_from_png(polyglot_path: &Path, output_path: &Path) -> PolyglotResult<()> {
+    let data = fs::read(polyglot_path)?;
+    fs::write(output_path, extract_clean_wav_from_png_data(&data)?)?;
 
     Ok(())
 }
 
-/// Extract ZIP data from a PNG-dominant polyglot
-fn extract_zip_from_png_file(data: &[u8], output_path: &Path) -> PolyglotResult<()> {
+/// Extract embedded WAV data from in-memory polyglot data, stripping PNG chunks and restoring the RIFF size
+///
+/// `extract_wav_from_png_data` returns the WAV exactly as declared by its RIFF size, which for a
+/// WAV-dominant polyglot still includes the embedded PNG.
+pub fn extract_clean_wav_from_png_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    let mut wav = crate::wav::WavFile::from_data(extract_wav_from_png_data(data)?)?;
+    wav.strip_png_chunks()?;
+    Ok(wav.raw_data)
+}
+
+/// Rewrite the RIFF size of a WAV, or of a WAV embedded in a PNG chunk, to match its chunks
+///
+/// Returns `None` when the size is already correct. For a PNG the WAV's end is found by walking
+/// its chunks within the PNG chunk holding it, and that chunk's CRC is recomputed after the
+/// header is patched. A WAV that continues past that PNG chunk can't be repaired in place.
+pub fn repair_wav_riff_size(data: &[u8]) -> PolyglotResult<Option<Vec<u8>>> {
+    if data.starts_with(&RIFF_SIGNATURE) {
+        let mut wav = crate::wav::WavFile::from_data(data.to_vec())?;
+        return Ok(wav.fix_riff_size()?.then_some(wav.raw_data));
+    }
+
+    let png = PngFile::from_data(data.to_vec())?;
+    let riff_start = find_riff_signature(&data[8..])
+        .map(|pos| 8 + pos)
+        .ok_or_else(|| PolyglotError::ValidationFailed("No WAV signature found in PNG polyglot".to_string()))?;
+    let (chunk, _) = png.chunk_at_offset(riff_start)
+        .filter(|(chunk, _)| riff_start >= chunk.data_offset)
+        .ok_or_else(|| PolyglotError::ValidationFailed("WAV signature is not inside a PNG chunk".to_string()))?;
+    let chunk_end = chunk.data_offset + chunk.data.len();
+    let wav_len = crate::wav::riff_chunks_end(&data[riff_start..chunk_end])
+        .ok_or_else(|| PolyglotError::ValidationFailed(
+            "WAV data continues past the PNG chunk holding it; only a WAV stored whole in one chunk can be repaired".to_string()
+        ))?;
+
+    // Still has to parse as a WAV, whatever its stored size says
+    crate::wav::WavFile::from_data(data[riff_start..riff_start + wav_len].to_vec())?;
+    let actual = u32::try_from(wav_len - 8)
+        .map_err(|_| PolyglotError::WavParse("RIFF data exceeds 4 GB".to_string()))?;
+    if crate::utils::read_u32_le(data, riff_start + 4) == actual {
+        return Ok(None);
+    }
+
+    // Only the holding chunk's CRC is recomputed, so damage elsewhere still shows
+    let mut repaired = data.to_vec();
+    repaired[riff_start + 4..riff_start + 8].copy_from_slice(&actual.to_le_bytes());
+    let crc = crate::png::chunk_crc(&chunk.chunk_type, &repaired[chunk.data_offset..chunk_end]);
+    repaired[chunk_end..chunk_end + 4].copy_from_slice(&crc.to_be_bytes());
+    Ok(Some(repaired))
+}
+
+/// Extract the payload of a PNG-dominant polyglot from a stream that can't seek, e.g. a pipe
+///
+/// Chunks are parsed as they arrive and the payload is returned as soon as it is complete:
+/// a `ZIP Archive` tEXt chunk, or whatever follows the end of the IDAT zlib stream. Image
+/// data is inflated on the fly rather than buffered. ZIP-dominant and ZIP-comment layouts
+/// are located from the end of the file, so they can't be streamed and are rejected.
+#[cfg(feature = "compression")]
+pub fn extract_payload_from_reader<R: std::io::Read>(mut reader: R) -> PolyglotResult<Vec<u8>> {
+    let mut parser = crate::png::StreamParser::new();
+    let mut inflater = flate2::Decompress::new(true);
+    let mut inflate_buffer = vec![0u8; 64 * 1024];
+    let mut stream_ended = false;
+    let mut trailing = Vec::new(); // IDAT bytes after the end of the zlib stream
+    let mut read_buffer = vec![0u8; 64 * 1024];
+
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature)?;
+    if signature.starts_with(b"PK") {
+        return Err(PolyglotError::InvalidInput(
+            "streaming extraction needs a PNG-dominant polyglot; ZIP-dominant and ZIP-comment files are read from the end".to_string()
+        ));
+    }
+    parser.feed(&signature)?;
+
+    loop {
+        let read = reader.read(&mut read_buffer)?;
+        if read == 0 {
+            return Err(PolyglotError::ValidationFailed("stream ended before IEND".to_string()));
+        }
+
+        for chunk in parser.feed(&read_buffer[..read])? {
+            match &chunk.chunk_type {
+                b"tEXt" if chunk.data.starts_with(b"ZIP Archive\0") => {
+                    return Ok(chunk.data[b"ZIP Archive\0".len()..].to_vec());
+                }
+                t if *t == crate::png::PAYLOAD_CHUNK_TYPE => return Ok(chunk.data),
+                b"IDAT" if stream_ended => trailing.extend_from_slice(&chunk.data),
+                b"IDAT" => {
+                    let mut input = chunk.data.as_slice();
+                    while !input.is_empty() && !stream_ended {
+                        let before = inflater.total_in();
+                        let status = inflater.decompress(input, &mut inflate_buffer, flate2::FlushDecompress::None)?;
+                        input = &input[(inflater.total_in() - before) as usize..];
+                        stream_ended = status == flate2::Status::StreamEnd;
+                    }
+                    trailing.extend_from_slice(input);
+                }
+                // The IDAT run is over; anything after the zlib stream is the payload
+                _ if !trailing.is_empty() => return Ok(trailing),
+                b"IEND" => {
+                    return Err(PolyglotError::ValidationFailed("no embedded payload found before IEND".to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Stand-in for streaming extraction, which inflates IDAT data, without the `compression` feature
+#[cfg(not(feature = "compression"))]
+pub fn extract_payload_from_reader<R: std::io::Read>(_reader: R) -> PolyglotResult<Vec<u8>> {
+    Err(PolyglotError::feature_disabled("compression"))
+}
+
+/// Extract ZIP data from in-memory PNG-dominant polyglot data
+pub fn extract_zip_from_png_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    Ok(data[zip_range_in_png(data)?].to_vec())
+}
+
+/// Byte range of the ZIP in PNG-dominant polyglot data
+fn zip_range_in_png(data: &[u8]) -> PolyglotResult<Range<usize>> {
     // Find ZIP signature within the PNG
-    let zip_start = match find_zip_signature(&data[8..]) {
+    let zip_start = match data.get(8..).and_then(find_zip_signature) {
         Some(pos) => 8 + pos, // Skip PNG signature
         None => return Err(PolyglotError::ValidationFailed(
             "No ZIP signature found in PNG polyglot".to_string()
         )),
     };
 
-    // Find the ZIP EOCD to determine ZIP data end
+    // Find the ZIP EOCD to determine ZIP data end; scan forward, since the rest of the
+    // PNG can follow the archive by far more than the usual EOCD search window
     let zip_slice = &data[zip_start..];
-    if let Ok(eocd) = crate::zip::offsets::find_eocd(zip_slice) {
-        // Calculate ZIP end based on EOCD position
-        let eocd_pos_in_zip = (zip_slice.len() - 22) as usize; // EOCD is typically at the end
-        let zip_end = zip_start + eocd_pos_in_zip + 22; // Include the EOCD
+    if let Some(eocd_pos_in_zip) = crate::zip::offsets::find_embedded_eocd_offset(zip_slice, zip_start) {
+        // ZIP ends after the EOCD and its comment
+        let comment_length = u16::from_le_bytes([zip_slice[eocd_pos_in_zip + 20], zip_slice[eocd_pos_in_zip + 21]]);
+        let zip_end = eocd_pos_in_zip + 22 + comment_length as usize;
 
-        let zip_data = &data[zip_start..zip_end];
-        fs::write(output_path, zip_data)?;
+        Ok(zip_start..zip_start + zip_end)
     } else {
-        // If EOCD parsing fails, extract the rest of the file
-        let zip_data = &data[zip_start..];
-        fs::write(output_path, zip_data)?;
+        // If EOCD parsing fails, let the zip crate bound the archive before giving up
+        #[cfg(feature = "zip-crate-extract")]
+        if let Ok(range) = locate_zip_with_zip_crate(data) {
+            return Ok(range);
+        }
+
+        // Otherwise extract the rest of the file
+        Ok(zip_start..data.len())
     }
+}
 
-    Ok(())
+/// Extract ZIP data from in-memory polyglot data using the `zip` crate to locate it
+#[cfg(feature = "zip-crate-extract")]
+pub fn extract_zip_with_zip_crate(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    let range = locate_zip_with_zip_crate(data)?;
+    Ok(data[range].to_vec())
+}
+
+/// Byte range of the embedded ZIP as reported by the `zip` crate
+///
+/// The crate tolerates data before and after the archive, so the whole file is
+/// the candidate range. The archive runs from its first local header to the end
+/// of the EOCD comment.
+#[cfg(feature = "zip-crate-extract")]
+pub fn locate_zip_with_zip_crate(data: &[u8]) -> PolyglotResult<std::ops::Range<usize>> {
+    let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(data))?;
+    if archive.is_empty() {
+        return Err(PolyglotError::ValidationFailed("Embedded ZIP has no entries".to_string()));
+    }
+
+    let mut start = usize::MAX;
+    let mut last_central_header = 0;
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        start = start.min(entry.header_start() as usize);
+        last_central_header = last_central_header.max(entry.central_header_start() as usize);
+    }
+
+    // The crate reports the comment but not where the EOCD sits; it follows the last central header
+    let eocd_offset = data[last_central_header..].windows(4)
+        .position(|w| w == ZIP_EOCD_SIGNATURE)
+        .map(|pos| last_central_header + pos)
+        .ok_or_else(|| PolyglotError::ZipParse("EOCD record not found after central directory".to_string()))?;
+
+    Ok(start..eocd_offset + 22 + archive.comment().len())
 }
 
-/// Extract PNG from a ZIP-dominant polyglot (legacy function)
-fn extract_png_from_zip_file(data: &[u8], output_path: &Path) -> PolyglotResult<()> {
+/// Extract PNG from in-memory ZIP-dominant polyglot data
+pub fn extract_png_from_zip_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    Ok(data[embedded_png_range(data)?].to_vec())
+}
+
+/// Byte range of the first complete PNG in ZIP-dominant or WAV-dominant polyglot data
+fn embedded_png_range(data: &[u8]) -> PolyglotResult<Range<usize>> {
     // Find PNG signature within the ZIP
-    let png_sig = b"\x89PNG\r\n\x1A\n";
-    let png_start = match data.windows(8).position(|w| w == png_sig) {
+    let png_start = match data.windows(8).position(|w| w == PNG_SIGNATURE) {
         Some(pos) => pos,
         None => return Err(PolyglotError::ValidationFailed(
             "No PNG data found in ZIP polyglot".to_string()
         )),
     };
 
-    // Extract PNG data from the found position
-    let png_data = &data[png_start..];
-    fs::write(output_path, png_data)?;
+    // PNG ends after its last parsed chunk (IEND); sum chunk sizes to find it
+    let png_slice = &data[png_start..];
+    let parsed = crate::png::parser::parse_png_chunks(png_slice)?;
+    let png_len = 8 + parsed.chunks.iter().map(|chunk| 12 + chunk.data.len()).sum::<usize>();
 
-    Ok(())
+    Ok(png_start..png_start + png_len)
+}
+
+/// Extract the PNG stored in the EOCD comment of a ZIP-comment polyglot
+pub fn extract_png_from_zip_comment_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    Ok(data[png_range_in_zip_comment(data)?].to_vec())
+}
+
+/// Byte range of the PNG in the EOCD comment of a ZIP-comment polyglot
+fn png_range_in_zip_comment(data: &[u8]) -> PolyglotResult<Range<usize>> {
+    // The comment runs to the end of the file, so prefer an EOCD at the very start
+    let eocd_offset = if data.len() >= 22
+        && data.starts_with(&ZIP_EOCD_SIGNATURE)
+        && u16::from_le_bytes([data[20], data[21]]) as usize == data.len() - 22 {
+        0
+    } else {
+        crate::zip::offsets::find_eocd_offset(data)?
+    };
+
+    let comment_length = u16::from_le_bytes([data[eocd_offset + 20], data[eocd_offset + 21]]) as usize;
+    let comment = eocd_offset + 22..eocd_offset + 22 + comment_length;
+    if !crate::utils::is_png_signature(&data[comment.clone()]) {
+        return Err(PolyglotError::ValidationFailed("ZIP comment does not contain a PNG".to_string()));
+    }
+
+    Ok(comment)
+}
+
+/// Extract the PNG carried in the private `plZP` chunk of a PNG-in-PNG polyglot
+///
+/// The inner image is parsed down to IEND before it is returned, so a damaged payload is
+/// an error rather than a file no viewer opens.
+pub fn extract_png_from_png_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    let carrier = PngFile::from_data(data.to_vec())?;
+    let inner = carrier.payload_chunk()
+        .ok_or_else(|| PolyglotError::ValidationFailed("no plZP chunk in PNG".to_string()))?;
+    if !crate::utils::is_png_signature(inner) {
+        return Err(PolyglotError::ValidationFailed("plZP chunk does not contain a PNG".to_string()));
+    }
+    let parsed = crate::png::parser::parse_png_chunks(inner)?;
+    if parsed.chunks.last().is_none_or(|chunk| chunk.chunk_type != *b"IEND") {
+        return Err(PolyglotError::ValidationFailed("PNG in plZP chunk is truncated before IEND".to_string()));
+    }
+
+    Ok(inner.to_vec())
+}
+
+/// Reassemble a payload split between the IDAT data and the bytes after IEND
+///
+/// Returns `None` when the file isn't framed as a split payload: the zlib stream can't be
+/// walked (e.g. without the `compression` feature), the IDAT data past it doesn't start
+/// with a `plSP` head, or no `plST` tail follows IEND. When both markers are present but
+/// their lengths don't agree, that's an error.
+pub fn extract_split_payload(data: &[u8]) -> PolyglotResult<Option<Vec<u8>>> {
+    use crate::polyglot::{SPLIT_HEAD_MAGIC, SPLIT_TAIL_MAGIC};
+
+    let png = PngFile::from_data(data.to_vec())?;
+    let idat = png.idat_data();
+    let Ok(zlib_end) = png.compute_idat_zlib_end() else {
+        return Ok(None);
+    };
+    let Some(frame) = idat[zlib_end..].strip_prefix(SPLIT_HEAD_MAGIC.as_slice()) else {
+        return Ok(None);
+    };
+    let Some(tail) = png.parsed.chunks.last()
+        .filter(|chunk| chunk.chunk_type == *b"IEND")
+        .and_then(|chunk| data[chunk.end_offset()..].strip_prefix(SPLIT_TAIL_MAGIC.as_slice()))
+    else {
+        return Ok(None);
+    };
+
+    if frame.len() < 8 {
+        return Err(PolyglotError::ValidationFailed("split payload header is truncated".to_string()));
+    }
+    let total_len = u32::from_be_bytes(frame[0..4].try_into().unwrap()) as usize;
+    let head_len = u32::from_be_bytes(frame[4..8].try_into().unwrap()) as usize;
+    if head_len > total_len || frame.len() != 8 + head_len {
+        return Err(PolyglotError::ValidationFailed(format!(
+            "split payload head should hold {} of {} bytes but IDAT has {} after the header",
+            head_len, total_len, frame.len() - 8
+        )));
+    }
+    if tail.len() != total_len - head_len {
+        return Err(PolyglotError::ValidationFailed(format!(
+            "split payload tail has {} bytes, expected {}", tail.len(), total_len - head_len
+        )));
+    }
+
+    Ok(Some([&frame[8..], tail].concat()))
+}
+
+/// Extract every entry of a ZIP archive into `out_dir`, returning the paths written
+///
+/// Entry names are sanitized first, so nothing is written outside `out_dir`. On Unix each
+/// file gets the permission bits stored with its entry (`DEFAULT_ENTRY_MODE` if none),
+/// without setuid, setgid or sticky bits.
+pub fn unzip_all(zip_data: &[u8], out_dir: &Path) -> PolyglotResult<Vec<PathBuf>> {
+    let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(zip_data))?;
+    let mut written = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let Some(relative_path) = sanitize_entry_name(entry.name()) else {
+            continue; // Nothing left of the name once traversal is stripped
+        };
+        let path = out_dir.join(relative_path);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(&path)?;
+        std::io::copy(&mut entry, &mut file)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = entry.unix_mode().unwrap_or(crate::zip::DEFAULT_ENTRY_MODE) & 0o777;
+            file.set_permissions(fs::Permissions::from_mode(mode))?;
+        }
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+/// Reduce a ZIP entry name to a relative path that cannot escape the extraction directory
+///
+/// Root, drive prefixes, `.` and `..` components are dropped; backslashes count as separators.
+pub fn sanitize_entry_name(name: &str) -> Option<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let path: PathBuf = Path::new(&normalized)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+
+    if path.as_os_str().is_empty() { None } else { Some(path) }
 }
 
 /// Validate data as ZIP format
 fn validate_as_zip(data: &[u8]) -> PolyglotResult<()> {
     // Check signature
-    if data.len() < 4 || data[0..4] != [0x50, 0x4B, 0x03, 0x04] {
-        return Err(PolyglotError::ValidationFailed("Invalid ZIP signature".to_string()));
+    if !data.starts_with(&ZIP_LOCAL_SIGNATURE) {
+        return Err(PolyglotError::not_zip(data));
     }
 
     // Try to parse as ZIP
@@ -172,8 +931,7 @@ fn validate_png_within_zip(data: &[u8]) -> PolyglotResult<()> {
 
     // Try to find the PNG data within the ZIP structure
     // Since we're using a simple ZIP format, look for PNG signature after local header
-    let png_sig = b"\x89PNG\r\n\x1A\n";
-    if let Some(pos) = data.windows(8).position(|w| w == png_sig) {
+    if let Some(pos) = data.windows(8).position(|w| w == PNG_SIGNATURE) {
         // Found PNG signature, try to validate it
         let png_data = &data[pos..];
         crate::png::parser::parse_png_chunks(png_data)?;
@@ -221,28 +979,280 @@ fn validate_zip_within_png(data: &[u8]) -> PolyglotResult<()> {
 
 /// Find ZIP signature (PK\x03\x04) in data, returning offset
 fn find_zip_signature(data: &[u8]) -> Option<usize> {
-    const ZIP_SIG: [u8; 4] = [0x50, 0x4B, 0x03, 0x04]; // PK\x03\x04
-    data.windows(4).position(|w| w == ZIP_SIG)
+    data.windows(4).position(|w| w == ZIP_LOCAL_SIGNATURE)
 }
 
 /// Find RIFF signature ("RIFF") in data, returning offset
 fn find_riff_signature(data: &[u8]) -> Option<usize> {
-    const RIFF_SIG: [u8; 4] = *b"RIFF";
-    data.windows(4).position(|w| w == RIFF_SIG)
+    data.windows(4).position(|w| w == RIFF_SIGNATURE)
 }
 
 /// Find ZIP64 EOCD signature in data, returning offset
 fn find_zip64_eocd(data: &[u8]) -> Option<usize> {
-    const ZIP64_EOCD_SIG: [u8; 4] = [0x50, 0x4B, 0x06, 0x06];
-    data.windows(4).position(|w| w == ZIP64_EOCD_SIG)
+    data.windows(4).position(|w| w == ZIP64_EOCD_SIGNATURE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::png::parser::Chunk;
+    use crate::testutil::minimal_zip;
     use tempfile::NamedTempFile;
     use std::io::Write;
 
+    #[test]
+    fn test_extract_from_gif_and_flac_carriers() {
+        use crate::testutil::{minimal_flac, minimal_gif, minimal_png};
+
+        let mut gif = crate::gif::GifFile::from_data(minimal_gif()).unwrap();
+        gif.add_zip_comment_extension(&minimal_zip()).unwrap();
+        assert_eq!(extract_from_carrier(gif.as_bytes()).unwrap(), (FileFormat::Zip, minimal_zip()));
+
+        let mut app_gif = crate::gif::GifFile::from_data(minimal_gif()).unwrap();
+        app_gif.add_zip_application_extension(b"POLYGLOT1.0", &minimal_zip()).unwrap();
+        assert_eq!(extract_from_carrier(app_gif.as_bytes()).unwrap(), (FileFormat::Zip, minimal_zip()));
+
+        // Grow the PADDING block so the PNG fits, leaving zeros after its IEND
+        let png = minimal_png();
+        let padding_len = png.len() + 16;
+        let mut flac_data = minimal_flac();
+        let padding_header = 4 + 4 + 34; // After fLaC and the STREAMINFO block
+        flac_data[padding_header + 1..padding_header + 4].copy_from_slice(&(padding_len as u32).to_be_bytes()[1..]);
+        flac_data.splice(padding_header + 4..padding_header + 12, vec![0u8; padding_len]);
+        let mut flac = crate::flac::FlacFile::from_data(flac_data).unwrap();
+        flac.inject_png_to_padding(&png).unwrap();
+        assert_eq!(extract_from_carrier(flac.as_bytes()).unwrap(), (FileFormat::Png, png));
+
+        assert!(extract_from_carrier(&minimal_gif()).is_err());
+        assert!(extract_from_carrier(&minimal_flac()).is_err());
+    }
+
+    #[test]
+    fn test_quick_classify_headers() {
+        use crate::testutil::{minimal_pdf, minimal_png, minimal_wav};
+
+        assert_eq!(quick_classify(&minimal_png()), QuickClass::DefinitelyPng);
+        assert_eq!(quick_classify(&minimal_zip()), QuickClass::DefinitelyZip);
+        assert_eq!(quick_classify(&minimal_wav()), QuickClass::RiffWav);
+        assert_eq!(quick_classify(b"GIF89a"), QuickClass::Unknown);
+        assert_eq!(quick_classify(&[]), QuickClass::Unknown);
+
+        // Empty ZIP whose comment is a PNG
+        let comment_polyglot = crate::polyglot::build_zip_comment_polyglot(&minimal_png()).unwrap();
+        assert_eq!(quick_classify(&comment_polyglot), QuickClass::PossiblePolyglot);
+
+        // Triple polyglot: the PDF chunk follows IHDR
+        let triple = crate::polyglot::build_triple_polyglot(&minimal_png(), &minimal_zip(), &minimal_pdf()).unwrap();
+        assert_eq!(quick_classify(&triple[..QUICK_CLASSIFY_LEN]), QuickClass::PossiblePolyglot);
+
+        // Signatures past the first 64 bytes are ignored
+        let mut png = minimal_png();
+        png.resize(QUICK_CLASSIFY_LEN, 0);
+        png.extend_from_slice(b"PK\x03\x04");
+        assert_eq!(quick_classify(&png), QuickClass::DefinitelyPng);
+    }
+
+    #[test]
+    fn test_locate_anomaly() {
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(crate::testutil::minimal_png(), minimal_zip()).unwrap();
+        let mut polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap();
+        assert_eq!(locate_anomaly(&polyglot), None);
+
+        polyglot[29] ^= 0xFF; // IHDR CRC
+        assert_eq!(locate_anomaly(&polyglot), Some((29, "bad CRC in IHDR chunk".to_string())));
+
+        let png = crate::testutil::minimal_png();
+        assert_eq!(locate_anomaly(&png), Some((png.len() - 1, "ZIP end of central directory record not found".to_string())));
+    }
+
+    #[test]
+    fn test_byte_swapped_chunk_length_is_flagged() {
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(crate::testutil::minimal_png(), minimal_zip()).unwrap();
+        let mut polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap();
+        assert_eq!(suggest_swapped_chunk_length(&polyglot, 8), None);
+
+        polyglot[8..12].copy_from_slice(&13u32.to_le_bytes()); // IHDR length written little-endian
+        assert_eq!(suggest_swapped_chunk_length(&polyglot, 8), Some(13));
+
+        let (offset, description) = locate_anomaly(&polyglot).unwrap();
+        assert_eq!(offset, 8);
+        assert_eq!(description, "IHDR chunk is truncated - length 218103808 looks byte-swapped (written little-endian?), 13 would fit and matches the CRC");
+    }
+
+    /// Read-only source handing out a few bytes per call, like a pipe
+    #[cfg(feature = "compression")]
+    struct Trickle<'a>(&'a [u8]);
+
+    #[cfg(feature = "compression")]
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let count = buf.len().min(self.0.len()).min(7);
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            Ok(count)
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_extract_text_payload_from_pipe() {
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(crate::testutil::minimal_png(), minimal_zip()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap();
+
+        assert_eq!(extract_payload_from_reader(Trickle(&polyglot)).unwrap(), minimal_zip());
+
+        // IDAT payloads follow the end of the zlib stream, which needs a carrier with real image data
+        let mut carrier = Vec::new();
+        let mut encoder = ::png::Encoder::new(&mut carrier, 2, 2);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.write_header().unwrap().write_image_data(&[0x40; 12]).unwrap();
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(carrier, minimal_zip()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+        assert_eq!(extract_payload_from_reader(Trickle(&polyglot)).unwrap(), creator.zip().as_bytes());
+
+        let comment_polyglot = crate::polyglot::build_zip_comment_polyglot(&crate::testutil::minimal_png()).unwrap();
+        let result = extract_payload_from_reader(Trickle(&comment_polyglot));
+        assert!(matches!(result, Err(PolyglotError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_trailing_bytes_after_eocd_are_counted() {
+        use crate::polyglot::PolyglotCreator;
+        use crate::testutil::minimal_png;
+
+        let mut zip_dominant = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap()
+            .create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).unwrap();
+        assert_eq!(trailing_bytes_after_eocd(&zip_dominant), Some(0));
+        zip_dominant.extend_from_slice(&[0xAA; 10]);
+        assert_eq!(trailing_bytes_after_eocd(&zip_dominant), Some(10));
+
+        // IEND after a ZIP in tEXt is the PNG's own structure, not trailing data
+        let text = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap()
+            .create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap();
+        assert_eq!(trailing_bytes_after_eocd(&text), Some(0));
+        assert_eq!(trailing_bytes_after_eocd(&minimal_png()), None);
+    }
+
+    #[test]
+    fn test_verify_embedded_zip_offsets() {
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(crate::testutil::minimal_png(), minimal_zip()).unwrap();
+        let mut polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+        verify_embedded_zip_offsets(&polyglot).unwrap();
+
+        // Point the only entry one byte past its local header
+        let eocd = offsets::find_eocd(&polyglot).unwrap();
+        let local_offset_pos = eocd.cd_offset as usize + 42;
+        let local_offset = crate::utils::read_u32_le(&polyglot, local_offset_pos);
+        crate::utils::write_u32_le(&mut polyglot, local_offset_pos, local_offset + 1);
+
+        let result = verify_embedded_zip_offsets(&polyglot);
+        assert!(matches!(result, Err(PolyglotError::ValidationFailed(ref msg)) if msg.starts_with("entry 0 local header offset")));
+    }
+
+    #[test]
+    fn test_indexed_payloads_skip_scanning() {
+        use crate::polyglot::{EmbeddingMethod, MultiPayloadBuilder};
+        use crate::testutil::{minimal_pdf, minimal_png, minimal_wav};
+
+        let build = |write_index: bool| {
+            let mut builder = MultiPayloadBuilder::new(PngFile::from_data(minimal_png()).unwrap());
+            builder.add_payload(FileFormat::Wav, EmbeddingMethod::Idat, minimal_wav()).unwrap();
+            builder.add_payload(FileFormat::Zip, EmbeddingMethod::Text, minimal_zip()).unwrap();
+            builder.add_named_payload("report.pdf", minimal_pdf()).unwrap();
+            builder.set_write_index(write_index);
+            builder.build().unwrap()
+        };
+
+        let polyglot = build(true);
+        PngFile::from_data(polyglot.clone()).unwrap(); // Index chunk keeps the PNG valid
+        let scans_before = SIGNATURE_SCANS.with(|scans| scans.get());
+        let payloads = extract_payloads(&polyglot).unwrap();
+        assert_eq!(SIGNATURE_SCANS.with(|scans| scans.get()), scans_before);
+
+        let summary: Vec<_> = payloads.iter().map(|(entry, data)| (entry.name.as_str(), entry.format, data.clone())).collect();
+        assert_eq!(summary, vec![
+            ("idat", FileFormat::Wav, minimal_wav()),
+            ("text", FileFormat::Zip, minimal_zip()),
+            ("report.pdf", FileFormat::Pdf, minimal_pdf()),
+        ]);
+        for (entry, data) in &payloads {
+            assert_eq!(&polyglot[entry.offset as usize..][..data.len()], data.as_slice());
+        }
+
+        // Without the index, extraction has to scan
+        let unindexed = build(false);
+        assert_eq!(read_trailing_index(&unindexed).unwrap(), None);
+        extract_payloads(&unindexed).unwrap();
+        assert_eq!(SIGNATURE_SCANS.with(|scans| scans.get()), scans_before + 1);
+    }
+
+    #[test]
+    fn test_extract_payload_at_index() {
+        use crate::polyglot::{EmbeddingMethod, MultiPayloadBuilder};
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let mut builder = MultiPayloadBuilder::new(PngFile::from_data(minimal_png()).unwrap());
+        builder.add_payload(FileFormat::Zip, EmbeddingMethod::Text, minimal_zip()).unwrap();
+        builder.add_payload(FileFormat::Wav, EmbeddingMethod::Idat, minimal_wav()).unwrap();
+        let polyglot = builder.build().unwrap();
+
+        // The WAV in the image data comes before the ZIP in the tEXt chunk
+        let order: Vec<_> = detect_payloads(&polyglot).iter().map(|payload| payload.format).collect();
+        assert_eq!(order, ["wav", "zip"]);
+        let (entry, payload) = extract_payload_at(&polyglot, 1).unwrap();
+        assert_eq!((entry.format, payload), (FileFormat::Zip, minimal_zip()));
+        assert_eq!(extract_payload_at(&polyglot, 0).unwrap().1, minimal_wav());
+
+        match extract_payload_at(&polyglot, 2) {
+            Err(PolyglotError::ValidationFailed(message)) => assert_eq!(message, "payload index 2 is out of range; found 2 payload(s)"),
+            other => panic!("expected an out of range error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_extract_payload_at_counts_png_payloads() {
+        use crate::testutil::minimal_png;
+
+        // A PNG in a plZP chunk, then a ZIP appended after IEND
+        let mut carrier = PngFile::from_data(minimal_png()).unwrap();
+        carrier.add_payload_chunk(&minimal_png()).unwrap();
+        let polyglot = [carrier.as_bytes(), minimal_zip().as_slice()].concat();
+
+        let order: Vec<_> = detect_payloads(&polyglot).iter().map(|payload| payload.format).collect();
+        assert_eq!(order, ["png", "zip"]);
+        let (entry, payload) = extract_payload_at(&polyglot, 0).unwrap();
+        assert_eq!((entry.format, payload), (FileFormat::Png, minimal_png()));
+        let (entry, payload) = extract_payload_at(&polyglot, 1).unwrap();
+        assert_eq!((entry.format, payload), (FileFormat::Zip, minimal_zip()));
+        assert!(extract_payload_at(&polyglot, 2).is_err());
+    }
+
+    #[test]
+    fn test_zip_followed_by_many_idats_is_bounded_by_its_eocd() {
+        // 16 more 8 KB IDATs after the first put the EOCD well outside a 64 KB tail search
+        let mut carrier = crate::testutil::minimal_png();
+        let iend = carrier.split_off(carrier.len() - 12);
+        for _ in 0..16 {
+            let chunk_data = vec![0u8; 8 * 1024];
+            carrier.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+            carrier.extend_from_slice(b"IDAT");
+            carrier.extend_from_slice(&chunk_data);
+            let crc = crate::utils::calculate_crc32(&[b"IDAT".as_slice(), &chunk_data].concat());
+            carrier.extend_from_slice(&crc.to_be_bytes());
+        }
+        carrier.extend_from_slice(&iend);
+
+        for method in [EmbeddingMethod::Idat, EmbeddingMethod::Text] {
+            let mut creator = crate::polyglot::PolyglotCreator::from_data(carrier.clone(), minimal_zip()).unwrap();
+            let polyglot = creator.create_polyglot_in_memory_with_method(method).unwrap();
+            let zip_data = extract_zip_from_png_data(&polyglot).unwrap();
+            assert_eq!(zip_data.len(), minimal_zip().len(), "{method}");
+        }
+    }
+
+    /// IDAT data of the carrier in `create_test_polyglot`, before the ZIP is appended
+    const CARRIER_IDAT: [u8; 12] = [0x78, 0xDA, 0x63, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01];
+
     fn create_test_polyglot() -> Vec<u8> {
         // Create PNG
         let mut png = vec![
@@ -258,13 +1268,11 @@ mod tests {
         let ihdr_crc = crate::utils::calculate_crc32(&[b"IHDR".as_slice(), &ihdr_data].concat());
         png.extend_from_slice(&ihdr_crc.to_be_bytes());
 
-        // IDAT chunk with minimal data + ZIP
-        let mut idat_data = vec![
-            0x78, 0x9C, 0xED, 0xC1, 0x01, 0x01, 0x00, 0x00, 0x00, 0x80, 0x90, 0xFE, 0x37, 0x10
-        ];
+        // IDAT chunk with the image data + ZIP
+        let mut idat_data = CARRIER_IDAT.to_vec();
 
         // Append ZIP data
-        let zip_data = create_test_zip();
+        let zip_data = minimal_zip();
         idat_data.extend_from_slice(&zip_data);
 
         let idat_length = idat_data.len() as u32;
@@ -283,55 +1291,11 @@ mod tests {
         png
     }
 
-    fn create_test_zip() -> Vec<u8> {
-        let mut zip = vec![0x50, 0x4B, 0x03, 0x04]; // LFHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(b"test"); // Filename
-
-        // Central directory header
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x01, 0x02]); // CDHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version made by
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // File comment length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Internal attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // External attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Local header offset
-        zip.extend_from_slice(b"test"); // Filename
-
-        // End of central directory
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x05, 0x06]); // EOCDS
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // CD disk number
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Entries on this disk
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Total entries
-        zip.extend_from_slice(&vec![0x16, 0x00, 0x00, 0x00]); // CD size
-        zip.extend_from_slice(&vec![0x1A, 0x00, 0x00, 0x00]); // CD offset
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Comment length
-
-        zip
-    }
 
     #[test]
     fn test_extract_zip_from_polyglot() {
         let polyglot_data = create_test_polyglot();
-        let expected_zip = create_test_zip();
+        let expected_zip = minimal_zip();
 
         // Write polyglot to temp file
         let mut polyglot_file = NamedTempFile::new().unwrap();
@@ -354,6 +1318,348 @@ mod tests {
         assert_eq!(extracted_data, expected_zip);
     }
 
+    #[test]
+    fn test_detect_embedding_method() {
+        use crate::testutil::{minimal_png, minimal_wav, minimal_pdf};
+
+        for method in [EmbeddingMethod::Text, EmbeddingMethod::Chunk, EmbeddingMethod::Idat, EmbeddingMethod::Zip] {
+            let mut creator = crate::polyglot::PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+            let polyglot = creator.create_polyglot_in_memory_with_method(method).unwrap();
+            assert_eq!(detect_embedding_method(&polyglot), Some(method), "{}", method.name());
+        }
+
+        let comment = crate::polyglot::build_zip_comment_polyglot(&minimal_png()).unwrap();
+        assert_eq!(detect_embedding_method(&comment), Some(EmbeddingMethod::Comment));
+
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        png.append_wav_to_idat(&minimal_wav()).unwrap();
+        assert_eq!(detect_embedding_method(png.as_bytes()), Some(EmbeddingMethod::Idat));
+
+        let creator = crate::polyglot::TrueBidirectionalPngWavCreator::from_data(minimal_png(), minimal_wav()).unwrap();
+        assert_eq!(detect_embedding_method(&creator.build_bidirectional_polyglot()), Some(EmbeddingMethod::Bidirectional));
+
+        // ZIP after IEND and files with no payload match no method
+        let triple = crate::polyglot::build_triple_polyglot(&minimal_png(), &minimal_zip(), &minimal_pdf()).unwrap();
+        assert_eq!(detect_embedding_method(&triple), None);
+        assert_eq!(detect_embedding_method(&minimal_png()), None);
+        assert_eq!(detect_embedding_method(&minimal_zip()), None);
+    }
+
+    #[test]
+    fn test_repair_wav_riff_size() {
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let wav = minimal_wav();
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        png.append_wav_to_idat(&wav).unwrap();
+        let riff_start = 8 + find_riff_signature(&png.raw_data[8..]).unwrap();
+
+        for bad_size in [4, wav.len() as u32 * 2] {
+            let mut corrupted = png.clone();
+            corrupted.raw_data[riff_start + 4..riff_start + 8].copy_from_slice(&bad_size.to_le_bytes());
+            corrupted.recalculate_crcs().unwrap();
+            assert_ne!(extract_wav_from_png_data(&corrupted.raw_data).ok(), Some(wav.clone()));
+
+            let repaired = repair_wav_riff_size(&corrupted.raw_data).unwrap().unwrap();
+            assert_eq!(extract_wav_from_png_data(&repaired).unwrap(), wav);
+
+            // Only the RIFF size and the CRC of the chunk holding it change
+            let holder = corrupted.chunk_at_offset(riff_start).unwrap().0.end_offset();
+            let mut changed = (0..repaired.len()).filter(|&i| repaired[i] != corrupted.raw_data[i]);
+            assert!(changed.all(|i| (riff_start + 4..riff_start + 8).contains(&i) || (holder - 4..holder).contains(&i)));
+            assert_eq!(locate_anomaly(&repaired).map(|(_, description)| description), Some("ZIP end of central directory record not found".to_string()));
+            assert_eq!(repair_wav_riff_size(&repaired).unwrap(), None);
+        }
+
+        // A standalone WAV is rewritten in place
+        let mut standalone = wav.clone();
+        standalone[4..8].copy_from_slice(&1000u32.to_le_bytes());
+        assert_eq!(repair_wav_riff_size(&standalone).unwrap(), Some(wav.clone()));
+    }
+
+    #[test]
+    fn test_repair_wav_riff_size_walks_wav_chunks() {
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let wav = minimal_wav();
+        let mut corrupted = wav.clone();
+        corrupted[4..8].copy_from_slice(&4u32.to_le_bytes());
+
+        // Bytes after the WAV in the same PNG chunk aren't counted as WAV data
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        png.add_payload_chunk(&[corrupted.as_slice(), &[0u8; 6]].concat()).unwrap();
+        let repaired = repair_wav_riff_size(png.as_bytes()).unwrap().unwrap();
+        assert_eq!(extract_wav_from_png_data(&repaired).unwrap(), wav);
+
+        // A WAV split across two PNG chunks can't be rewritten in place
+        let half = wav.len() / 2;
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        png.insert_chunk_before_iend(b"wvAa", &corrupted[..half]).unwrap();
+        png.insert_chunk_before_iend(b"wvAb", &corrupted[half..]).unwrap();
+        assert!(matches!(repair_wav_riff_size(png.as_bytes()), Err(PolyglotError::ValidationFailed(_))));
+    }
+
+    #[test]
+    fn test_detect_payloads() {
+        let polyglot_data = create_test_polyglot();
+        let zip_data = minimal_zip();
+
+        let payloads = detect_payloads(&polyglot_data);
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].format, "zip");
+
+        let offset = payloads[0].offset;
+        assert_eq!(&polyglot_data[offset..offset + zip_data.len()], zip_data.as_slice());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_split_polyglot_outputs() {
+        let polyglot_data = create_test_polyglot();
+        let zip_data = minimal_zip();
+
+        let mut polyglot_file = NamedTempFile::new().unwrap();
+        polyglot_file.write_all(&polyglot_data).unwrap();
+        let carrier_file = NamedTempFile::new().unwrap();
+        let payload_file = NamedTempFile::new().unwrap();
+
+        split_polyglot(polyglot_file.path(), carrier_file.path(), payload_file.path()).unwrap();
+
+        // Carrier is a plain PNG again, with no embedded archive
+        let carrier = fs::read(carrier_file.path()).unwrap();
+        validate_as_png(&carrier).unwrap();
+        assert!(find_zip_signature(&carrier).is_none());
+
+        // Payload matches the original archive
+        assert_eq!(fs::read(payload_file.path()).unwrap(), zip_data);
+    }
+
+    #[test]
+    fn test_split_text_polyglot_restores_carrier() {
+        let mut png = PngFile::from_data(create_test_polyglot()).unwrap();
+        let payload = png.strip_appended_idat_payload(CARRIER_IDAT.len()).unwrap();
+        assert_eq!(payload, minimal_zip());
+        let carrier = png.raw_data.clone();
+
+        png.add_zip_text_chunk(&minimal_zip()).unwrap();
+        let (split_carrier, payload) = split_polyglot_data(png.as_bytes()).unwrap();
+
+        assert_eq!(split_carrier, carrier);
+        assert_eq!(payload, minimal_zip());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_split_multi_idat_carrier() {
+        let mut png = PngFile::from_data(create_test_polyglot()).unwrap();
+        png.strip_appended_idat_payload(CARRIER_IDAT.len()).unwrap();
+
+        // The image data spread over two IDAT chunks, with nothing appended
+        let mut chunks = Vec::new();
+        for chunk in &png.parsed.chunks {
+            if chunk.chunk_type == *b"IDAT" {
+                let (head, tail) = chunk.data.split_at(5);
+                chunks.push(Chunk { data: head.to_vec(), ..chunk.clone() });
+                chunks.push(Chunk { data: tail.to_vec(), ..chunk.clone() });
+            } else {
+                chunks.push(chunk.clone());
+            }
+        }
+        png.rebuild_from_chunks(&chunks).unwrap();
+        let carrier = png.raw_data.clone();
+        let result = split_polyglot_data(&carrier);
+        assert!(matches!(result, Err(PolyglotError::ValidationFailed(ref msg)) if msg.contains("No embedded payload")));
+
+        // A payload in an IDAT chunk of its own is dropped whole
+        let iend = chunks.len() - 1;
+        let idat = chunks.iter().find(|chunk| chunk.chunk_type == *b"IDAT").unwrap().clone();
+        chunks.insert(iend, Chunk { data: minimal_zip(), ..idat });
+        png.rebuild_from_chunks(&chunks).unwrap();
+        let (split_carrier, payload) = split_polyglot_data(png.as_bytes()).unwrap();
+        assert_eq!(split_carrier, carrier);
+        assert_eq!(payload, minimal_zip());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_split_polyglot_with_small_idat_limit() {
+        let mut png = PngFile::from_data(create_test_polyglot()).unwrap();
+        png.strip_appended_idat_payload(CARRIER_IDAT.len()).unwrap();
+
+        // The image data is split into pieces of 4 bytes and the payload lands in the last one
+        png.append_to_idat_with_limit(&minimal_zip(), 4).unwrap();
+        assert_eq!(crate::png::parser::find_all_idat(&png.parsed).len(), 3);
+
+        let (carrier, payload) = split_polyglot_data(png.as_bytes()).unwrap();
+        assert_eq!(payload, minimal_zip());
+        let carrier = PngFile::from_data(carrier).unwrap();
+        assert_eq!(carrier.idat_data(), CARRIER_IDAT);
+    }
+
+    #[test]
+    fn test_unzip_all_neutralizes_traversal() {
+        use ::zip::write::SimpleFileOptions;
+
+        let mut writer = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file("../../evil", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"escaped?").unwrap();
+        writer.start_file("/abs/path.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"absolute").unwrap();
+        writer.start_file("docs/readme.txt", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"fine").unwrap();
+        let zip_data = writer.finish().unwrap().into_inner();
+
+        let root = tempfile::TempDir::new().unwrap();
+        let out_dir = root.path().join("a").join("b");
+        let written = unzip_all(&zip_data, &out_dir).unwrap();
+
+        // Everything lands inside out_dir, traversal stripped
+        assert_eq!(written.len(), 3);
+        assert!(written.iter().all(|path| path.starts_with(&out_dir)));
+        assert_eq!(fs::read(out_dir.join("evil")).unwrap(), b"escaped?");
+        assert_eq!(fs::read(out_dir.join("abs/path.txt")).unwrap(), b"absolute");
+        assert_eq!(fs::read(out_dir.join("docs/readme.txt")).unwrap(), b"fine");
+        assert!(!root.path().join("evil").exists());
+
+        assert_eq!(sanitize_entry_name("..\\..\\evil"), Some(PathBuf::from("evil")));
+        assert_eq!(sanitize_entry_name("../.."), None);
+    }
+
+    #[cfg(feature = "zip-crate-extract")]
+    #[test]
+    fn test_zip_crate_path_ignores_decoy_signature() {
+        use ::zip::write::SimpleFileOptions;
+
+        let options = SimpleFileOptions::default().compression_method(::zip::CompressionMethod::Deflated);
+        let mut writer = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file("a.txt", options).unwrap();
+        writer.write_all(&b"compressible ".repeat(50)).unwrap();
+        writer.start_file("b.txt", options).unwrap();
+        writer.write_all(b"second").unwrap();
+        writer.set_comment("archive comment");
+        let zip_data = writer.finish().unwrap().into_inner();
+
+        // A tEXt chunk with a stray local header signature comes before the real archive
+        let mut png = PngFile::from_data(crate::testutil::minimal_png()).unwrap();
+        png.insert_chunk_after_ihdr(b"tEXt", b"Decoy\0PK\x03\x04").unwrap();
+        png.add_zip_text_chunk(&zip_data).unwrap();
+
+        // The decoy start is rejected, so both paths land on the real archive
+        let hand_rolled = extract_zip_from_png_data(&png.raw_data).unwrap();
+        let zip_crate = extract_zip_with_zip_crate(&png.raw_data).unwrap();
+        assert_eq!(hand_rolled, zip_data);
+        assert_eq!(zip_crate, zip_data);
+
+        // Without the decoy both paths agree
+        let mut plain = PngFile::from_data(crate::testutil::minimal_png()).unwrap();
+        plain.add_zip_text_chunk(&zip_data).unwrap();
+        assert_eq!(extract_zip_from_png_data(&plain.raw_data).unwrap(), extract_zip_with_zip_crate(&plain.raw_data).unwrap());
+    }
+
+    #[test]
+    fn test_clean_wav_extraction_strips_png_chunk() {
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let mut wav = crate::wav::WavFile::from_data(minimal_wav()).unwrap();
+        wav.embed_png_data(&minimal_png()).unwrap();
+        let polyglot = wav.raw_data;
+
+        // Raw extraction keeps the PNG chunk; clean extraction is the original WAV
+        assert_eq!(extract_wav_from_png_data(&polyglot).unwrap(), polyglot);
+        let clean = extract_clean_wav_from_png_data(&polyglot).unwrap();
+        assert!(!clean.windows(4).any(|w| w == b"pnG "));
+        assert_eq!(u32::from_le_bytes(clean[4..8].try_into().unwrap()) as usize, clean.len() - 8);
+        assert_eq!(clean, minimal_wav());
+    }
+
+    #[test]
+    fn test_wav_extraction_rejects_tiny_file() {
+        let mut tiny_file = NamedTempFile::new().unwrap();
+        tiny_file.write_all(b"RI").unwrap();
+        let output_file = NamedTempFile::new().unwrap();
+
+        match extract_wav_from_png(tiny_file.path(), output_file.path()) {
+            Err(PolyglotError::InvalidInput(message)) => assert_eq!(message, "file too short"),
+            other => panic!("expected a clean error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_wav_polyglot_decodes_audio() {
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let mut wav = crate::wav::WavFile::from_data(minimal_wav()).unwrap();
+        wav.embed_png_data(&minimal_png()).unwrap();
+        let mut polyglot_file = NamedTempFile::new().unwrap();
+        polyglot_file.write_all(&wav.raw_data).unwrap();
+        assert_eq!(validate_polyglot(polyglot_file.path()).unwrap(), ValidationResult::ValidWavPng);
+
+        // A data chunk claiming more samples than the file holds no longer decodes, and the
+        // chunk walk to the PNG overruns too
+        let data_start = wav.raw_data.windows(4).position(|w| w == b"data").unwrap();
+        wav.raw_data[data_start + 4..data_start + 8].copy_from_slice(&1000u32.to_le_bytes());
+        let mut broken_file = NamedTempFile::new().unwrap();
+        broken_file.write_all(&wav.raw_data).unwrap();
+        assert!(matches!(validate_polyglot(broken_file.path()).unwrap(), ValidationResult::InvalidWavAndPng(_, _)));
+    }
+
+    #[test]
+    fn test_validate_png_wav_and_wav_png_polyglots() {
+        use crate::testutil::{minimal_png, minimal_wav};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let (png_path, wav_path) = (dir.path().join("image.png"), dir.path().join("audio.wav"));
+        fs::write(&png_path, minimal_png()).unwrap();
+        fs::write(&wav_path, minimal_wav()).unwrap();
+
+        // The output extension picks the dominant format
+        for name in ["png_wav.png", "wav_png.wav"] {
+            let output = dir.path().join(name);
+            crate::polyglot::create_png_wav_polyglot(&png_path, &wav_path, &output).unwrap();
+            let result = validate_polyglot(&output).unwrap();
+            assert_eq!(result, ValidationResult::ValidWavPng, "{}", name);
+            assert!(result.is_valid());
+        }
+
+        // Breaking the PNG chunk inside the WAV is reported on the PNG side
+        let wav_png = dir.path().join("wav_png.wav");
+        let mut data = fs::read(&wav_png).unwrap();
+        let png_start = data.windows(8).position(|w| w == PNG_SIGNATURE).unwrap();
+        data[png_start + 20] ^= 0xFF; // Inside IHDR, so its CRC no longer matches
+        fs::write(&wav_png, &data).unwrap();
+        assert!(matches!(validate_polyglot(&wav_png).unwrap(), ValidationResult::InvalidPng(_)));
+    }
+
+    #[test]
+    fn test_payload_range_slices_the_extracted_payload() {
+        use crate::testutil::{minimal_png, minimal_wav, minimal_zip};
+
+        let mut text = PngFile::from_data(minimal_png()).unwrap();
+        text.add_zip_text_chunk(&minimal_zip()).unwrap();
+        let mut idat_wav = PngFile::from_data(minimal_png()).unwrap();
+        idat_wav.append_to_idat(&minimal_wav()).unwrap();
+        let mut wav_png = crate::wav::WavFile::from_data(minimal_wav()).unwrap();
+        wav_png.embed_png_data(&minimal_png()).unwrap();
+        let mut zip_dominant = crate::polyglot::PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        let png_in_png = crate::polyglot::build_png_in_png_polyglot(&minimal_png(), &minimal_png()).unwrap();
+
+        let cases = [
+            (text.raw_data.clone(), FileFormat::Zip, extract_zip_from_png_data(&text.raw_data).unwrap()),
+            (idat_wav.raw_data.clone(), FileFormat::Wav, extract_wav_from_png_data(&idat_wav.raw_data).unwrap()),
+            (wav_png.raw_data.clone(), FileFormat::Png, wav_png.extract_png_data().unwrap()),
+            (zip_dominant.create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).unwrap(), FileFormat::Png, minimal_png()),
+            (crate::polyglot::build_zip_comment_polyglot(&minimal_png()).unwrap(), FileFormat::Png, minimal_png()),
+            (png_in_png.clone(), FileFormat::Png, extract_png_from_png_data(&png_in_png).unwrap()),
+        ];
+        for (index, (data, format, expected)) in cases.into_iter().enumerate() {
+            let (found_format, range) = payload_range(&data).unwrap();
+            assert_eq!(found_format, format, "case {}", index);
+            assert_eq!(&data[range], expected.as_slice(), "case {}", index);
+        }
+
+        assert!(payload_range(&minimal_png()).is_err());
+    }
+
     #[test]
     fn test_validate_polyglot() {
         let polyglot_data = create_test_polyglot();
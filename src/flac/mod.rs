@@ -2,11 +2,9 @@
 
 use std::path::Path;
 use std::fs;
+use crate::utils::FLAC_SIGNATURE;
 use crate::{PolyglotError, PolyglotResult};
 
-/// FLAC file signature
-const FLAC_SIGNATURE: &[u8; 4] = b"fLaC";
-
 /// FLAC metadata block types
 #[derive(Debug, Clone)]
 pub enum MetadataBlock {
@@ -20,15 +18,74 @@ pub enum MetadataBlock {
     Unknown { block_type: u8, length: u32, data: Vec<u8> },
 }
 
+/// Block type codes, as stored in the low 7 bits of a metadata block header
+pub const BLOCK_TYPE_STREAMINFO: u8 = 0;
+pub const BLOCK_TYPE_PADDING: u8 = 1;
+pub const BLOCK_TYPE_APPLICATION: u8 = 2;
+pub const BLOCK_TYPE_SEEKTABLE: u8 = 3;
+pub const BLOCK_TYPE_VORBIS_COMMENT: u8 = 4;
+pub const BLOCK_TYPE_CUESHEET: u8 = 5;
+pub const BLOCK_TYPE_PICTURE: u8 = 6;
+
+impl MetadataBlock {
+    /// Block type code of this block
+    pub fn block_type(&self) -> u8 {
+        match self {
+            MetadataBlock::StreamInfo(_) => BLOCK_TYPE_STREAMINFO,
+            MetadataBlock::Padding { .. } => BLOCK_TYPE_PADDING,
+            MetadataBlock::Application { .. } => BLOCK_TYPE_APPLICATION,
+            MetadataBlock::SeekTable(_) => BLOCK_TYPE_SEEKTABLE,
+            MetadataBlock::VorbisComment(_) => BLOCK_TYPE_VORBIS_COMMENT,
+            MetadataBlock::Cuesheet(_) => BLOCK_TYPE_CUESHEET,
+            MetadataBlock::Picture(_) => BLOCK_TYPE_PICTURE,
+            MetadataBlock::Unknown { block_type, .. } => *block_type,
+        }
+    }
+
+    /// Name of the block type as the FLAC spec spells it
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetadataBlock::StreamInfo(_) => "STREAMINFO",
+            MetadataBlock::Padding { .. } => "PADDING",
+            MetadataBlock::Application { .. } => "APPLICATION",
+            MetadataBlock::SeekTable(_) => "SEEKTABLE",
+            MetadataBlock::VorbisComment(_) => "VORBIS_COMMENT",
+            MetadataBlock::Cuesheet(_) => "CUESHEET",
+            MetadataBlock::Picture(_) => "PICTURE",
+            MetadataBlock::Unknown { .. } => "UNKNOWN",
+        }
+    }
+
+    /// Length of the block body, excluding the 4-byte header
+    pub fn len(&self) -> usize {
+        match self {
+            MetadataBlock::StreamInfo(_) => 34,
+            MetadataBlock::Application { data, .. } => 4 + data.len(),
+            MetadataBlock::Padding { data, .. }
+            | MetadataBlock::SeekTable(data)
+            | MetadataBlock::VorbisComment(data)
+            | MetadataBlock::Cuesheet(data)
+            | MetadataBlock::Picture(data)
+            | MetadataBlock::Unknown { data, .. } => data.len(),
+        }
+    }
+
+    /// Whether the block body is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Parsed FLAC structure
 #[derive(Debug, Clone)]
 pub struct FlacStructure {
     pub streaminfo: StreamInfo,
     pub metadata_blocks: Vec<MetadataBlock>,
+    pub audio_data: Vec<u8>, // Audio frames following the metadata blocks
 }
 
 /// STREAMINFO block (always the first metadata block)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StreamInfo {
     pub min_block_size: u16,
     pub max_block_size: u16,
@@ -41,6 +98,25 @@ pub struct StreamInfo {
     pub md5_signature: [u8; 16],
 }
 
+/// Picture type for front cover art in a PICTURE block
+pub const PICTURE_TYPE_FRONT_COVER: u32 = 3;
+
+/// PICTURE block type for back cover art
+pub const PICTURE_TYPE_BACK_COVER: u32 = 4;
+
+/// Decoded PICTURE metadata block (embedded artwork)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Picture {
+    pub picture_type: u32,
+    pub mime_type: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,  // Bits per pixel
+    pub colors: u32, // Palette size for indexed images, otherwise 0
+    pub data: Vec<u8>,
+}
+
 /// FLAC file handler for parasitic polyglots
 #[derive(Debug, Clone)]
 pub struct FlacFile {
@@ -52,12 +128,16 @@ impl FlacFile {
     /// Load FLAC file from path
     pub fn from_file(path: &Path) -> PolyglotResult<Self> {
         let raw_data = fs::read(path)?;
-        
+        Self::from_data(raw_data)
+    }
+
+    /// Load FLAC file from raw data
+    pub fn from_data(raw_data: Vec<u8>) -> PolyglotResult<Self> {
         if raw_data.len() < 8 {
             return Err(PolyglotError::PngParse("File too short for FLAC".to_string()));
         }
         
-        if &raw_data[0..4] != FLAC_SIGNATURE {
+        if raw_data[0..4] != FLAC_SIGNATURE {
             return Err(PolyglotError::PngParse("Invalid FLAC signature".to_string()));
         }
         
@@ -68,6 +148,8 @@ impl FlacFile {
     
     /// Inject PNG data into PADDING metadata blocks (parasitic embedding)
     pub fn inject_png_to_padding(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(png_data)?;
+
         // Find a PADDING block large enough, or find one to expand
         let (block_idx, padding_block) = self.find_or_create_padding_for_png(png_data.len())?;
         
@@ -82,6 +164,123 @@ impl FlacFile {
         Ok(())
     }
     
+    /// Add an APPLICATION block carrying `data` under the 4-byte application `id`
+    pub fn inject_application_block(&mut self, id: [u8; 4], data: &[u8]) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(data)?;
+
+        // Work on a copy so a block too large to write leaves the file as it was
+        let mut structure = self.structure.clone();
+        structure.metadata_blocks.push(MetadataBlock::Application { id, data: data.to_vec() });
+
+        // Rebuild the raw data; the last-block flag moves to the new block
+        self.raw_data = structure.to_bytes()?;
+        self.structure = structure;
+
+        Ok(())
+    }
+
+    /// Get the data of the first APPLICATION block with the given `id`
+    pub fn extract_application_block(&self, id: [u8; 4]) -> Option<Vec<u8>> {
+        self.structure.metadata_blocks.iter().find_map(|block| match block {
+            MetadataBlock::Application { id: block_id, data } if *block_id == id => Some(data.clone()),
+            _ => None,
+        })
+    }
+
+    /// Store a PNG as front cover art in a PICTURE block, replacing any existing front cover
+    ///
+    /// Unlike PADDING or APPLICATION embedding this is how FLAC files normally carry
+    /// images, so players show the PNG as cover art.
+    pub fn set_picture_block(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        let png = crate::png::PngFile::from_data(png_data.to_vec())?;
+        let header = crate::png::parser::parse_ihdr(&png.parsed)?;
+        let colors = match header.color_type {
+            3 => png.parsed.chunks.iter()
+                .find(|chunk| chunk.chunk_type == *b"PLTE")
+                .map_or(0, |chunk| chunk.data.len() as u32 / 3),
+            _ => 0,
+        };
+
+        let picture = Picture {
+            picture_type: PICTURE_TYPE_FRONT_COVER,
+            mime_type: "image/png".to_string(),
+            description: String::new(),
+            width: header.width,
+            height: header.height,
+            depth: header.bits_per_pixel() as u32,
+            colors,
+            data: png_data.to_vec(),
+        };
+        let block = MetadataBlock::Picture(picture.to_bytes()?);
+
+        // Work on a copy so a block too large to write leaves the file as it was
+        let mut structure = self.structure.clone();
+        let existing = structure.metadata_blocks.iter().position(|block| matches!(
+            block,
+            MetadataBlock::Picture(data) if Picture::parse_from_data(data)
+                .is_ok_and(|picture| picture.picture_type == PICTURE_TYPE_FRONT_COVER)
+        ));
+        match existing {
+            Some(index) => structure.metadata_blocks[index] = block,
+            None => structure.metadata_blocks.push(block),
+        }
+
+        self.raw_data = structure.to_bytes()?;
+        self.structure = structure;
+
+        Ok(())
+    }
+
+    /// Get the image data of the first PICTURE block holding a PNG
+    pub fn extract_picture_block(&self) -> PolyglotResult<Option<Vec<u8>>> {
+        for block in &self.structure.metadata_blocks {
+            if let MetadataBlock::Picture(data) = block {
+                let picture = Picture::parse_from_data(data)?;
+                if picture.mime_type == "image/png" {
+                    return Ok(Some(picture.data));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Picture type and image data of every PICTURE block, in file order
+    ///
+    /// Blocks whose fields don't parse are skipped rather than failing the whole listing.
+    pub fn extract_all_pictures(&self) -> Vec<(u32, Vec<u8>)> {
+        self.structure.metadata_blocks.iter()
+            .filter_map(|block| match block {
+                MetadataBlock::Picture(data) => Picture::parse_from_data(data).ok(),
+                _ => None,
+            })
+            .map(|picture| (picture.picture_type, picture.data))
+            .collect()
+    }
+
+    /// Get the PNG embedded in a PADDING, PICTURE or APPLICATION block, in that order
+    ///
+    /// A PADDING block is zero-filled after the PNG, so the image is cut off at its IEND.
+    pub fn extract_embedded_png(&self) -> PolyglotResult<Option<Vec<u8>>> {
+        for block in self.structure.padding_blocks() {
+            if let MetadataBlock::Padding { data, .. } = block
+                && crate::utils::is_png_signature(data) {
+                    let parsed = crate::png::parser::parse_png_chunks(data)?;
+                    let png_len = 8 + parsed.chunks.iter().map(|chunk| 12 + chunk.data.len()).sum::<usize>();
+                    return Ok(Some(data[..png_len].to_vec()));
+                }
+        }
+
+        if let Some(png) = self.extract_picture_block()? {
+            return Ok(Some(png));
+        }
+
+        Ok(self.structure.blocks_of_type(BLOCK_TYPE_APPLICATION).find_map(|block| match block {
+            MetadataBlock::Application { data, .. } if crate::utils::is_png_signature(data) => Some(data.clone()),
+            _ => None,
+        }))
+    }
+
     /// Find existing PADDING block large enough for PNG, or create/enlarge one
     fn find_or_create_padding_for_png(&self, png_size: usize) -> PolyglotResult<(usize, &MetadataBlock)> {
         // Look for existing PADDING blocks
@@ -127,36 +326,64 @@ impl FlacFile {
 }
 
 impl FlacStructure {
+    /// Metadata blocks with the given type code, in file order
+    pub fn blocks_of_type(&self, block_type: u8) -> impl Iterator<Item = &MetadataBlock> {
+        self.metadata_blocks.iter().filter(move |block| block.block_type() == block_type)
+    }
+
+    /// PADDING blocks, in file order
+    pub fn padding_blocks(&self) -> impl Iterator<Item = &MetadataBlock> {
+        self.blocks_of_type(BLOCK_TYPE_PADDING)
+    }
+
+    /// Raw body of the VORBIS_COMMENT block, if any (the spec allows at most one)
+    pub fn find_vorbis_comment(&self) -> Option<&[u8]> {
+        self.metadata_blocks.iter().find_map(|block| match block {
+            MetadataBlock::VorbisComment(data) => Some(data.as_slice()),
+            _ => None,
+        })
+    }
+
     pub fn parse(data: &[u8]) -> PolyglotResult<Self> {
         let mut offset = 4; // Skip "fLaC" signature
         
         // Parse STREAMINFO (first and mandatory block)
+        if offset + 4 > data.len() || data[offset] & 0x7F != 0 {
+            return Err(PolyglotError::PngParse("FLAC is missing its STREAMINFO block".to_string()));
+        }
+        let mut is_last = (data[offset] & 0x80) != 0;
         let (streaminfo, new_offset) = StreamInfo::parse(data, offset)?;
         offset = new_offset;
         
         let mut metadata_blocks = vec![MetadataBlock::StreamInfo(streaminfo.clone())];
         
-        // Parse remaining metadata blocks until we hit a data frame
-        while offset < data.len() {
-            let is_last = (data[offset] & 0x80) != 0;
+        // Parse remaining metadata blocks until the last-block flag is seen
+        while !is_last && offset < data.len() {
+            is_last = (data[offset] & 0x80) != 0;
             let (block, new_offset) = Self::parse_metadata_block(data, offset)?;
             metadata_blocks.push(block);
             offset = new_offset;
-
-            if is_last {
-                // Last metadata block, parsing would continue for frames...
-                break;
-            }
         }
+
+        // Everything after the metadata is audio frames, kept verbatim
+        let audio_data = data[offset..].to_vec();
         
-        Ok(FlacStructure { streaminfo, metadata_blocks })
+        Ok(FlacStructure { streaminfo, metadata_blocks, audio_data })
     }
     
     fn parse_metadata_block(data: &[u8], offset: usize) -> PolyglotResult<(MetadataBlock, usize)> {
+        if offset + 4 > data.len() {
+            return Err(PolyglotError::PngParse("Insufficient data for FLAC block header".to_string()));
+        }
+
         let block_type = data[offset] & 0x7F;
-        let length = u32::from_be_bytes([data[offset + 1], data[offset + 2], data[offset + 3], data[offset + 4]]);
+        let length = u32::from_be_bytes([0, data[offset + 1], data[offset + 2], data[offset + 3]]); // 24-bit
         let data_start = offset + 4;
         let data_end = data_start + length as usize;
+
+        if data_end > data.len() {
+            return Err(PolyglotError::PngParse("FLAC block extends beyond file".to_string()));
+        }
         
         let block_data = data[data_start..data_end].to_vec();
         
@@ -167,13 +394,14 @@ impl FlacStructure {
                 if block_data.len() >= 4 {
                     let mut id = [0u8; 4];
                     id.copy_from_slice(&block_data[0..4]);
-                    (MetadataBlock::Application { id, data: block_data }, data_end)
+                    (MetadataBlock::Application { id, data: block_data[4..].to_vec() }, data_end)
                 } else {
                     (MetadataBlock::Unknown { block_type, length, data: block_data }, data_end)
                 }
             },
             3 => (MetadataBlock::SeekTable(block_data), data_end),
             4 => (MetadataBlock::VorbisComment(block_data), data_end),
+            5 => (MetadataBlock::Cuesheet(block_data), data_end),
             6 => (MetadataBlock::Picture(block_data), data_end),
             _ => (MetadataBlock::Unknown { block_type, length, data: block_data }, data_end),
         };
@@ -184,35 +412,45 @@ impl FlacStructure {
     pub fn to_bytes(&self) -> PolyglotResult<Vec<u8>> {
         let mut result = FLAC_SIGNATURE.to_vec();
         
-        // Write all metadata blocks
-        for block in &self.metadata_blocks {
-            Self::write_metadata_block(block, &mut result)?;
+        // Write all metadata blocks, flagging only the final one as last
+        for (i, block) in self.metadata_blocks.iter().enumerate() {
+            let is_last = i + 1 == self.metadata_blocks.len();
+            Self::write_metadata_block(block, is_last, &mut result)?;
         }
         
-        // Would need to write frames for complete implementation
-        // For now, assume metadata-only operations
+        // Audio frames are carried over unchanged
+        result.extend_from_slice(&self.audio_data);
         
         Ok(result)
     }
     
-    fn write_metadata_block(block: &MetadataBlock, output: &mut Vec<u8>) -> PolyglotResult<()> {
-        match block {
-            MetadataBlock::Padding { data, .. } => {
-                let length = data.len() as u32;
-                output.push(1); // PADDING type
-                output.extend_from_slice(&length.to_be_bytes());
-                output.extend_from_slice(data);
-            }
+    fn write_metadata_block(block: &MetadataBlock, is_last: bool, output: &mut Vec<u8>) -> PolyglotResult<()> {
+        let (block_type, body) = match block {
             MetadataBlock::StreamInfo(streaminfo) => {
-                output.push(0); // STREAMINFO type
-                streaminfo.write_to(output)?;
-            }
-            // Other block types would be implemented here
-            _ => {
-                // Placeholder - would implement full serialization
-                return Err(PolyglotError::InvalidInput("Block serialization not implemented".to_string()));
+                let mut body = Vec::with_capacity(34);
+                streaminfo.write_to(&mut body)?;
+                (0, body)
             }
+            MetadataBlock::Padding { data, .. } => (1, data.clone()),
+            MetadataBlock::Application { id, data } => (2, [id.as_slice(), data].concat()),
+            MetadataBlock::SeekTable(data) => (3, data.clone()),
+            MetadataBlock::VorbisComment(data) => (4, data.clone()),
+            MetadataBlock::Cuesheet(data) => (5, data.clone()),
+            MetadataBlock::Picture(data) => (6, data.clone()),
+            MetadataBlock::Unknown { block_type, data, .. } => (*block_type, data.clone()),
+        };
+
+        // Block length is a 24-bit field
+        if body.len() > 0xFF_FFFF {
+            return Err(PolyglotError::InvalidInput(
+                format!("FLAC metadata block too large ({} bytes)", body.len())
+            ));
         }
+
+        let header = if is_last { block_type | 0x80 } else { block_type };
+        output.push(header);
+        output.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        output.extend_from_slice(&body);
         
         Ok(())
     }
@@ -221,6 +459,9 @@ impl FlacStructure {
 impl StreamInfo {
     pub fn parse(data: &[u8], offset: usize) -> PolyglotResult<(StreamInfo, usize)> {
         let block_start = offset + 4; // Skip block header
+        if block_start + 34 > data.len() {
+            return Err(PolyglotError::PngParse("STREAMINFO data too short".to_string()));
+        }
         let streaminfo_data = &data[block_start..block_start + 34];
         
         Self::parse_from_data(streaminfo_data).map(|si| (si, block_start + 34))
@@ -233,17 +474,16 @@ impl StreamInfo {
         
         let min_block_size = u16::from_be_bytes([data[0], data[1]]);
         let max_block_size = u16::from_be_bytes([data[2], data[3]]);
-        let min_frame_size = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
-        let max_frame_size = u32::from_be_bytes([data[8], data[9], data[10], data[11]]);
-        
-        let sample_rate_20 = u32::from_be_bytes([data[12], data[13], data[14], data[15]]);
-        let sample_rate = (sample_rate_20 >> 12) & 0xFFFFF;
-        let channels = (((sample_rate_20 >> 9) & 0x07) + 1) as u8;
-        let bits_per_sample = (((sample_rate_20 >> 4) & 0x1F) + 1) as u8;
+        let min_frame_size = u32::from_be_bytes([0, data[4], data[5], data[6]]); // 24-bit
+        let max_frame_size = u32::from_be_bytes([0, data[7], data[8], data[9]]); // 24-bit
         
-        let total_samples_36 = u64::from_be_bytes([data[16], data[17], data[18], data[19], 
-                                                  data[20], data[21], data[22], data[23]]);
-        let total_samples = total_samples_36 & 0xFFFFFFFFF;
+        // 20-bit sample rate, 3-bit channels-1, 5-bit bits-1, 36-bit total samples
+        let packed = u64::from_be_bytes([data[10], data[11], data[12], data[13],
+                                         data[14], data[15], data[16], data[17]]);
+        let sample_rate = (packed >> 44) as u32 & 0xFFFFF;
+        let channels = ((packed >> 41) & 0x07) as u8 + 1;
+        let bits_per_sample = ((packed >> 36) & 0x1F) as u8 + 1;
+        let total_samples = packed & 0xF_FFFF_FFFF;
         
         let mut md5_signature = [0u8; 16];
         md5_signature.copy_from_slice(&data[18..34]);
@@ -262,7 +502,237 @@ impl StreamInfo {
     }
     
     pub fn write_to(&self, output: &mut Vec<u8>) -> PolyglotResult<()> {
-        // Would implement STREAMINFO serialization
-        Err(PolyglotError::InvalidInput("STREAMINFO serialization not implemented yet".to_string()))
+        if self.channels == 0 || self.bits_per_sample == 0 {
+            return Err(PolyglotError::InvalidInput("STREAMINFO channels and bits per sample must be non-zero".to_string()));
+        }
+
+        output.extend_from_slice(&self.min_block_size.to_be_bytes());
+        output.extend_from_slice(&self.max_block_size.to_be_bytes());
+        output.extend_from_slice(&self.min_frame_size.to_be_bytes()[1..]);
+        output.extend_from_slice(&self.max_frame_size.to_be_bytes()[1..]);
+
+        let packed = ((self.sample_rate as u64 & 0xFFFFF) << 44)
+            | (((self.channels - 1) as u64 & 0x07) << 41)
+            | (((self.bits_per_sample - 1) as u64 & 0x1F) << 36)
+            | (self.total_samples & 0xF_FFFF_FFFF);
+        output.extend_from_slice(&packed.to_be_bytes());
+        output.extend_from_slice(&self.md5_signature);
+
+        Ok(())
+    }
+}
+
+impl Picture {
+    pub fn parse_from_data(data: &[u8]) -> PolyglotResult<Picture> {
+        let truncated = || PolyglotError::PngParse("PICTURE block truncated".to_string());
+        let mut offset = 0;
+        let read_u32 = |offset: &mut usize| -> PolyglotResult<u32> {
+            let bytes = data.get(*offset..*offset + 4).ok_or_else(truncated)?;
+            *offset += 4;
+            Ok(u32::from_be_bytes(bytes.try_into().expect("slice is 4 bytes")))
+        };
+
+        let picture_type = read_u32(&mut offset)?;
+        let mime_len = read_u32(&mut offset)? as usize;
+        let mime_type = data.get(offset..offset + mime_len).ok_or_else(truncated)?;
+        let mime_type = String::from_utf8_lossy(mime_type).into_owned();
+        offset += mime_len;
+        let description_len = read_u32(&mut offset)? as usize;
+        let description = data.get(offset..offset + description_len).ok_or_else(truncated)?;
+        let description = String::from_utf8_lossy(description).into_owned();
+        offset += description_len;
+
+        let width = read_u32(&mut offset)?;
+        let height = read_u32(&mut offset)?;
+        let depth = read_u32(&mut offset)?;
+        let colors = read_u32(&mut offset)?;
+        let data_len = read_u32(&mut offset)? as usize;
+        let picture_data = data.get(offset..offset + data_len).ok_or_else(truncated)?.to_vec();
+
+        Ok(Picture { picture_type, mime_type, description, width, height, depth, colors, data: picture_data })
+    }
+
+    pub fn to_bytes(&self) -> PolyglotResult<Vec<u8>> {
+        let data_len = u32::try_from(self.data.len()).map_err(|_| PolyglotError::InvalidInput(
+            format!("picture data too large ({} bytes)", self.data.len())
+        ))?;
+
+        let mut output = Vec::with_capacity(32 + self.mime_type.len() + self.description.len() + self.data.len());
+        output.extend_from_slice(&self.picture_type.to_be_bytes());
+        output.extend_from_slice(&(self.mime_type.len() as u32).to_be_bytes());
+        output.extend_from_slice(self.mime_type.as_bytes());
+        output.extend_from_slice(&(self.description.len() as u32).to_be_bytes());
+        output.extend_from_slice(self.description.as_bytes());
+        for field in [self.width, self.height, self.depth, self.colors, data_len] {
+            output.extend_from_slice(&field.to_be_bytes());
+        }
+        output.extend_from_slice(&self.data);
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{minimal_flac, minimal_png};
+
+    #[test]
+    fn test_flac_parse() {
+        let flac = FlacFile::from_data(minimal_flac()).unwrap();
+
+        assert_eq!(flac.structure.streaminfo.sample_rate, 44100);
+        assert_eq!(flac.structure.streaminfo.channels, 2);
+        assert_eq!(flac.structure.streaminfo.bits_per_sample, 16);
+        assert_eq!(flac.structure.streaminfo.total_samples, 88200);
+        assert_eq!(flac.structure.metadata_blocks.len(), 2);
+        assert_eq!(flac.structure.audio_data.len(), 8);
+    }
+
+    #[test]
+    fn test_block_accessors() {
+        let mut flac = FlacFile::from_data(minimal_flac()).unwrap();
+        assert!(flac.structure.find_vorbis_comment().is_none());
+
+        let blocks = &mut flac.structure.metadata_blocks;
+        blocks.push(MetadataBlock::VorbisComment(b"vendor".to_vec()));
+        blocks.push(MetadataBlock::Padding { length: 3, data: vec![0; 3] });
+        blocks.push(MetadataBlock::Application { id: *b"test", data: vec![1, 2] });
+        let reparsed = FlacStructure::parse(&flac.structure.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(reparsed.padding_blocks().map(MetadataBlock::len).collect::<Vec<_>>(), [8, 3]);
+        assert_eq!(reparsed.blocks_of_type(BLOCK_TYPE_STREAMINFO).count(), 1);
+        assert_eq!(reparsed.blocks_of_type(BLOCK_TYPE_APPLICATION).next().unwrap().len(), 6);
+        assert_eq!(reparsed.blocks_of_type(BLOCK_TYPE_PICTURE).count(), 0);
+        assert_eq!(reparsed.find_vorbis_comment(), Some(b"vendor".as_slice()));
+    }
+
+    #[test]
+    fn test_streaminfo_round_trip() {
+        let original = minimal_flac();
+        let structure = FlacStructure::parse(&original).unwrap();
+        assert_eq!(structure.to_bytes().unwrap(), original);
+
+        // Values that fill every packed field up to its bit boundary
+        let streaminfo = StreamInfo {
+            min_block_size: 16,
+            max_block_size: 65535,
+            min_frame_size: 0x00_0102,
+            max_frame_size: 0xFF_FFFF,
+            sample_rate: 655350,
+            channels: 8,
+            bits_per_sample: 32,
+            total_samples: 0xF_FFFF_FFFF,
+            md5_signature: *b"0123456789abcdef",
+        };
+        let mut body = Vec::new();
+        streaminfo.write_to(&mut body).unwrap();
+        assert_eq!(body.len(), 34);
+        assert_eq!(StreamInfo::parse_from_data(&body).unwrap(), streaminfo);
+    }
+
+    #[test]
+    fn test_application_block_round_trip() {
+        let original = minimal_flac();
+        let png_data = b"\x89PNG\r\n\x1A\nnot really an image".to_vec();
+        let app_id = *b"pnGx";
+
+        let mut flac = FlacFile::from_data(original.clone()).unwrap();
+        assert!(flac.extract_application_block(app_id).is_none());
+        flac.inject_application_block(app_id, &png_data).unwrap();
+
+        // Reparse the rebuilt file and recover the payload
+        let reparsed = FlacFile::from_data(flac.as_bytes().to_vec()).unwrap();
+        assert_eq!(reparsed.extract_application_block(app_id).unwrap(), png_data);
+        assert!(reparsed.extract_application_block(*b"othr").is_none());
+
+        // Only the new block carries the last-block flag, and audio frames are untouched
+        let padding_header = 4 + 4 + 34;
+        assert_eq!(flac.as_bytes()[padding_header], 0x01);
+        assert_eq!(flac.as_bytes()[padding_header + 4 + 8], 0x82);
+        assert!(flac.as_bytes().ends_with(&original[original.len() - 8..]));
+    }
+
+    #[test]
+    fn test_rejected_application_block_leaves_file_unchanged() {
+        let original = minimal_flac();
+        let mut flac = FlacFile::from_data(original.clone()).unwrap();
+
+        assert!(matches!(flac.inject_application_block(*b"pnGx", &[]), Err(PolyglotError::InvalidInput(_))));
+        assert!(flac.inject_application_block(*b"pnGx", &vec![0u8; 1 << 24]).is_err());
+
+        assert_eq!(flac.as_bytes(), original.as_slice());
+        assert!(flac.extract_application_block(*b"pnGx").is_none());
+    }
+
+    #[test]
+    fn test_picture_block_round_trip() {
+        let png_data = minimal_png();
+        let mut flac = FlacFile::from_data(minimal_flac()).unwrap();
+        assert_eq!(flac.extract_picture_block().unwrap(), None);
+
+        flac.set_picture_block(&png_data).unwrap();
+        flac.set_picture_block(&png_data).unwrap(); // Replaces rather than duplicates
+
+        let reparsed = FlacFile::from_data(flac.as_bytes().to_vec()).unwrap();
+        assert_eq!(reparsed.extract_picture_block().unwrap(), Some(png_data.clone()));
+
+        // The block is a well-formed front cover as a FLAC tool would read it
+        let picture_blocks: Vec<_> = reparsed.structure.metadata_blocks.iter()
+            .filter_map(|block| match block { MetadataBlock::Picture(data) => Some(data), _ => None })
+            .collect();
+        assert_eq!(picture_blocks.len(), 1);
+        let picture_header = 4 + 4 + 34 + 4 + 8;
+        assert_eq!(flac.as_bytes()[picture_header], 0x86); // Last block, type 6
+        let picture = Picture::parse_from_data(picture_blocks[0]).unwrap();
+        assert_eq!(picture.picture_type, PICTURE_TYPE_FRONT_COVER);
+        assert_eq!(picture.mime_type, "image/png");
+        assert_eq!((picture.width, picture.height, picture.depth, picture.colors), (1, 1, 24, 0));
+        assert_eq!(picture_blocks[0].len(), 4 + 4 + 9 + 4 + 16 + 4 + png_data.len());
+    }
+
+    #[test]
+    fn test_oversized_picture_leaves_file_unchanged() {
+        // A PNG padded past the 24-bit block length limit with an ancillary chunk
+        let mut png_data = minimal_png();
+        let iend = png_data.split_off(png_data.len() - 12);
+        let filler = vec![0u8; 0xFF_FFFF];
+        png_data.extend_from_slice(&(filler.len() as u32).to_be_bytes());
+        png_data.extend_from_slice(b"fILl");
+        png_data.extend_from_slice(&filler);
+        png_data.extend_from_slice(&crate::png::chunk_crc(b"fILl", &filler).to_be_bytes());
+        png_data.extend_from_slice(&iend);
+
+        let mut flac = FlacFile::from_data(minimal_flac()).unwrap();
+        assert!(flac.set_picture_block(&png_data).is_err());
+        assert_eq!(flac.as_bytes(), minimal_flac());
+        assert_eq!(flac.structure.to_bytes().unwrap(), minimal_flac());
+    }
+
+    #[test]
+    fn test_extract_all_pictures() {
+        let front = minimal_png();
+        let back = [minimal_png().as_slice(), b"trailing bytes"].concat();
+        let mut flac = FlacFile::from_data(minimal_flac()).unwrap();
+        assert!(flac.extract_all_pictures().is_empty());
+
+        flac.set_picture_block(&front).unwrap();
+        let back_cover = Picture {
+            picture_type: PICTURE_TYPE_BACK_COVER,
+            mime_type: "image/png".to_string(),
+            description: "back".to_string(),
+            width: 1,
+            height: 1,
+            depth: 24,
+            colors: 0,
+            data: back.clone(),
+        };
+        flac.structure.metadata_blocks.push(MetadataBlock::Picture(back_cover.to_bytes().unwrap()));
+
+        let reparsed = FlacFile::from_data(flac.structure.to_bytes().unwrap()).unwrap();
+        assert_eq!(reparsed.extract_all_pictures(), vec![
+            (PICTURE_TYPE_FRONT_COVER, front),
+            (PICTURE_TYPE_BACK_COVER, back),
+        ]);
     }
 }
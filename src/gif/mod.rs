@@ -2,8 +2,12 @@
 
 use std::path::Path;
 use std::fs;
+use crate::utils::GIF_SIGNATURE;
 use crate::{PolyglotError, PolyglotResult};
 
+/// Marker that precedes the ZIP in a comment extension written by `add_zip_comment_extension`
+const ZIP_COMMENT_PREFIX: &[u8] = b"ZIP_ARCHIVE:";
+
 /// GIF file representation
 #[derive(Debug, Clone)]
 pub struct GifFile {
@@ -17,6 +21,7 @@ pub struct GifStructure {
     pub header: GifHeader,
     pub global_color_table: Option<Vec<u8>>,
     pub blocks: Vec<GifBlock>,
+    pub images: Vec<ImageDescriptor>,
 }
 
 /// GIF header (6 bytes)
@@ -26,7 +31,21 @@ pub struct GifHeader {
     pub version: [u8; 3],   // "89a" or "87a"
 }
 
-/// GIF blocks (simplified)
+/// Image descriptor fields and the position of the image data that follows them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageDescriptor {
+    pub left: u16,
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+    pub interlaced: bool,
+    pub local_color_table: Option<Vec<u8>>,
+    pub lzw_min_code_size: u8,
+    pub data_offset: usize, // Absolute offset of the first LZW data sub-block
+    pub end_offset: usize,  // Absolute offset just past the sub-block terminator
+}
+
+/// GIF blocks, each holding its raw bytes from introducer to terminator
 #[derive(Debug, Clone)]
 pub enum GifBlock {
     ImageDescriptor(Vec<u8>),
@@ -41,13 +60,17 @@ impl GifFile {
     /// Load GIF file from path
     pub fn from_file(path: &Path) -> PolyglotResult<Self> {
         let raw_data = fs::read(path)?;
-        
+        Self::from_data(raw_data)
+    }
+
+    /// Load GIF file from raw data
+    pub fn from_data(raw_data: Vec<u8>) -> PolyglotResult<Self> {
         if raw_data.len() < 6 {
-            return Err(PolyglotError::PngParse("File too short for GIF".to_string())); // reusing error type
+            return Err(PolyglotError::GifParse("File too short for GIF".to_string()));
         }
         
-        if &raw_data[0..3] != b"GIF" {
-            return Err(PolyglotError::PngParse("Invalid GIF signature".to_string()));
+        if raw_data[0..3] != GIF_SIGNATURE {
+            return Err(PolyglotError::GifParse("Invalid GIF signature".to_string()));
         }
         
         // Basic structure parsing would go here
@@ -61,33 +84,42 @@ impl GifFile {
         // Embed ZIP data in GIF comment extension
         // This is similar to PNG text chunks but using GIF comment blocks
         let mut comment_data = Vec::new();
-        comment_data.extend_from_slice(b"ZIP_ARCHIVE:");
+        comment_data.extend_from_slice(ZIP_COMMENT_PREFIX);
         comment_data.extend_from_slice(zip_data);
         
-        // Build comment extension: 0x21 0xFE + length + data + 0x00
+        // Build comment extension: 0x21 0xFE + sub-blocks + 0x00
         let mut extension = vec![0x21, 0xFE]; // Comment extension introducer
-        
-        // Add comment sub-blocks
-        let mut remaining = &comment_data[..];
-        while remaining.len() > 255 {
-            extension.push(255);
-            extension.extend_from_slice(&remaining[0..255]);
-            remaining = &remaining[255..];
-        }
-        if !remaining.is_empty() {
-            extension.push(remaining.len() as u8);
-            extension.extend_from_slice(remaining);
-        }
-        extension.push(0x00); // End of extension
-        
-        // Insert before trailer (0x3B)
-        if let Some(trailer_pos) = self.raw_data.iter().position(|&b| b == 0x3B) {
-            let mut new_data = self.raw_data[0..trailer_pos].to_vec();
-            new_data.extend_from_slice(&extension);
-            new_data.push(0x3B); // Trailer
-            self.raw_data = new_data;
-        }
-        
+        write_sub_blocks(&mut extension, &comment_data);
+
+        self.insert_before_trailer(&extension)
+    }
+
+    /// Add ZIP data embedded in an application extension with a custom 11-byte identifier
+    ///
+    /// The identifier is 8 bytes of application name plus a 3-byte authentication code,
+    /// like `NETSCAPE2.0`. Decoders skip application extensions they don't recognise.
+    pub fn add_zip_application_extension(&mut self, app_id: &[u8; 11], zip_data: &[u8]) -> PolyglotResult<()> {
+        // Build application extension: 0x21 0xFF + 11-byte identifier block + sub-blocks + 0x00
+        let mut extension = vec![0x21, 0xFF, 0x0B];
+        extension.extend_from_slice(app_id);
+        write_sub_blocks(&mut extension, zip_data);
+
+        self.insert_before_trailer(&extension)
+    }
+
+    /// Data of the first application extension with the given identifier, with sub-blocks reassembled
+    pub fn extract_application_extension(&self, app_id: &[u8; 11]) -> PolyglotResult<Option<Vec<u8>>> {
+        extract_application_extension(&self.raw_data, app_id)
+    }
+
+    /// Insert an extension before the trailer, located by walking the block structure
+    fn insert_before_trailer(&mut self, extension: &[u8]) -> PolyglotResult<()> {
+        let trailer_pos = find_trailer(&self.raw_data)?;
+        let mut new_data = self.raw_data[0..trailer_pos].to_vec();
+        new_data.extend_from_slice(extension);
+        new_data.push(0x3B); // Trailer
+        self.raw_data = new_data;
+
         Ok(())
     }
     
@@ -103,18 +135,288 @@ impl GifFile {
     }
 }
 
+/// Find the trailer byte by walking the GIF block structure
+///
+/// A bare search for 0x3B would also match that byte inside image or extension data.
+pub fn find_trailer(data: &[u8]) -> PolyglotResult<usize> {
+    walk_blocks(data).map(|(_, trailer)| trailer)
+}
+
+/// Data of the first application extension with the given identifier, with sub-blocks reassembled
+pub fn extract_application_extension(data: &[u8], app_id: &[u8; 11]) -> PolyglotResult<Option<Vec<u8>>> {
+    let (extensions, _) = walk_blocks(data)?;
+
+    for offset in extensions {
+        // Introducer, application label, 11-byte identifier block
+        if data[offset + 1] == 0xFF && data.get(offset + 2) == Some(&0x0B)
+            && data.get(offset + 3..offset + 14) == Some(app_id.as_slice()) {
+            let payload = read_sub_blocks(data, offset + 14)
+                .ok_or_else(|| PolyglotError::GifParse("truncated application extension".to_string()))?;
+            return Ok(Some(payload));
+        }
+    }
+
+    Ok(None)
+}
+
+/// ZIP carried by a `ZIP_ARCHIVE:` comment extension, or by an application extension whose data is a ZIP
+pub fn extract_embedded_zip(data: &[u8]) -> PolyglotResult<Option<Vec<u8>>> {
+    let (extensions, _) = walk_blocks(data)?;
+
+    for offset in extensions {
+        let payload = match (data[offset + 1], data.get(offset + 2)) {
+            (0xFE, _) => read_sub_blocks(data, offset + 2)
+                .and_then(|comment| comment.strip_prefix(ZIP_COMMENT_PREFIX).map(<[u8]>::to_vec)),
+            (0xFF, Some(0x0B)) => read_sub_blocks(data, offset + 14)
+                .filter(|app_data| app_data.starts_with(&crate::utils::ZIP_LOCAL_SIGNATURE)),
+            _ => None,
+        };
+        if payload.is_some() {
+            return Ok(payload);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Walk the block structure, returning the offsets of all extensions and of the trailer
+fn walk_blocks(data: &[u8]) -> PolyglotResult<(Vec<usize>, usize)> {
+    let mut offset = first_block_offset(data)?;
+    let mut extensions = Vec::new();
+
+    while offset < data.len() {
+        match data[offset] {
+            0x3B => return Ok((extensions, offset)),
+            0x21 => extensions.push(offset),
+            _ => {}
+        }
+        offset = block_end(data, offset)?;
+    }
+
+    Err(missing_trailer())
+}
+
+fn missing_trailer() -> PolyglotError {
+    PolyglotError::GifParse("missing GIF trailer".to_string())
+}
+
+/// Size in bytes of a color table flagged in a screen or image descriptor's packed field
+fn color_table_size(packed: u8) -> usize {
+    3 * (1 << ((packed & 0x07) + 1))
+}
+
+/// Offset of the first block, after the header, logical screen descriptor and global color table
+fn first_block_offset(data: &[u8]) -> PolyglotResult<usize> {
+    // Header (6) + logical screen descriptor (7)
+    if data.len() < 13 {
+        return Err(missing_trailer());
+    }
+
+    let lsd_flags = data[10];
+    if lsd_flags & 0x80 != 0 {
+        Ok(13 + color_table_size(lsd_flags))
+    } else {
+        Ok(13)
+    }
+}
+
+/// Offset just past the extension or image block starting at `offset`
+fn block_end(data: &[u8], offset: usize) -> PolyglotResult<usize> {
+    match data[offset] {
+        // Extension introducer + label, then sub-blocks
+        0x21 => skip_sub_blocks(data, offset + 2).ok_or_else(missing_trailer),
+        0x2C => parse_image_descriptor(data, offset).map(|image| image.end_offset),
+        other => Err(PolyglotError::GifParse(
+            format!("unexpected block introducer 0x{:02x} at offset {}", other, offset)
+        )),
+    }
+}
+
+/// Parse the image descriptor whose 0x2C separator is at `offset`, locating the end of its image data
+///
+/// The descriptor is 10 bytes, followed by the local color table if its packed field flags
+/// one, the LZW minimum code size and the data sub-blocks. Interlacing only changes the row
+/// order inside the LZW data, not the block layout.
+pub fn parse_image_descriptor(data: &[u8], offset: usize) -> PolyglotResult<ImageDescriptor> {
+    let descriptor = data.get(offset..offset + 10).ok_or_else(missing_trailer)?;
+    let field = |at: usize| u16::from_le_bytes([descriptor[at], descriptor[at + 1]]);
+    let packed = descriptor[9];
+    let mut position = offset + 10;
+
+    let local_color_table = if packed & 0x80 != 0 {
+        let size = color_table_size(packed);
+        let table = data.get(position..position + size).ok_or_else(missing_trailer)?.to_vec();
+        position += size;
+        Some(table)
+    } else {
+        None
+    };
+
+    let lzw_min_code_size = *data.get(position).ok_or_else(missing_trailer)?;
+    let data_offset = position + 1;
+    let end_offset = skip_sub_blocks(data, data_offset).ok_or_else(missing_trailer)?;
+
+    Ok(ImageDescriptor {
+        left: field(1),
+        top: field(3),
+        width: field(5),
+        height: field(7),
+        interlaced: packed & 0x40 != 0,
+        local_color_table,
+        lzw_min_code_size,
+        data_offset,
+        end_offset,
+    })
+}
+
+/// Write data as 255-byte sub-blocks followed by the block terminator
+fn write_sub_blocks(output: &mut Vec<u8>, data: &[u8]) {
+    for block in data.chunks(255) {
+        output.push(block.len() as u8);
+        output.extend_from_slice(block);
+    }
+    output.push(0x00);
+}
+
+/// Concatenate the contents of a chain of data sub-blocks
+fn read_sub_blocks(data: &[u8], mut offset: usize) -> Option<Vec<u8>> {
+    let mut result = Vec::new();
+    loop {
+        let block_len = *data.get(offset)? as usize;
+        offset += 1;
+        if block_len == 0 {
+            return Some(result);
+        }
+        result.extend_from_slice(data.get(offset..offset + block_len)?);
+        offset += block_len;
+    }
+}
+
+/// Skip a chain of data sub-blocks, returning the offset after the terminator
+fn skip_sub_blocks(data: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let block_len = *data.get(offset)? as usize;
+        offset += 1;
+        if block_len == 0 {
+            return Some(offset);
+        }
+        offset += block_len;
+    }
+}
+
 impl GifStructure {
+    /// Parse the header, global color table and every block up to the trailer
+    ///
+    /// A missing trailer is tolerated, since embedding adds one back; truncated blocks are not.
     pub fn parse(data: &[u8]) -> PolyglotResult<Self> {
-        // Simplified GIF parsing - just extract header for now
         let header = GifHeader {
             signature: [data[0], data[1], data[2]],
             version: [data[3], data[4], data[5]],
         };
-        
-        Ok(Self {
-            header,
-            global_color_table: None, // Would parse LSD and GCT properly
-            blocks: Vec::new(),        // Would parse all blocks
-        })
+
+        let mut offset = first_block_offset(data)?;
+        if offset > data.len() {
+            return Err(missing_trailer());
+        }
+        let global_color_table = (offset > 13).then(|| data[13..offset].to_vec());
+
+        let mut blocks = Vec::new();
+        let mut images = Vec::new();
+        while offset < data.len() && data[offset] != 0x3B {
+            let end = block_end(data, offset)?;
+            let raw = data[offset..end].to_vec();
+            let block = match data[offset] {
+                0x2C => {
+                    images.push(parse_image_descriptor(data, offset)?);
+                    GifBlock::ImageDescriptor(raw)
+                }
+                _ => match data.get(offset + 1) {
+                    Some(0xF9) => GifBlock::GraphicControlExtension(raw),
+                    Some(0xFE) => GifBlock::CommentExtension(raw),
+                    Some(0x01) => GifBlock::PlainTextExtension(raw),
+                    Some(0xFF) => GifBlock::ApplicationExtension(raw),
+                    _ => GifBlock::Unknown(raw),
+                },
+            };
+            blocks.push(block);
+            offset = end;
+        }
+
+        Ok(Self { header, global_color_table, blocks, images })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::minimal_gif;
+
+    #[test]
+    fn test_comment_inserted_before_real_trailer() {
+        let original = minimal_gif();
+        let mut gif = GifFile::from_data(original.clone()).unwrap();
+
+        gif.add_zip_comment_extension(b"PK\x03\x04").unwrap();
+
+        // Image data (including its 0x3B byte) is untouched and the trailer stays last
+        let image_end = original.len() - 1;
+        assert_eq!(&gif.as_bytes()[..image_end], &original[..image_end]);
+        assert_eq!(&gif.as_bytes()[image_end..image_end + 2], &[0x21, 0xFE]);
+        assert_eq!(gif.as_bytes().last(), Some(&0x3B));
+        assert_eq!(find_trailer(gif.as_bytes()).unwrap(), gif.as_bytes().len() - 1);
+    }
+
+    #[test]
+    fn test_block_after_interlaced_image_is_located() {
+        let mut data = b"GIF89a".to_vec();
+        data.extend_from_slice(&[0x04, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00]); // No global color table
+
+        // Interlaced image with a 4-color local color table; its LZW data holds 0x21 and 0x3B bytes
+        let image_offset = data.len();
+        data.extend_from_slice(&[0x2C, 0x01, 0x00, 0x02, 0x00, 0x04, 0x00, 0x04, 0x00, 0xC1]);
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x3B, 0x21, 0x2C]);
+        data.push(0x02);
+        data.extend_from_slice(&[0x03, 0x21, 0x3B, 0x2C, 0x02, 0x3B, 0x00, 0x00]);
+        let comment_offset = data.len();
+        data.extend_from_slice(&[0x21, 0xFE, 0x02, b'h', b'i', 0x00]);
+        data.push(0x3B);
+
+        let gif = GifFile::from_data(data.clone()).unwrap();
+        let image = &gif.parsed.images[0];
+        assert!(image.interlaced);
+        assert_eq!((image.left, image.top, image.width, image.height), (1, 2, 4, 4));
+        assert_eq!(image.local_color_table.as_deref(), Some(&data[image_offset + 10..image_offset + 22]));
+        assert_eq!(image.lzw_min_code_size, 2);
+        assert_eq!(image.end_offset, comment_offset);
+        assert!(gif.parsed.global_color_table.is_none());
+        assert!(matches!(gif.parsed.blocks.as_slice(), [GifBlock::ImageDescriptor(_), GifBlock::CommentExtension(comment)] if comment[3..5] == *b"hi"));
+
+        let (extensions, trailer) = walk_blocks(&data).unwrap();
+        assert_eq!(extensions, [comment_offset]);
+        assert_eq!(trailer, data.len() - 1);
+    }
+
+    #[test]
+    fn test_missing_trailer_is_an_error() {
+        let mut data = minimal_gif();
+        data.pop(); // Drop the trailer
+
+        let mut gif = GifFile::from_data(data.clone()).unwrap();
+        let result = gif.add_zip_comment_extension(b"PK\x03\x04");
+
+        assert!(matches!(result, Err(PolyglotError::GifParse(ref msg)) if msg == "missing GIF trailer"));
+        assert_eq!(gif.as_bytes(), data.as_slice());
+    }
+
+    #[test]
+    fn test_application_extension_round_trip() {
+        let payload: Vec<u8> = (0..600u32).map(|i| (i % 251) as u8).collect(); // Spans three sub-blocks
+        let mut gif = GifFile::from_data(minimal_gif()).unwrap();
+
+        gif.add_zip_application_extension(b"POLYGLOTZIP", &payload).unwrap();
+
+        assert_eq!(gif.extract_application_extension(b"POLYGLOTZIP").unwrap(), Some(payload));
+        assert_eq!(gif.extract_application_extension(b"NETSCAPE2.0").unwrap(), None);
+        assert_eq!(*gif.as_bytes().last().unwrap(), 0x3B);
     }
 }
@@ -0,0 +1,194 @@
+//! Trailing payload index, so extractors can find payloads without scanning
+//!
+//! The index is the data of a private `plIX` chunk placed directly before IEND.
+//! All integers are big-endian, like the rest of PNG:
+//!
+//! ```text
+//! entry count        u16
+//! per entry:
+//!   offset           u64   absolute file offset of the payload's first byte
+//!   length           u64
+//!   format           u8    0 unknown, 1 PNG, 2 ZIP, 3 WAV, 4 FLAC, 5 GIF, 6 PDF
+//!   name length      u8
+//!   name             UTF-8
+//! index length       u32   length of the whole chunk data, this trailer included
+//! magic              "PGIX"
+//! ```
+//!
+//! The trailer lets a reader find the chunk backwards from IEND without walking
+//! the file. The chunk is not safe-to-copy: editors that rewrite the image must
+//! drop it, since its offsets would no longer hold.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use crate::utils::{read_u32_be, FileFormat};
+use crate::{PolyglotError, PolyglotResult};
+
+/// Chunk type of the index: ancillary, private, not safe-to-copy
+pub const INDEX_CHUNK_TYPE: [u8; 4] = *b"plIX";
+
+/// Magic closing the index data
+const INDEX_MAGIC: &[u8; 4] = b"PGIX";
+
+/// Index length and magic
+const TRAILER_SIZE: usize = 8;
+
+/// One embedded payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub offset: u64,
+    pub length: u64,
+    pub format: FileFormat,
+    pub name: String, // At most 255 bytes
+}
+
+/// Every payload embedded in a polyglot
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayloadIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+impl PayloadIndex {
+    /// Serialize to the index chunk's data
+    pub fn to_bytes(&self) -> PolyglotResult<Vec<u8>> {
+        let count = u16::try_from(self.entries.len()).map_err(|_| PolyglotError::InvalidInput(
+            format!("payload index is limited to 65535 entries, got {}", self.entries.len())
+        ))?;
+
+        let mut output = count.to_be_bytes().to_vec();
+        for entry in &self.entries {
+            let name_len = u8::try_from(entry.name.len()).map_err(|_| PolyglotError::InvalidInput(
+                format!("payload name {:?} is longer than 255 bytes", entry.name)
+            ))?;
+            output.extend_from_slice(&entry.offset.to_be_bytes());
+            output.extend_from_slice(&entry.length.to_be_bytes());
+            output.push(format_code(entry.format));
+            output.push(name_len);
+            output.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let index_len = (output.len() + TRAILER_SIZE) as u32;
+        output.extend_from_slice(&index_len.to_be_bytes());
+        output.extend_from_slice(INDEX_MAGIC);
+        Ok(output)
+    }
+
+    /// Parse the index chunk's data
+    pub fn parse(data: &[u8]) -> PolyglotResult<Self> {
+        let truncated = || PolyglotError::PngParse("payload index is truncated".to_string());
+        if data.len() < 2 + TRAILER_SIZE || !data.ends_with(INDEX_MAGIC) {
+            return Err(PolyglotError::PngParse("payload index is missing its trailer".to_string()));
+        }
+
+        let count = u16::from_be_bytes([data[0], data[1]]);
+        let mut offset = 2;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let fixed = data.get(offset..offset + 18).ok_or_else(truncated)?;
+            let name_len = fixed[17] as usize;
+            let name = data.get(offset + 18..offset + 18 + name_len).ok_or_else(truncated)?;
+            entries.push(IndexEntry {
+                offset: u64::from_be_bytes(fixed[0..8].try_into().expect("8 bytes")),
+                length: u64::from_be_bytes(fixed[8..16].try_into().expect("8 bytes")),
+                format: format_from_code(fixed[16]),
+                name: String::from_utf8_lossy(name).into_owned(),
+            });
+            offset += 18 + name_len;
+        }
+
+        if offset + TRAILER_SIZE != data.len() {
+            return Err(PolyglotError::PngParse("payload index length does not match its entries".to_string()));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Data of every payload, sliced out of the polyglot
+    pub fn payloads<'a>(&self, data: &'a [u8]) -> PolyglotResult<Vec<&'a [u8]>> {
+        self.entries.iter()
+            .map(|entry| {
+                let start = usize::try_from(entry.offset).ok();
+                let end = start.zip(usize::try_from(entry.length).ok()).and_then(|(start, length)| start.checked_add(length));
+                start.zip(end)
+                    .and_then(|(start, end)| data.get(start..end))
+                    .ok_or_else(|| PolyglotError::PngParse(format!("indexed payload {:?} lies outside the file", entry.name)))
+            })
+            .collect()
+    }
+}
+
+/// Read the index from the chunk directly before IEND, without walking the file
+///
+/// Returns `None` when the file doesn't end with an index chunk and IEND.
+pub fn read_trailing_index(data: &[u8]) -> PolyglotResult<Option<PayloadIndex>> {
+    // IEND is always 12 bytes: zero length, type, CRC
+    if data.len() < 12 + 12 + TRAILER_SIZE || &data[data.len() - 8..data.len() - 4] != b"IEND" {
+        return Ok(None);
+    }
+
+    let index_end = data.len() - 12 - 4; // Index chunk's CRC precedes IEND
+    if &data[index_end - 4..index_end] != INDEX_MAGIC {
+        return Ok(None);
+    }
+    let index_len = read_u32_be(data, index_end - TRAILER_SIZE) as usize;
+    let Some(index_start) = index_end.checked_sub(index_len).filter(|&start| start >= 8) else {
+        return Ok(None);
+    };
+    if data[index_start - 4..index_start] != INDEX_CHUNK_TYPE || read_u32_be(data, index_start - 8) as usize != index_len {
+        return Ok(None);
+    }
+
+    let stored_crc = read_u32_be(data, index_end);
+    let crc = crate::utils::calculate_crc32(&data[index_start - 4..index_end]);
+    if crc != stored_crc {
+        return Err(PolyglotError::CrcMismatch("plIX".to_string()));
+    }
+
+    PayloadIndex::parse(&data[index_start..index_end]).map(Some)
+}
+
+/// Stable byte code for a format in the index
+fn format_code(format: FileFormat) -> u8 {
+    match format {
+        FileFormat::Unknown => 0,
+        FileFormat::Png => 1,
+        FileFormat::Zip => 2,
+        FileFormat::Wav => 3,
+        FileFormat::Flac => 4,
+        FileFormat::Gif => 5,
+        FileFormat::Pdf => 6,
+    }
+}
+
+/// Format for an index byte code; unknown codes read as `FileFormat::Unknown`
+fn format_from_code(code: u8) -> FileFormat {
+    match code {
+        1 => FileFormat::Png,
+        2 => FileFormat::Zip,
+        3 => FileFormat::Wav,
+        4 => FileFormat::Flac,
+        5 => FileFormat::Gif,
+        6 => FileFormat::Pdf,
+        _ => FileFormat::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_layout_round_trip() {
+        let index = PayloadIndex {
+            entries: alloc::vec![IndexEntry { offset: 0x0102, length: 3, format: FileFormat::Zip, name: "a.zip".to_string() }],
+        };
+        let bytes = index.to_bytes().unwrap();
+
+        assert_eq!(&bytes[..2], &[0, 1]); // Entry count
+        assert_eq!(&bytes[2..10], &[0, 0, 0, 0, 0, 0, 0x01, 0x02]); // Offset
+        assert_eq!(&bytes[18..20], &[2, 5]); // Format code, name length
+        assert_eq!(&bytes[bytes.len() - 8..], &[0, 0, 0, 33, b'P', b'G', b'I', b'X']);
+        assert_eq!(PayloadIndex::parse(&bytes).unwrap(), index);
+
+        assert!(PayloadIndex::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
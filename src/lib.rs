@@ -5,62 +5,178 @@
 //!
 //! The core concept is embedding ZIP archive data into the PNG's IDAT chunk
 //! while maintaining valid checksums and offsets in both formats.
+//!
+//! The byte-level parsers (`png`, `zip`, `wav`, `utils`) only need `alloc`. File IO,
+//! decoding and everything built on top of them sit behind the default `std` feature.
+//! zlib and pixel decoding (`flate2`, `png`) additionally need the default `compression`
+//! feature; without it those APIs still exist but return `PolyglotError::feature_disabled`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{format, string::{String, ToString}};
 
 // Public API exports
+#[cfg(feature = "std")]
 pub mod cli;
 pub mod png;
+pub mod index;
+pub mod signing;
+#[cfg(feature = "std")]
 pub mod gif;
+#[cfg(feature = "std")]
 pub mod flac;
 pub mod wav;
+#[cfg(feature = "std")]
+pub mod pdf;
 pub mod zip;
+#[cfg(feature = "std")]
 pub mod polyglot;
 pub mod utils;
+#[cfg(feature = "std")]
 pub mod extract;
-
-pub use polyglot::{PolyglotCreator, create_png_wav_polyglot, create_png_flac_polyglot};
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
+
+#[cfg(feature = "std")]
+pub use polyglot::{PolyglotCreator, EmbeddingMethod, create_png_wav_polyglot, create_png_flac_polyglot, verify_roundtrip};
+#[cfg(feature = "std")]
 pub use extract::{validate_polyglot, extract_zip_from_png, extract_wav_from_png};
 
 /// Result type alias for polyglot operations
 pub type PolyglotResult<T> = Result<T, PolyglotError>;
 
 /// Comprehensive error type for the polyglot tool
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub enum PolyglotError {
-    #[error("PNG parse error: {0}")]
+    #[cfg_attr(feature = "std", error("PNG parse error: {0}"))]
     PngParse(String),
 
-    #[error("ZIP parse error: {0}")]
+    #[cfg_attr(feature = "std", error("ZIP parse error: {0}"))]
     ZipParse(String),
 
-    #[error("WAV parse error: {0}")]
+    #[cfg_attr(feature = "std", error("WAV parse error: {0}"))]
     WavParse(String),
 
-    #[error("CRC mismatch in chunk {0}")]
+    #[cfg_attr(feature = "std", error("GIF parse error: {0}"))]
+    GifParse(String),
+
+    #[cfg_attr(feature = "std", error("PDF parse error: {0}"))]
+    PdfParse(String),
+
+    #[cfg_attr(feature = "std", error("CRC mismatch in chunk {0}"))]
     CrcMismatch(String),
 
-    #[error("No IDAT chunk found")]
+    #[cfg_attr(feature = "std", error("No IDAT chunk found"))]
     NoIdatChunk,
 
-    #[error("Invalid RIFF header")]
+    #[cfg_attr(feature = "std", error("Invalid RIFF header"))]
     InvalidRiffHeader,
 
-    #[error("Chunk not found: {0}")]
+    #[cfg_attr(feature = "std", error("Chunk not found: {0}"))]
     ChunkNotFound(String),
 
-    #[error("Size overflow in RIFF file")]
+    #[cfg_attr(feature = "std", error("Size overflow in RIFF file"))]
     SizeOverflow,
 
+    #[cfg(feature = "std")]
     #[error("Input file error: {0}")]
     InputFile(#[from] std::io::Error),
 
-    #[error("Polyglot creation failed: {0}")]
+    #[cfg_attr(feature = "std", error("Polyglot creation failed: {0}"))]
     CreationFailed(String),
 
-    #[error("Validation failed: {0}")]
+    #[cfg_attr(feature = "std", error("Validation failed: {0}"))]
     ValidationFailed(String),
 
-    #[error("Invalid input: {0}")]
+    #[cfg_attr(feature = "std", error("Invalid input: {0}"))]
     InvalidInput(String),
+
+    #[cfg_attr(feature = "std", error("Invalid usage: {0}"))]
+    Usage(String),
+
+    /// Returned by the stand-ins for APIs left out of the build, e.g. without `compression`
+    ///
+    /// Kept apart from `InvalidInput` so callers can tell a build missing a feature from
+    /// input they could fix, and the CLI can give it its own exit code.
+    #[cfg_attr(feature = "std", error("feature `{0}` not enabled"))]
+    FeatureDisabled(String),
+
+    #[cfg_attr(feature = "std", error("Decode error: {0}"))]
+    DecodeError(String),
+}
+
+#[cfg(feature = "compression")]
+impl From<::png::DecodingError> for PolyglotError {
+    fn from(err: ::png::DecodingError) -> Self {
+        PolyglotError::DecodeError(format!("PNG: {err}"))
+    }
+}
+
+#[cfg(feature = "compression")]
+impl From<::png::EncodingError> for PolyglotError {
+    fn from(err: ::png::EncodingError) -> Self {
+        PolyglotError::CreationFailed(format!("PNG encoding failed: {err}"))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<hound::Error> for PolyglotError {
+    fn from(err: hound::Error) -> Self {
+        PolyglotError::DecodeError(format!("WAV: {err}"))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<::zip::result::ZipError> for PolyglotError {
+    fn from(err: ::zip::result::ZipError) -> Self {
+        match err {
+            ::zip::result::ZipError::Io(io_err) => PolyglotError::InputFile(io_err),
+            other => PolyglotError::ZipParse(other.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl From<flate2::DecompressError> for PolyglotError {
+    fn from(err: flate2::DecompressError) -> Self {
+        PolyglotError::DecodeError(format!("zlib: {err}"))
+    }
+}
+
+#[cfg(feature = "compression")]
+impl From<flate2::CompressError> for PolyglotError {
+    fn from(err: flate2::CompressError) -> Self {
+        PolyglotError::CreationFailed(format!("deflate failed: {err}"))
+    }
+}
+
+impl PolyglotError {
+    /// Error for an API whose Cargo feature was left out of the build
+    pub fn feature_disabled(feature: &str) -> Self {
+        PolyglotError::FeatureDisabled(feature.to_string())
+    }
+
+    /// Error for ZIP operations on data without a ZIP signature, naming the format it sniffs as
+    pub fn not_zip(data: &[u8]) -> Self {
+        let found = match utils::sniff_format(data) {
+            utils::FileFormat::Unknown => "unrecognised data",
+            format => format.name(),
+        };
+        PolyglotError::ZipParse(format!("Invalid ZIP signature: expected ZIP, found {}", found))
+    }
+}
+
+/// Without `std` there is no `Error` trait to derive, so messages fall back to the variant
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for PolyglotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
+    }
 }
 
 impl PolyglotError {
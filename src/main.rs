@@ -1,14 +1,45 @@
  use std::path::Path;
 use clap::{Parser, Subcommand};
-use rust_polyglot::{cli, polyglot::{PolyglotCreator, create_png_wav_polyglot, create_true_bidirectional_png_wav_polyglot}, utils};
-use rust_polyglot::extract::{validate_polyglot, extract_zip_from_png, extract_wav_from_png};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use rust_polyglot::{cli, signing, polyglot::{EmbeddingMethod, PolyglotCreator, create_png_wav_polyglot, create_true_bidirectional_png_wav_polyglot, create_zip_comment_polyglot, create_png_in_png_polyglot, create_triple, merge, verify_embedded_payload}, utils};
+use rust_polyglot::extract::{validate_polyglot, locate_anomaly, extract_payload_from_reader, verify_embedded_zip_offsets, extract_zip_from_png, extract_wav_from_png, extract_clean_wav_from_png, extract_png_from_zip_comment_data, extract_png_from_png_data, extract_zip_from_png_data, repair_wav_riff_size, split_polyglot, trailing_bytes_after_eocd, derive_output_path, extract_split_payload, extract_payload_at, extract_from_carrier};
+use rust_polyglot::png::PngFile;
+use rust_polyglot::{PolyglotError, PolyglotResult};
 
 // Find RIFF signature ("RIFF") in data, returning offset
 fn find_riff_signature(data: &[u8]) -> Option<usize> {
-    const RIFF_SIG: [u8; 4] = *b"RIFF";
-    data.windows(4).position(|w| w == RIFF_SIG)
+    data.windows(4).position(|w| w == utils::RIFF_SIGNATURE)
 }
 
+// Re-read a freshly written polyglot and check its payload comes back intact, deleting it if not
+fn verify_written_output(output_path: &Path, embedded: &[u8], method: EmbeddingMethod) -> PolyglotResult<()> {
+    let result = std::fs::read(output_path)
+        .map_err(PolyglotError::from)
+        .and_then(|written| verify_embedded_payload(&written, embedded, method));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(output_path);
+        return Err(PolyglotError::ValidationFailed(format!("{} failed verification and was removed: {}", output_path.display(), e)));
+    }
+    println!("[OK] Payload round trip verified");
+    Ok(())
+}
+
+// Parse --method into an EmbeddingMethod, offering exactly the names it defines
+fn method_parser() -> impl TypedValueParser<Value = EmbeddingMethod> {
+    PossibleValuesParser::new(EmbeddingMethod::names())
+        .map(|name| name.parse::<EmbeddingMethod>().expect("possible values are method names"))
+}
+
+// Help for options whose zlib-dependent parts are only offered with the `compression` feature
+#[cfg(feature = "compression")]
+const METHOD_HELP: &str = "Embedding method; run `methods` for the formats and trade-offs of each. idat adds a blank IDAT to carriers without one";
+#[cfg(not(feature = "compression"))]
+const METHOD_HELP: &str = "Embedding method; run `methods` for the formats and trade-offs of each";
+#[cfg(feature = "compression")]
+const EXTRACT_INPUT_HELP: &str = "Path to polyglot PNG file, or - to stream a PNG-dominant polyglot from stdin";
+#[cfg(not(feature = "compression"))]
+const EXTRACT_INPUT_HELP: &str = "Path to polyglot PNG file";
+
 #[derive(Parser)]
 #[command(name = "rust-polyglot")]
 #[command(about = "Create and manipulate PNG/ZIP polyglots")]
@@ -29,31 +60,97 @@ enum Commands {
         #[arg(short, long)]
         zip: Option<String>,
 
+        /// Directory to archive as the ZIP payload (alternative to --zip)
+        #[arg(long, conflicts_with_all = ["zip", "wav", "pdf"])]
+        dir: Option<String>,
+
         /// Path to input WAV file (alternative to --zip for PNG+WAV polyglots)
         #[arg(long)]
         wav: Option<String>,
 
+        /// PNG to hide in a private chunk of the --png carrier (PNG-in-PNG)
+        #[arg(long, conflicts_with_all = ["zip", "dir", "wav", "pdf", "hmac_key"])]
+        inner_png: Option<String>,
+
+        /// Path to input PDF file (with --zip, creates a PNG+ZIP+PDF polyglot)
+        #[arg(long, requires = "zip", conflicts_with = "wav")]
+        pdf: Option<String>,
+
         /// Path for output polyglot file (must end with .png or .zip)
         #[arg(short, long)]
         output: String,
 
-        /// Embedding method: idat (PNG-dominant, data in image data), text (PNG-dominant, data in metadata - RECOMMENDED), zip (ZIP-dominant, PNG in archive), bidirectional (true bidirectional PNG+WAV)
-        #[arg(short, long, default_value = "text")]
-        method: String,
+        #[arg(short, long, default_value = "text", value_parser = method_parser(), help = METHOD_HELP)]
+        method: EmbeddingMethod,
+
+        /// Write a JSON record of inputs, method, payload offset and output hash (PNG+ZIP only)
+        #[arg(long)]
+        log: Option<String>,
+
+        /// Abort before writing if the output would exceed this many bytes (PNG+ZIP only)
+        #[arg(long)]
+        max_size: Option<usize>,
+
+        /// After writing, check that every ZIP central directory entry points at a local header (ZIP payloads only)
+        #[arg(long)]
+        strict: bool,
+
+        /// Sign the ZIP payload with HMAC-SHA256 under this hex key, stored in a plHM chunk (PNG-dominant PNG+ZIP only)
+        #[arg(long, conflicts_with_all = ["wav", "pdf"])]
+        hmac_key: Option<String>,
+
+        /// Remove the carrier's ancillary metadata chunks (tEXt, eXIf, tIME, ...) before embedding (PNG+ZIP only)
+        #[arg(long)]
+        strip_metadata: bool,
+
+        /// Unix permissions (octal, e.g. 755) stored for the image entry of a ZIP-dominant polyglot; defaults to 644
+        #[arg(long)]
+        mode: Option<String>,
+
+        /// Mark the image entry of a ZIP-dominant polyglot as text in its internal attributes
+        #[arg(long)]
+        text_entry: bool,
+
+        /// After writing, re-read the output and check the payload extracts byte-for-byte; the output is deleted if not
+        #[arg(long, conflicts_with_all = ["pdf", "inner_png"])]
+        verify: bool,
     },
 
     /// Extract the ZIP archive from a polyglot file
     Extract {
-        /// Path to polyglot PNG file
-        #[arg(short, long)]
+        #[arg(short, long, help = EXTRACT_INPUT_HELP)]
         input: String,
 
         /// Path for extracted ZIP file
-        #[arg(short, long)]
-        output: String,
+        #[arg(short, long, required_unless_present_any = ["carrier", "output_dir"])]
+        output: Option<String>,
+
+        /// Directory to extract into, naming the file after the input with the payload's extension
+        #[arg(long, conflicts_with_all = ["output", "carrier", "payload"])]
+        output_dir: Option<String>,
+
+        /// Path for the carrier PNG with the payload stripped (use with --payload)
+        #[arg(long, requires = "payload", conflicts_with = "output")]
+        carrier: Option<String>,
+
+        /// Path for the extracted payload (use with --carrier)
+        #[arg(long, requires = "carrier", conflicts_with = "output")]
+        payload: Option<String>,
+
+        /// Strip embedded PNG chunks from an extracted WAV and restore its RIFF size
+        #[arg(long)]
+        clean: bool,
+
+        /// Check the ZIP payload against its plHM HMAC-SHA256 tag under this hex key before writing it
+        #[arg(long, conflicts_with_all = ["carrier", "payload", "clean"])]
+        verify_hmac: Option<String>,
+
+        /// Extract only the n-th payload (from 0, in file order) of a multi-payload polyglot
+        #[arg(long, conflicts_with_all = ["carrier", "payload", "clean", "verify_hmac", "output_dir"])]
+        index: Option<usize>,
     },
 
-    /// Validate that a file is a valid PNG/ZIP polyglot (PNG+WAV validation not supported)
+    /// Validate that a file is a valid PNG/ZIP or PNG/WAV polyglot (WAV sides are decoded)
     Validate {
         /// Path to potential polyglot file
         #[arg(short, long)]
@@ -63,34 +160,161 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
+
+    /// List the supported embedding methods and their trade-offs
+    Methods,
+
+    /// Combine the payloads of two polyglot PNGs into one file, keeping the first one's image
+    Merge {
+        /// First polyglot; its carrier image is kept
+        #[arg(long)]
+        first: String,
+
+        /// Second polyglot
+        #[arg(long)]
+        second: String,
+
+        /// Output path for the merged polyglot
+        #[arg(short, long)]
+        output: String,
+    },
+
+    /// Rewrite a WAV's RIFF size to match its chunks, standalone or embedded in a PNG
+    Repair {
+        /// Path to the WAV or PNG+WAV polyglot
+        #[arg(short, long)]
+        input: String,
+
+        /// Output path for the repaired file
+        #[arg(short, long)]
+        output: String,
+    },
+
+    /// Show a file's format and, for PNGs and FLACs, its chunks or metadata blocks
+    Info {
+        /// Path to input file
+        #[arg(short, long)]
+        input: String,
+    },
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
     let cli = Cli::parse();
 
+    if let Err(err) = run(cli) {
+        eprintln!("Error: {}", err);
+        std::process::exit(cli::exit_code(&err));
+    }
+}
+
+fn run(cli: Cli) -> PolyglotResult<()> {
     match cli.command {
-        Commands::Create { png, zip, wav, output, method } => {
+        Commands::Create { png, zip, dir, wav, inner_png, pdf, output, method, log, max_size, strict, hmac_key, mode, text_entry, strip_metadata, verify } => {
             let png_path = Path::new(&png);
             let output_path = Path::new(&output);
+            let has_zip_payload = zip.is_some() || dir.is_some();
+
+            if log.is_some() && !has_zip_payload {
+                return Err(PolyglotError::Usage("--log is only supported for PNG+ZIP polyglots".to_string()));
+            }
+
+            if max_size.is_some() && !has_zip_payload {
+                return Err(PolyglotError::Usage("--max-size is only supported for PNG+ZIP polyglots".to_string()));
+            }
+
+            if strict && !has_zip_payload {
+                return Err(PolyglotError::Usage("--strict is only supported for polyglots with a --zip payload".to_string()));
+            }
+
+            let hmac_key = hmac_key.as_deref().map(signing::parse_hex_key).transpose()?;
+            if hmac_key.is_some() && !has_zip_payload {
+                return Err(PolyglotError::Usage("--hmac-key is only supported for PNG+ZIP polyglots".to_string()));
+            }
+
+            if strip_metadata && !has_zip_payload {
+                return Err(PolyglotError::Usage("--strip-metadata is only supported for PNG+ZIP polyglots".to_string()));
+            }
+
+            let mode = mode
+                .map(|m| u32::from_str_radix(&m, 8).map_err(|_| PolyglotError::Usage(format!("--mode must be an octal permission value, got {}", m))))
+                .transpose()?;
+            if mode.is_some() && !(has_zip_payload && method == EmbeddingMethod::Zip) {
+                return Err(PolyglotError::Usage("--mode is only supported for ZIP-dominant PNG+ZIP polyglots (--method zip)".to_string()));
+            }
+            if text_entry && !(has_zip_payload && method == EmbeddingMethod::Zip) {
+                return Err(PolyglotError::Usage("--text-entry is only supported for ZIP-dominant PNG+ZIP polyglots (--method zip)".to_string()));
+            }
+
+            if verify && wav.is_some() && method != EmbeddingMethod::Bidirectional {
+                return Err(PolyglotError::Usage("--verify is not supported for extension-dominant PNG+WAV polyglots; use --method bidirectional".to_string()));
+            }
+
+            // PNG+WAV picks its dominant format from the extension, so only other methods can conflict
+            let checked_method = if wav.is_some() && method != EmbeddingMethod::Bidirectional { None } else { Some(method) };
+            let output_check = cli::check_output_name(output_path, checked_method);
+            for warning in &output_check.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+
+            if let (Some(zip_path), Some(pdf_path)) = (&zip, &pdf) {
+                if log.is_some() || max_size.is_some() || strip_metadata {
+                    return Err(PolyglotError::Usage("--log, --max-size and --strip-metadata are not supported with --pdf".to_string()));
+                }
+
+                println!("Creating PNG+ZIP+PDF polyglot: {} + {} + {} -> {}", png, zip_path, pdf_path, output);
+                create_triple(png_path, Path::new(zip_path), Path::new(pdf_path), output_path)?;
+                if strict {
+                    verify_embedded_zip_offsets(&std::fs::read(output_path)?)?;
+                    println!("[OK] ZIP offsets verified");
+                }
+                println!("PNG+ZIP+PDF polyglot created successfully!");
+                return Ok(());
+            }
+
+            if let Some(inner_path) = &inner_png {
+                if log.is_some() || max_size.is_some() || strict {
+                    return Err(PolyglotError::Usage("--log, --max-size and --strict are not supported with --inner-png".to_string()));
+                }
+
+                println!("Creating PNG-in-PNG polyglot: {} + {} -> {}", png, inner_path, output);
+                create_png_in_png_polyglot(png_path, Path::new(inner_path), output_path)?;
+                println!("PNG-in-PNG polyglot created successfully!");
+                return Ok(());
+            }
+
+            if method == EmbeddingMethod::Comment {
+                if has_zip_payload || wav.is_some() {
+                    return Err(PolyglotError::Usage("the comment method takes only --png (the ZIP is generated empty)".to_string()));
+                }
+
+                println!("Creating ZIP-comment polyglot: {} -> {}", png, output);
+                create_zip_comment_polyglot(png_path, output_path)?;
+                if verify {
+                    verify_written_output(output_path, &std::fs::read(png_path)?, EmbeddingMethod::Comment)?;
+                }
+                println!("ZIP-comment polyglot created successfully!");
+                return Ok(());
+            }
 
             // Check if user wants true bidirectional polyglot
-            if method == "bidirectional" {
+            if method == EmbeddingMethod::Bidirectional {
                 if let Some(wav_path) = wav {
                     // True bidirectional PNG+WAV polyglot
                     let wav_path = Path::new(&wav_path);
 
                     // Validate inputs - allow flexibility for bidirectional mode
-                    if !output_path.extension().is_some_and(|ext| ext == "png" || ext == "wav") {
-                        eprintln!("Error: Output file for bidirectional polyglot can have .png or .wav extension");
-                        std::process::exit(1);
+                    if !matches!(output_check.dominant, Some(utils::FileFormat::Png | utils::FileFormat::Wav)) {
+                        return Err(PolyglotError::Usage("Output file for bidirectional polyglot can have .png or .wav extension".to_string()));
                     }
 
                     println!("Creating truly bidirectional PNG+WAV polyglot (custom format): {} + {} -> {}", png, wav_path.display(), output);
                     create_true_bidirectional_png_wav_polyglot(png_path, wav_path, output_path)?;
+                    if verify {
+                        verify_written_output(output_path, &std::fs::read(wav_path)?, EmbeddingMethod::Bidirectional)?;
+                    }
                     println!("True bidirectional PNG+WAV polyglot created successfully!");
                 } else {
-                    eprintln!("Error: --wav parameter required for bidirectional mode");
-                    std::process::exit(1);
+                    return Err(PolyglotError::Usage("--wav parameter required for bidirectional mode".to_string()));
                 }
             } else {
                 // Regular polyglot creation logic
@@ -102,53 +326,145 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     // PNG+WAV polyglot
                     let wav_path = Path::new(&wav_path);
 
-                    // Choose approach based on extension:
-                    // .png → PNG-dominant (PNG + embedded WAV)
-                    // .wav → WAV-dominant (WAV + embedded PNG)
-                    let png_dominant = output_path.extension().is_some_and(|ext| ext == "png");
-
-                    // Extensions are validated - proceed
-
+                    // The output extension picks the approach: .png embeds the WAV, .wav embeds the PNG
                     println!("Creating PNG+WAV bidirectional polyglot: {} + {} -> {}", png, wav_path.display(), output);
                     create_png_wav_polyglot(png_path, wav_path, output_path)?;
                     println!("PNG+WAV polyglot created successfully!");
 
-                } else if let Some(zip_path) = zip {
-                    // PNG+ZIP polyglot (original)
-                    let zip_path = Path::new(&zip_path);
+                } else if let Some(payload_path) = zip.as_ref().or(dir.as_ref()) {
+                    // PNG+ZIP polyglot, archiving the directory first if one was given
+                    let payload_path = Path::new(payload_path);
 
                     // Validate inputs
-                    if !output_path.extension().is_some_and(|ext| ext == "png" || ext == "zip") {
-                        eprintln!("Error: Output file must have .png or .zip extension");
-                        std::process::exit(1);
+                    if !matches!(output_check.dominant, Some(utils::FileFormat::Png | utils::FileFormat::Zip)) {
+                        return Err(PolyglotError::Usage("Output file must have .png or .zip extension".to_string()));
                     }
 
-                    println!("Creating polyglot: {} + {} -> {}", png, zip_path.display(), output);
-                    let mut creator = PolyglotCreator::new(png_path, zip_path)?;
-                    creator.create_polyglot_with_method(output_path, &method)?;
+                    println!("Creating polyglot: {} + {} -> {}", png, payload_path.display(), output);
+                    let mut creator = if dir.is_some() {
+                        PolyglotCreator::from_directory(png_path, payload_path)?
+                    } else {
+                        PolyglotCreator::new(png_path, payload_path)?
+                    };
+                    if strip_metadata {
+                        let removed = creator.strip_carrier_metadata()?;
+                        println!("Stripped {} metadata chunk(s) from {}", removed, png);
+                    }
+                    creator.set_max_output_size(max_size);
+                    creator.set_hmac_key(hmac_key);
+                    if let Some(mode) = mode {
+                        creator.set_entry_mode(mode)?;
+                    }
+                    creator.set_entry_text(text_entry);
+                    let mut method = method;
+                    let requested_method = method;
+                    if method == EmbeddingMethod::Idat {
+                        match creator.synthesize_idat() {
+                            Ok(true) => println!("Notice: {} has no IDAT chunk, adding a blank one sized to its IHDR", png),
+                            Ok(false) => {}
+                            Err(err) => {
+                                method = creator.effective_method(requested_method);
+                                println!("Notice: {} has no IDAT chunk and a blank one can't be added ({}), using the {} method instead of {}", png, err, method, requested_method);
+                            }
+                        }
+                    }
+                    if let Some(log_path) = log {
+                        let creation_log = creator.create_polyglot_with_log(output_path, method)?;
+                        creation_log.write_to_file(Path::new(&log_path))?;
+                        println!("Creation log written to {}", log_path);
+                    } else {
+                        creator.create_polyglot_with_method(output_path, method)?;
+                    }
+                    if strict {
+                        verify_embedded_zip_offsets(&std::fs::read(output_path)?)?;
+                        println!("[OK] ZIP offsets verified");
+                    }
+                    if verify {
+                        let embedded = if method == EmbeddingMethod::Zip { creator.png().as_bytes() } else { creator.zip().as_bytes() };
+                        verify_written_output(output_path, embedded, method)?;
+                    }
                     println!("PNG+ZIP polyglot created successfully!");
 
                 } else {
-                    eprintln!("Error: Must specify either --zip or --wav");
-                    std::process::exit(1);
+                    return Err(PolyglotError::Usage("Must specify --zip, --dir or --wav".to_string()));
                 }
             }
         }
 
-        Commands::Extract { input, output } => {
+        Commands::Extract { input, output, output_dir, carrier, payload, clean, verify_hmac, index } => {
             let input_path = Path::new(&input);
+
+            if let (Some(carrier), Some(payload)) = (carrier, payload) {
+                println!("Splitting polyglot: {} -> carrier {} + payload {}", input, carrier, payload);
+                split_polyglot(input_path, Path::new(&carrier), Path::new(&payload))?;
+                println!("Carrier and payload written successfully!");
+                return Ok(());
+            }
+
+            let output = match output_dir {
+                Some(dir) => {
+                    if input == "-" {
+                        return Err(PolyglotError::Usage("--output-dir needs an input file name to derive the output from".to_string()));
+                    }
+                    std::fs::create_dir_all(&dir)?;
+                    derive_output_path(input_path, &std::fs::read(input_path)?, Path::new(&dir))?.display().to_string()
+                }
+                None => output.expect("clap requires --output without --carrier/--payload or --output-dir"),
+            };
             let output_path = Path::new(&output);
 
+            if input == "-" {
+                if clean || verify_hmac.is_some() || index.is_some() {
+                    return Err(PolyglotError::Usage("--clean, --verify-hmac and --index are not supported when streaming from stdin".to_string()));
+                }
+                let payload = extract_payload_from_reader(std::io::stdin().lock())?;
+                std::fs::write(output_path, &payload)?;
+                println!("Payload streamed from stdin: {} bytes -> {}", payload.len(), output);
+                return Ok(());
+            }
+
             // Determine what to extract based on file content
             let data = std::fs::read(input_path)?;
+
+            if let Some(index) = index {
+                let (entry, payload) = extract_payload_at(&data, index)?;
+                println!("Extracting payload {} ({}, offset {}): {} -> {}", index, entry.format.name(), entry.offset, input, output);
+                std::fs::write(output_path, &payload)?;
+                println!("Payload extracted successfully: {} bytes", payload.len());
+                return Ok(());
+            }
             let is_png = utils::is_png_signature(&data);
 
+            if let Some(hex_key) = &verify_hmac {
+                if !is_png || find_riff_signature(&data[8..]).is_some() {
+                    return Err(PolyglotError::InvalidInput("--verify-hmac is only supported for PNG-dominant PNG+ZIP polyglots".to_string()));
+                }
+                let png = PngFile::from_data(data.clone())?;
+                signing::verify_payload_hmac(&png.parsed, &extract_zip_from_png_data(&data)?, &signing::parse_hex_key(hex_key)?)?;
+                println!("[OK] Payload HMAC verified");
+            }
+
             if is_png {
                 // PNG-dominant polyglot - check which data is embedded
-                if find_riff_signature(&data[8..]).is_some() {
+                let carries_png = PngFile::from_data(data.clone())
+                    .is_ok_and(|png| png.payload_chunk().is_some_and(utils::is_png_signature));
+                // Files that aren't framed as split go to the usual extractors; mismatched framing is an error
+                if let Some(split_payload) = extract_split_payload(&data)? {
+                    println!("Reassembling payload split across IDAT and after IEND: {} -> {}", input, output);
+                    std::fs::write(output_path, &split_payload)?;
+                    println!("Payload extracted successfully: {} bytes", split_payload.len());
+                } else if carries_png {
+                    println!("Extracting PNG from PNG-in-PNG polyglot: {} -> {}", input, output);
+                    std::fs::write(output_path, extract_png_from_png_data(&data)?)?;
+                    println!("PNG extracted successfully!");
+                } else if find_riff_signature(&data[8..]).is_some() {
                     // PNG+WAV polyglot
                     println!("Extracting WAV from PNG+WAV polyglot: {} -> {}", input, output);
-                    extract_wav_from_png(input_path, output_path)?;
+                    if clean {
+                        extract_clean_wav_from_png(input_path, output_path)?;
+                    } else {
+                        extract_wav_from_png(input_path, output_path)?;
+                    }
                     println!("WAV extracted successfully!");
                 } else {
                     // Default to ZIP extraction for backward compatibility
@@ -156,7 +472,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     extract_zip_from_png(input_path, output_path)?;
                     println!("ZIP extracted successfully!");
                 }
-            } else if &data[0..4] == b"RIFF" {
+            } else if data.starts_with(&utils::RIFF_SIGNATURE) {
                 // WAV-dominant polyglot - this IS the WAV file, extract PNG from it
                 println!("Extracting PNG from WAV+PNG polyglot: {} -> {}", input, output);
                 // For WAV-dominant polyglots, we'll extract PNG since WAV is the container
@@ -166,14 +482,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     std::fs::write(output_path, png_data)?;
                     println!("PNG extracted successfully!");
                 } else {
-                    eprintln!("No PNG data found in WAV polyglot");
-                    std::process::exit(1);
+                    return Err(PolyglotError::ValidationFailed("No PNG data found in WAV polyglot".to_string()));
                 }
+            } else if data.starts_with(&utils::ZIP_EOCD_SIGNATURE) {
+                // Empty ZIP carrying the PNG in its comment
+                println!("Extracting PNG from ZIP comment: {} -> {}", input, output);
+                std::fs::write(output_path, extract_png_from_zip_comment_data(&data)?)?;
+                println!("PNG extracted successfully!");
+            } else if matches!(utils::sniff_format(&data), utils::FileFormat::Gif | utils::FileFormat::Flac) {
+                let carrier = utils::sniff_format(&data).name();
+                let (format, payload) = extract_from_carrier(&data)?;
+                println!("Extracting {} from {}+{} polyglot: {} -> {}", format.name(), carrier, format.name(), input, output);
+                std::fs::write(output_path, &payload)?;
+                println!("{} extracted successfully!", format.name());
             } else {
                 // For ZIP-dominant cases, fall back to generic handling
-                eprintln!("ZIP-dominant polyglot extraction not yet supported for this interface");
-                eprintln!("Use existing ZIP tools or access via other methods");
-                std::process::exit(1);
+                return Err(PolyglotError::InvalidInput(
+                    "ZIP-dominant polyglot extraction not yet supported; use existing ZIP tools".to_string()
+                ));
             }
         }
 
@@ -183,10 +509,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("Validating polyglot: {}", input);
             let result = validate_polyglot(input_path)?;
 
-            match result {
+            match &result {
                 cli::ValidationResult::Valid => {
                     println!("[OK] File is a valid PNG/ZIP polyglot");
                 }
+                cli::ValidationResult::ValidWavPng => {
+                    println!("[OK] File is a valid PNG/WAV polyglot");
+                }
                 cli::ValidationResult::InvalidPng(reason) => {
                     println!("[ERROR] Not a valid PNG: {}", reason);
                 }
@@ -197,11 +526,64 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("[ERROR] Invalid PNG: {}", png_reason);
                     println!("         Invalid ZIP: {}", zip_reason);
                 }
+                cli::ValidationResult::InvalidWav(reason) => {
+                    println!("[ERROR] WAV does not decode: {}", reason);
+                }
+                cli::ValidationResult::InvalidWavAndPng(wav_reason, png_reason) => {
+                    println!("[ERROR] WAV does not decode: {}", wav_reason);
+                    println!("         Invalid PNG: {}", png_reason);
+                }
             }
 
-            if verbose {
-                println!("Detailed validation information:");
-                // TODO: Add more detailed output
+            let data = std::fs::read(input_path)?;
+            if let Some(trailing) = trailing_bytes_after_eocd(&data).filter(|&trailing| trailing > 0) {
+                println!("[WARN] {} bytes of trailing data after the ZIP end of central directory record", trailing);
+            }
+
+            if verbose && !result.is_valid()
+                && let Some((offset, description)) = locate_anomaly(&data) {
+                println!("Anomaly at offset {} (0x{:x}): {}", offset, offset, description);
+                print!("{}", utils::hexdump(&data, offset, 16));
+            }
+
+            if !result.is_valid() {
+                return Err(PolyglotError::ValidationFailed(format!("{} is not a valid polyglot", input)));
+            }
+        }
+
+        Commands::Methods => {
+            print!("{}", cli::format_method_list());
+        }
+
+        Commands::Merge { first, second, output } => {
+            println!("Merging payloads: {} + {} -> {}", first, second, output);
+            merge(Path::new(&first), Path::new(&second), Path::new(&output))?;
+            println!("Payloads merged successfully!");
+        }
+
+        Commands::Repair { input, output } => {
+            let data = std::fs::read(&input)?;
+            match repair_wav_riff_size(&data)? {
+                Some(repaired) => {
+                    std::fs::write(&output, repaired)?;
+                    println!("[OK] RIFF size rewritten: {}", output);
+                }
+                None => println!("RIFF size already matches the chunks, nothing written"),
+            }
+        }
+
+        Commands::Info { input } => {
+            let data = std::fs::read(&input)?;
+            match utils::sniff_format(&data) {
+                utils::FileFormat::Png => {
+                    let png = PngFile::from_data(data)?;
+                    print!("{}", cli::format_png_info(&png)?);
+                }
+                utils::FileFormat::Flac => {
+                    let flac = rust_polyglot::flac::FlacFile::from_data(data)?;
+                    print!("{}", cli::format_flac_info(&flac));
+                }
+                format => println!("Format: {}, {} bytes", format.name(), data.len()),
             }
         }
     }
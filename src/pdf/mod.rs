@@ -0,0 +1,210 @@
+//! Minimal PDF support for relocating a document inside a polyglot
+
+use crate::{PolyglotError, PolyglotResult};
+
+/// How far from the start a reader will look for the `%PDF` header
+pub const HEADER_SEARCH_WINDOW: usize = 1024;
+
+/// How far from the end a reader will look for `startxref` and `%%EOF`
+pub const TRAILER_SEARCH_WINDOW: usize = 1024;
+
+/// Classic cross-reference table entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XrefEntry {
+    pub object: usize,
+    pub offset: usize,
+    pub generation: u16,
+    pub in_use: bool,
+    entry_position: usize, // File offset of the 20-byte entry
+}
+
+/// Location of the cross-reference data found via `startxref`
+#[derive(Debug, Clone)]
+pub struct XrefTable {
+    pub offset: usize, // Value given by startxref
+    pub entries: Vec<XrefEntry>,
+    startxref_value: std::ops::Range<usize>, // Bytes of the startxref number
+}
+
+/// Parse the last `startxref` and the classic xref table it points at
+///
+/// Cross-reference streams and incremental updates (`/Prev`) are rejected rather than
+/// half-handled, since relocating them would need a full object parser.
+pub fn parse_xref(data: &[u8]) -> PolyglotResult<XrefTable> {
+    let keyword = rfind(data, b"startxref")
+        .ok_or_else(|| PolyglotError::PdfParse("missing startxref".to_string()))?;
+    let value_start = skip_whitespace(data, keyword + b"startxref".len());
+    let value_end = value_start + data[value_start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let offset = parse_number(&data[value_start..value_end])
+        .ok_or_else(|| PolyglotError::PdfParse("startxref is not followed by an offset".to_string()))?;
+
+    if !data.get(offset..).is_some_and(|rest| rest.starts_with(b"xref")) {
+        return Err(PolyglotError::PdfParse(format!(
+            "startxref points at offset {offset}, which is not a classic xref table"
+        )));
+    }
+
+    let mut entries = Vec::new();
+    let truncated = || PolyglotError::PdfParse("xref table ends before its trailer".to_string());
+    let mut position = skip_whitespace(data, offset + 4);
+    while !data.get(position..).ok_or_else(truncated)?.starts_with(b"trailer") {
+        // Subsection header: first object number and entry count
+        let (first, after_first) = read_number(data, position)?;
+        let (count, after_count) = read_number(data, skip_whitespace(data, after_first))?;
+        position = skip_whitespace(data, after_count);
+
+        for index in 0..count {
+            let entry = data.get(position..position + 18)
+                .ok_or_else(|| PolyglotError::PdfParse("truncated xref entry".to_string()))?;
+            let entry_offset = parse_number(&entry[0..10]);
+            let generation = parse_number(&entry[11..16]);
+            let (Some(entry_offset), Some(generation)) = (entry_offset, generation) else {
+                return Err(PolyglotError::PdfParse(format!("malformed xref entry at offset {position}")));
+            };
+            entries.push(XrefEntry {
+                object: first + index,
+                offset: entry_offset,
+                generation: generation as u16,
+                in_use: entry[17] == b'n',
+                entry_position: position,
+            });
+            position += 20;
+        }
+        position = skip_whitespace(data, position);
+    }
+
+    let trailer = data.get(position..keyword).ok_or_else(|| PolyglotError::PdfParse(format!(
+        "xref trailer at offset {position} comes after the last startxref at offset {keyword}"
+    )))?;
+    if find(trailer, b"/Prev").is_some() || find(trailer, b"/XRefStm").is_some() {
+        return Err(PolyglotError::PdfParse("incrementally updated PDFs are not supported".to_string()));
+    }
+
+    Ok(XrefTable { offset, entries, startxref_value: value_start..value_end })
+}
+
+/// Rewrite every offset in the PDF for a document that will start `shift` bytes into a file
+pub fn shift_offsets(data: &[u8], shift: usize) -> PolyglotResult<Vec<u8>> {
+    let table = parse_xref(data)?;
+    let mut shifted = data[..table.startxref_value.start].to_vec();
+
+    for entry in table.entries.iter().filter(|entry| entry.in_use) {
+        let new_offset = format!("{:010}", entry.offset + shift);
+        if new_offset.len() != 10 {
+            return Err(PolyglotError::PdfParse("shifted offset does not fit an xref entry".to_string()));
+        }
+        shifted[entry.entry_position..entry.entry_position + 10].copy_from_slice(new_offset.as_bytes());
+    }
+
+    // The startxref value is the last offset in the file, so its length may change freely
+    shifted.extend_from_slice((table.offset + shift).to_string().as_bytes());
+    shifted.extend_from_slice(&data[table.startxref_value.end..]);
+
+    Ok(shifted)
+}
+
+/// Trailer that points a reader at the xref table, for placing at the very end of a file
+pub fn trailer_for(xref_offset: usize) -> Vec<u8> {
+    format!("\nstartxref\n{xref_offset}\n%%EOF\n").into_bytes()
+}
+
+/// Check the file the way PDF readers locate a document: header near the start,
+/// trailer near the end, and every in-use xref entry pointing at its object
+pub fn validate_pdf(data: &[u8]) -> PolyglotResult<()> {
+    let header_window = &data[..data.len().min(HEADER_SEARCH_WINDOW)];
+    if find(header_window, b"%PDF-").is_none() {
+        return Err(PolyglotError::PdfParse(format!("no %PDF header in the first {HEADER_SEARCH_WINDOW} bytes")));
+    }
+
+    let trailer_window = &data[data.len().saturating_sub(TRAILER_SEARCH_WINDOW)..];
+    if find(trailer_window, b"%%EOF").is_none() || find(trailer_window, b"startxref").is_none() {
+        return Err(PolyglotError::PdfParse(format!("no startxref/%%EOF in the last {TRAILER_SEARCH_WINDOW} bytes")));
+    }
+
+    let table = parse_xref(data)?;
+    for entry in table.entries.iter().filter(|entry| entry.in_use) {
+        let expected = format!("{} {} obj", entry.object, entry.generation);
+        if !data.get(entry.offset..).is_some_and(|rest| rest.starts_with(expected.as_bytes())) {
+            return Err(PolyglotError::PdfParse(format!(
+                "xref entry for object {} points at offset {}, which is not its definition",
+                entry.object, entry.offset
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn skip_whitespace(data: &[u8], mut position: usize) -> usize {
+    while data.get(position).is_some_and(|b| b.is_ascii_whitespace()) {
+        position += 1;
+    }
+    position
+}
+
+fn parse_number(digits: &[u8]) -> Option<usize> {
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+/// Read an unsigned decimal at `position`, returning it and the offset after it
+fn read_number(data: &[u8], position: usize) -> PolyglotResult<(usize, usize)> {
+    let length = data.get(position..).unwrap_or_default().iter().take_while(|b| b.is_ascii_digit()).count();
+    let value = data.get(position..position + length).and_then(parse_number)
+        .ok_or_else(|| PolyglotError::PdfParse(format!("expected a number at offset {position}")))?;
+    Ok((value, position + length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::minimal_pdf;
+
+    #[test]
+    fn test_minimal_pdf_is_valid() {
+        let pdf = minimal_pdf();
+        validate_pdf(&pdf).unwrap();
+        assert_eq!(parse_xref(&pdf).unwrap().entries.len(), 4);
+    }
+
+    /// PDF whose only `startxref` precedes the xref table it points at
+    fn xref_after_startxref(table: &str) -> Vec<u8> {
+        let prefix = "%PDF-1.4\nstartxref\n";
+        let offset = prefix.len() + 3; // Two digits and a newline
+        format!("{prefix}{offset}\n{table}").into_bytes()
+    }
+
+    #[test]
+    fn test_xref_entry_at_end_of_data_is_an_error() {
+        let pdf = xref_after_startxref("xref\n0 1\n0000000000 65535 f");
+        match parse_xref(&pdf) {
+            Err(PolyglotError::PdfParse(message)) => assert_eq!(message, "xref table ends before its trailer"),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_trailer_after_last_startxref_is_an_error() {
+        let pdf = xref_after_startxref("xref\n0 1\n0000000000 65535 f \ntrailer\n<< /Size 1 >>\n");
+        match parse_xref(&pdf) {
+            Err(PolyglotError::PdfParse(message)) => assert!(message.contains("comes after the last startxref"), "{}", message),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_shifted_pdf_validates_after_prefix() {
+        let prefix = vec![b'#'; 100];
+        let shifted = shift_offsets(&minimal_pdf(), prefix.len()).unwrap();
+
+        validate_pdf(&[prefix.as_slice(), &shifted].concat()).unwrap();
+        assert!(validate_pdf(&[prefix.as_slice(), &minimal_pdf()].concat()).is_err());
+    }
+}
@@ -1,12 +1,39 @@
 //! PNG chunk manipulation module
 
 pub mod parser;
+pub mod stream;
 
+use alloc::{format, string::ToString, vec::Vec};
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "compression")]
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
 use crate::utils::write_u32_be;
 use crate::{PolyglotError, PolyglotResult};
-pub use parser::{Chunk, ParsedPng};
+pub use parser::{Chunk, ImageHeader, ModificationTime, ParseOptions, ParsedPng, PhysicalDimensions};
+pub use stream::{StreamParser, MAX_CHUNK_LENGTH};
+
+/// Private chunk holding a payload after the image data: ancillary, private, not safe-to-copy
+pub const PAYLOAD_CHUNK_TYPE: [u8; 4] = *b"plZP";
+
+/// Default upper bound for a single IDAT chunk when embedding data
+pub const DEFAULT_MAX_IDAT_SIZE: usize = 8 * 1024 * 1024;
+
+#[cfg(feature = "compression")]
+/// Largest payload a single stored deflate block can hold
+const MAX_STORED_BLOCK_SIZE: usize = 0xFFFF;
+
+#[cfg(feature = "compression")]
+/// Largest decompressed image a synthesized blank IDAT may describe
+pub const MAX_BLANK_IMAGE_SIZE: usize = 256 * 1024 * 1024;
+
+/// Bytes of big-endian length stored ahead of an LSB payload
+const LSB_LENGTH_SIZE: usize = 4;
+
+/// Size of an 8-bit sPLT palette entry: red, green, blue, alpha and a 2-byte frequency
+const SPLT_ENTRY_SIZE: usize = 6;
 
 /// PNG file representation with manipulation capabilities
 #[derive(Debug, Clone)]
@@ -16,6 +43,7 @@ pub struct PngFile {
 }
 
 impl PngFile {
+    #[cfg(feature = "std")]
     /// Load PNG file from path
     pub fn from_file(path: &Path) -> PolyglotResult<Self> {
         let raw_data = fs::read(path)?;
@@ -36,10 +64,60 @@ impl PngFile {
         Ok((chunk.data_offset, chunk.data.len()))
     }
 
+    /// Absolute offset data appended with `append_to_idat_with_limit` will start at
+    ///
+    /// It follows the first IDAT's data, pushed back by the framing of any chunks that data
+    /// is split into; the size of the appended data doesn't matter.
+    pub fn appended_idat_offset(&self, max_idat_size: usize) -> PolyglotResult<usize> {
+        if max_idat_size == 0 {
+            return Err(PolyglotError::InvalidInput("IDAT size limit must be greater than zero".to_string()));
+        }
+        let (idat_offset, idat_length) = self.find_first_idat()?;
+        Ok(idat_offset + idat_length + 12 * idat_length.div_ceil(max_idat_size).saturating_sub(1))
+    }
+
+    /// Chunk containing the absolute file offset, with the offset's position relative to the chunk start
+    pub fn chunk_at_offset(&self, offset: usize) -> Option<(&Chunk, usize)> {
+        self.parsed.chunk_at_offset(offset)
+    }
+
     /// Embed ZIP data in a new tEXt chunk (parasitic - embeds in metadata)
     pub fn add_zip_text_chunk(&mut self, zip_data: &[u8]) -> PolyglotResult<()> {
-        // Find IEND position for insertion
-        let iend_pos = self.raw_data.windows(4).position(|w| w == b"IEND").unwrap() - 4;
+        self.raw_data = self.with_zip_text_chunk(zip_data)?;
+        self.parsed = parser::parse_png_chunks(&self.raw_data)?;
+        #[cfg(debug_assertions)]
+        self.check_iend_last()?;
+
+        Ok(())
+    }
+
+    /// Check the chunks end with a single IEND, counting chunks that still parse after the
+    /// first IEND; other data appended to the file is not a chunk and is ignored
+    #[cfg(debug_assertions)]
+    fn check_iend_last(&self) -> PolyglotResult<()> {
+        let mut parsed = self.parsed.clone();
+        if let Some(iend_end) = parsed.chunks.last().map(|chunk| chunk.end_offset()) {
+            parsed.chunks.extend(parser::parse_trailing_chunks(&self.raw_data, iend_end));
+        }
+        parsed.assert_iend_last()
+    }
+
+    /// File bytes with ZIP data in a new tEXt chunk, without re-parsing them
+    ///
+    /// Consuming counterpart of `add_zip_text_chunk` for callers that write the bytes and
+    /// drop the `PngFile`, so the chunk list would never be read again.
+    pub fn into_bytes_with_zip_text_chunk(self, zip_data: &[u8]) -> PolyglotResult<Vec<u8>> {
+        self.with_zip_text_chunk(zip_data)
+    }
+
+    fn with_zip_text_chunk(&self, zip_data: &[u8]) -> PolyglotResult<Vec<u8>> {
+        crate::utils::ensure_payload_not_empty(zip_data)?;
+
+        // Insert before the parsed IEND chunk, whose length and type precede its data
+        let iend_pos = self.parsed.chunks.iter()
+            .find(|chunk| chunk.chunk_type == *b"IEND")
+            .map(|chunk| chunk.data_offset - 8)
+            .ok_or_else(|| PolyglotError::ChunkNotFound("IEND".to_string()))?;
 
         // Create new chunk data
         let keyword = b"ZIP Archive";
@@ -53,19 +131,88 @@ impl PngFile {
         new_chunk.extend_from_slice(&chunk_length.to_be_bytes());
         new_chunk.extend_from_slice(b"tEXt");
         new_chunk.extend_from_slice(&chunk_data);
-        let crc_data = [b"tEXt".as_slice(), &chunk_data].concat();
-        let crc = crate::utils::calculate_crc32(&crc_data);
-        new_chunk.extend_from_slice(&crc.to_be_bytes());
+        new_chunk.extend_from_slice(&chunk_crc(b"tEXt", &chunk_data).to_be_bytes());
 
         // Insert before IEND
         let mut new_data = self.raw_data[0..iend_pos].to_vec();
         new_data.extend_from_slice(&new_chunk);
         new_data.extend_from_slice(&self.raw_data[iend_pos..]);
 
+        Ok(new_data)
+    }
+
+    /// Store a payload in its own `plZP` chunk before IEND, returning the absolute offset of its data
+    ///
+    /// The chunk follows every IDAT, so the image's zlib stream is untouched and decoders
+    /// skip the payload as an unknown ancillary chunk.
+    pub fn add_payload_chunk(&mut self, data: &[u8]) -> PolyglotResult<usize> {
+        crate::utils::ensure_payload_not_empty(data)?;
+        if data.len() > i32::MAX as usize {
+            return Err(PolyglotError::InvalidInput(format!(
+                "payload of {} bytes exceeds the PNG chunk length limit", data.len()
+            )));
+        }
+        self.insert_chunk_before_iend(&PAYLOAD_CHUNK_TYPE, data)
+    }
+
+    /// Insert a chunk directly before IEND, returning the absolute offset of its data
+    pub fn insert_chunk_before_iend(&mut self, chunk_type: &[u8; 4], data: &[u8]) -> PolyglotResult<usize> {
+        let iend_start = self.parsed.chunks.iter()
+            .find(|chunk| chunk.chunk_type == *b"IEND")
+            .map(|chunk| chunk.start_offset())
+            .ok_or_else(|| PolyglotError::ChunkNotFound("IEND".to_string()))?;
+
+        let mut new_data = self.raw_data[0..iend_start].to_vec();
+        write_chunk(&mut new_data, chunk_type, data);
+        new_data.extend_from_slice(&self.raw_data[iend_start..]);
+
         self.raw_data = new_data;
         self.parsed = parser::parse_png_chunks(&self.raw_data)?;
 
-        Ok(())
+        Ok(iend_start + 8)
+    }
+
+    /// Payload stored by `add_payload_chunk`, if any
+    pub fn payload_chunk(&self) -> Option<&[u8]> {
+        self.parsed.chunks.iter()
+            .find(|chunk| chunk.chunk_type == PAYLOAD_CHUNK_TYPE)
+            .map(|chunk| chunk.data.as_slice())
+    }
+
+    /// Insert a chunk directly after IHDR, returning the absolute offset of its data
+    ///
+    /// This is the earliest position a chunk may take, for payloads that readers
+    /// only look for near the start of a file.
+    pub fn insert_chunk_after_ihdr(&mut self, chunk_type: &[u8; 4], data: &[u8]) -> PolyglotResult<usize> {
+        let ihdr_end = self.parsed.chunks.first()
+            .filter(|chunk| chunk.chunk_type == *b"IHDR")
+            .map(|chunk| chunk.end_offset())
+            .ok_or_else(|| PolyglotError::ChunkNotFound("IHDR".to_string()))?;
+
+        let mut new_data = self.raw_data[0..ihdr_end].to_vec();
+        write_chunk(&mut new_data, chunk_type, data);
+        new_data.extend_from_slice(&self.raw_data[ihdr_end..]);
+
+        self.raw_data = new_data;
+        self.parsed = parser::parse_png_chunks(&self.raw_data)?;
+
+        Ok(ihdr_end + 8)
+    }
+
+    /// Remove every ancillary chunk except the listed types, returning how many were removed
+    ///
+    /// Critical chunks (uppercase first letter: IHDR, PLTE, IDAT, IEND) are always kept, so
+    /// the image still decodes; text, EXIF, timestamps and any earlier payload chunks go.
+    pub fn strip_ancillary(&mut self, keep: &[[u8; 4]]) -> PolyglotResult<usize> {
+        let kept: Vec<Chunk> = self.parsed.chunks.iter()
+            .filter(|chunk| chunk.chunk_type[0].is_ascii_uppercase() || keep.contains(&chunk.chunk_type))
+            .cloned()
+            .collect();
+        let removed = self.parsed.chunks.len() - kept.len();
+        if removed > 0 {
+            self.rebuild_from_chunks(&kept)?;
+        }
+        Ok(removed)
     }
 
     /// Append WAV data to the first IDAT chunk (parasitic - embeds in image data)
@@ -75,9 +222,74 @@ impl PngFile {
 
     /// Append data to the first IDAT chunk (parasitic - embeds in image data)
     pub fn append_to_idat(&mut self, additional_data: &[u8]) -> PolyglotResult<()> {
-        let idat_chunk = parser::find_first_idat(&self.parsed)?
-            .clone();
+        self.append_to_idat_with_limit(additional_data, DEFAULT_MAX_IDAT_SIZE)
+    }
+
+    /// Append data to the first IDAT chunk, splitting the image data into IDAT chunks of at most
+    /// `max_idat_size` bytes
+    ///
+    /// The appended data itself is never split: it goes whole into the chunk that ends the first
+    /// IDAT's data, even past the limit, so it stays contiguous in the file and offsets into it
+    /// (like a rebased ZIP's) still hold.
+    pub fn append_to_idat_with_limit(&mut self, additional_data: &[u8], max_idat_size: usize) -> PolyglotResult<()> {
+        self.raw_data = self.with_appended_idat(additional_data, max_idat_size)?;
+
+        // Re-parse after modification to ensure consistency
+        self.parsed = parser::parse_png_chunks(&self.raw_data)?;
+        #[cfg(debug_assertions)]
+        self.check_iend_last()?;
 
+        Ok(())
+    }
+
+    /// File bytes with data appended to the first IDAT chunk, without re-parsing them
+    ///
+    /// Consuming counterpart of `append_to_idat_with_limit`, like `into_bytes_with_zip_text_chunk`.
+    pub fn into_bytes_with_appended_idat(self, additional_data: &[u8], max_idat_size: usize) -> PolyglotResult<Vec<u8>> {
+        self.with_appended_idat(additional_data, max_idat_size)
+    }
+
+    fn with_appended_idat(&self, additional_data: &[u8], max_idat_size: usize) -> PolyglotResult<Vec<u8>> {
+        crate::utils::ensure_payload_not_empty(additional_data)?;
+        if max_idat_size == 0 {
+            return Err(PolyglotError::InvalidInput("IDAT size limit must be greater than zero".to_string()));
+        }
+
+        let idat_chunk = parser::find_first_idat(&self.parsed)?;
+        if idat_chunk.data.len() <= max_idat_size && parser::find_all_idat(&self.parsed).len() == 1 {
+            Ok(self.splice_into_single_idat(additional_data))
+        } else {
+            Ok(self.rebuild_with_appended_idat(additional_data, max_idat_size))
+        }
+    }
+
+    /// File bytes with the payload spliced onto the only IDAT, which stays one chunk
+    ///
+    /// Fast path for the common layout: the bytes around the IDAT are copied in two slices
+    /// into a buffer allocated once, and the CRC is computed without concatenating.
+    fn splice_into_single_idat(&self, additional_data: &[u8]) -> Vec<u8> {
+        let idat = parser::find_first_idat(&self.parsed).expect("caller found an IDAT");
+        let data_end = idat.data_offset + idat.data.len();
+        let file_end = self.parsed.chunks.last().map_or(self.raw_data.len(), |chunk| chunk.end_offset());
+
+        let mut hasher = crate::utils::Crc32Hasher::new();
+        hasher.update(b"IDAT");
+        hasher.update(&idat.data);
+        hasher.update(additional_data);
+
+        let mut new_data = Vec::with_capacity(file_end + additional_data.len());
+        new_data.extend_from_slice(&self.raw_data[..idat.start_offset()]);
+        new_data.extend_from_slice(&((idat.data.len() + additional_data.len()) as u32).to_be_bytes());
+        new_data.extend_from_slice(&self.raw_data[idat.start_offset() + 4..data_end]);
+        new_data.extend_from_slice(additional_data);
+        new_data.extend_from_slice(&hasher.finalize().to_be_bytes());
+        new_data.extend_from_slice(&self.raw_data[data_end + 4..file_end]);
+        new_data
+    }
+
+    /// File bytes rebuilt chunk by chunk, with the first IDAT's data split to the limit and the
+    /// payload appended whole to its last piece
+    fn rebuild_with_appended_idat(&self, additional_data: &[u8], max_idat_size: usize) -> Vec<u8> {
         // Build new PNG data with modified IDAT
         let mut new_data = Vec::with_capacity(self.raw_data.len() + additional_data.len());
 
@@ -91,24 +303,13 @@ impl PngFile {
                 // Modify IDAT chunk
                 found_idat = true;
 
-                // New IDAT data = original + additional
-                let new_idat_data = [chunk.data.as_slice(), additional_data].concat();
-                let new_length = new_idat_data.len() as u32;
-
-                // Write length
-                new_data.extend_from_slice(&new_length.to_be_bytes());
-
-                // Write type
-                new_data.extend_from_slice(b"IDAT");
-
-                // Write data
-                new_data.extend_from_slice(&new_idat_data);
-
-                // Calculate and write CRC
-                let mut crc_data = b"IDAT".to_vec();
-                crc_data.extend_from_slice(&new_idat_data);
-                let crc = crate::utils::calculate_crc32(&crc_data);
-                new_data.extend_from_slice(&crc.to_be_bytes());
+                // Image data split to stay under the limit; the payload must stay in one piece
+                let split_at = chunk.data.len().saturating_sub(1) / max_idat_size * max_idat_size;
+                let (pieces, last_piece) = chunk.data.split_at(split_at);
+                for piece in pieces.chunks(max_idat_size) {
+                    write_chunk(&mut new_data, b"IDAT", piece);
+                }
+                write_chunk(&mut new_data, b"IDAT", &[last_piece, additional_data].concat());
             } else {
                 // Copy chunk as-is
                 let length_bytes = chunk.length.to_be_bytes();
@@ -119,15 +320,512 @@ impl PngFile {
             }
         }
 
-        // Replace raw data
+        new_data
+    }
+
+    #[cfg(feature = "compression")]
+    /// Re-deflate the image data with the payload appended as stored blocks of the same zlib stream
+    ///
+    /// The IDAT chunks become a single valid zlib stream (correct Adler-32 included) whose
+    /// decompressed output is the original scanlines followed by the payload. Decoders read
+    /// only as many bytes as IHDR declares, so the image is unchanged; the trailing bytes are
+    /// recovered with `extract_zlib_payload`. Any payload embedded this way before is replaced.
+    pub fn embed_in_zlib_stream(&mut self, payload: &[u8]) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(payload)?;
+        let mut raw = self.inflate_idat()?;
+        raw.truncate(parser::parse_ihdr(&self.parsed)?.raw_data_len());
+
+        // Compress the image data and sync-flush, leaving the stream open and byte aligned
+        let mut compress = Compress::new(Compression::default(), true);
+        let mut stream = Vec::with_capacity(raw.len() + payload.len() + 64);
+        loop {
+            let consumed = compress.total_in() as usize;
+            compress.compress_vec(&raw[consumed..], &mut stream, FlushCompress::Sync)?;
+            if compress.total_in() as usize == raw.len() && stream.len() < stream.capacity() {
+                break;
+            }
+            stream.reserve(64 * 1024);
+        }
+
+        // Payload as stored blocks, the last one marked final
+        let mut blocks = payload.chunks(MAX_STORED_BLOCK_SIZE).peekable();
+        while let Some(block) = blocks.next() {
+            let is_final = blocks.peek().is_none();
+            stream.push(u8::from(is_final));
+            stream.extend_from_slice(&(block.len() as u16).to_le_bytes());
+            stream.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+            stream.extend_from_slice(block);
+        }
+
+        raw.extend_from_slice(payload);
+        stream.extend_from_slice(&crate::utils::calculate_adler32(&raw).to_be_bytes());
+
+        // Replace all IDAT chunks with the new stream at the position of the first
+        let mut new_data = self.raw_data[0..8].to_vec();
+        let mut wrote_idat = false;
+        for chunk in &self.parsed.chunks {
+            if chunk.chunk_type != *b"IDAT" {
+                write_chunk(&mut new_data, &chunk.chunk_type, &chunk.data);
+            } else if !wrote_idat {
+                wrote_idat = true;
+                for piece in stream.chunks(DEFAULT_MAX_IDAT_SIZE) {
+                    write_chunk(&mut new_data, b"IDAT", piece);
+                }
+            }
+        }
+
         self.raw_data = new_data;
+        self.parsed = parser::parse_png_chunks(&self.raw_data)?;
 
-        // Re-parse after modification to ensure consistency
+        Ok(())
+    }
+
+    #[cfg(feature = "compression")]
+    /// Recover a payload embedded with `embed_in_zlib_stream`
+    pub fn extract_zlib_payload(&self) -> PolyglotResult<Vec<u8>> {
+        let image_len = parser::parse_ihdr(&self.parsed)?.raw_data_len();
+        let mut raw = self.inflate_idat()?;
+        if raw.len() < image_len {
+            return Err(PolyglotError::PngParse(format!(
+                "IDAT decompresses to {} bytes, image needs {}", raw.len(), image_len
+            )));
+        }
+
+        Ok(raw.split_off(image_len))
+    }
+
+    #[cfg(feature = "compression")]
+    /// Length of the zlib stream at the start of the concatenated IDAT data
+    ///
+    /// Anything past this point was appended to the image data, e.g. by the IDAT method.
+    pub fn compute_idat_zlib_end(&self) -> PolyglotResult<usize> {
+        let idat = self.idat_data();
+        let mut decompress = Decompress::new(true);
+        let mut scratch = vec![0u8; 64 * 1024];
+        loop {
+            let consumed = decompress.total_in() as usize;
+            let produced = decompress.total_out();
+            let status = decompress.decompress(&idat[consumed..], &mut scratch, FlushDecompress::None)?;
+            if status == Status::StreamEnd {
+                return Ok(decompress.total_in() as usize);
+            }
+            if decompress.total_in() as usize == consumed && decompress.total_out() == produced {
+                return Err(PolyglotError::DecodeError("zlib: IDAT stream is truncated".to_string()));
+            }
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    /// Decode the carrier image to 8-bit RGBA, returning width, height and pixels
+    ///
+    /// Image data is cut at the end of its zlib stream first, so a payload appended to
+    /// IDAT doesn't upset the decoder.
+    pub fn decode_to_rgba(&self) -> PolyglotResult<(u32, u32, Vec<u8>)> {
+        let (frame, pixels) = self.decode_image(::png::Transformations::ALPHA | ::png::Transformations::STRIP_16)?;
+
+        let rgba = match frame.color_type {
+            ::png::ColorType::Rgba => pixels,
+            ::png::ColorType::Rgb => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 0xFF]).collect(),
+            ::png::ColorType::GrayscaleAlpha => pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            ::png::ColorType::Grayscale => pixels.iter().flat_map(|&g| [g, g, g, 0xFF]).collect(),
+            ::png::ColorType::Indexed => {
+                return Err(PolyglotError::DecodeError("palette was not expanded".to_string()));
+            }
+        };
+
+        Ok((frame.width, frame.height, rgba))
+    }
+
+    /// Largest payload `embed_lsb` can hide, in bytes
+    pub fn lsb_capacity(&self) -> PolyglotResult<usize> {
+        self.lsb_capacity_with_bits(1)
+    }
+
+    /// Largest payload `embed_lsb_with_bits` can hide using the low `bits_per_channel` bits, in bytes
+    ///
+    /// Computed from IHDR and tRNS alone, counting channels as the decoder expands them:
+    /// palettes become RGB, and tRNS adds an alpha channel.
+    pub fn lsb_capacity_with_bits(&self, bits_per_channel: u8) -> PolyglotResult<usize> {
+        check_lsb_bits(bits_per_channel)?;
+        let header = parser::parse_ihdr(&self.parsed)?;
+        let has_trns = self.parsed.chunks.iter().any(|chunk| chunk.chunk_type == *b"tRNS");
+        let channels = match header.color_type {
+            2 | 3 => 3 + usize::from(has_trns),
+            4 => 2,
+            6 => 4,
+            _ => 1 + usize::from(has_trns),
+        };
+
+        // Even dimensions within the spec's 2^31-1 limit overflow a 64-bit product, so saturate
+        let samples = (header.width as usize).saturating_mul(header.height as usize).saturating_mul(channels);
+        Ok(lsb_payload_capacity(samples, bits_per_channel))
+    }
+
+    #[cfg(feature = "compression")]
+    /// Hide a payload in the least-significant bit of every pixel channel
+    ///
+    /// The image is decoded to 8 bits per channel (palettes expanded), a 32-bit big-endian
+    /// length and the payload are written one bit per channel, most significant bit first,
+    /// and the result is re-encoded in place of the old image data. Each channel value
+    /// changes by at most one. Other chunks are kept, minus palette and colour chunks when
+    /// the colour type changes. Data appended to IDAT is dropped with the old image data.
+    pub fn embed_lsb(&mut self, payload: &[u8]) -> PolyglotResult<()> {
+        self.embed_lsb_with_bits(payload, 1)
+    }
+
+    #[cfg(feature = "compression")]
+    /// Like `embed_lsb`, but using the low `bits_per_channel` bits (1-8) of each channel
+    ///
+    /// More bits hold more data at the cost of visible noise: channels change by up to
+    /// `2^bits - 1`.
+    pub fn embed_lsb_with_bits(&mut self, payload: &[u8], bits_per_channel: u8) -> PolyglotResult<()> {
+        check_lsb_bits(bits_per_channel)?;
+        crate::utils::ensure_payload_not_empty(payload)?;
+        let (frame, mut samples) = self.decode_image(::png::Transformations::EXPAND | ::png::Transformations::STRIP_16)?;
+        let capacity = lsb_payload_capacity(samples.len(), bits_per_channel);
+        if payload.len() > capacity {
+            return Err(PolyglotError::InvalidInput(format!(
+                "payload of {} bytes exceeds the image's LSB capacity of {} bytes", payload.len(), capacity
+            )));
+        }
+
+        let message = [(payload.len() as u32).to_be_bytes().as_slice(), payload].concat();
+        let mut bits = message.iter().flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1)).peekable();
+        let mask = u8::MAX >> (8 - bits_per_channel);
+        for sample in samples.iter_mut() {
+            if bits.peek().is_none() {
+                break;
+            }
+            let value = (0..bits_per_channel).fold(0, |value, _| (value << 1) | bits.next().unwrap_or(0));
+            *sample = (*sample & !mask) | value;
+        }
+
+        let mut encoded = Vec::new();
+        let mut encoder = ::png::Encoder::new(&mut encoded, frame.width, frame.height);
+        encoder.set_color(frame.color_type);
+        encoder.set_depth(::png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&samples)?;
+        let encoded = parser::parse_png_chunks(&encoded)?;
+
+        // Swap in the new header and image data, keeping every other chunk where it was
+        let header = parser::parse_ihdr(&self.parsed)?;
+        let recoloured = header.color_type != frame.color_type as u8 || header.bit_depth != 8;
+        let mut chunks = Vec::with_capacity(self.parsed.chunks.len());
+        let mut wrote_idat = false;
+        for chunk in &self.parsed.chunks {
+            match &chunk.chunk_type {
+                b"IHDR" => chunks.push(encoded.chunks[0].clone()),
+                b"IDAT" if !wrote_idat => {
+                    wrote_idat = true;
+                    chunks.extend(encoded.chunks.iter().filter(|chunk| chunk.chunk_type == *b"IDAT").cloned());
+                }
+                b"IDAT" => {}
+                b"PLTE" | b"tRNS" | b"sBIT" | b"bKGD" | b"hIST" if recoloured => {}
+                _ => chunks.push(chunk.clone()),
+            }
+        }
+        self.rebuild_from_chunks(&chunks)
+    }
+
+    #[cfg(feature = "compression")]
+    /// Recover a payload hidden with `embed_lsb`
+    pub fn extract_lsb(&self) -> PolyglotResult<Vec<u8>> {
+        self.extract_lsb_with_bits(1)
+    }
+
+    #[cfg(feature = "compression")]
+    /// Recover a payload hidden with `embed_lsb_with_bits` using the same `bits_per_channel`
+    pub fn extract_lsb_with_bits(&self, bits_per_channel: u8) -> PolyglotResult<Vec<u8>> {
+        check_lsb_bits(bits_per_channel)?;
+        let (_, samples) = self.decode_image(::png::Transformations::EXPAND | ::png::Transformations::STRIP_16)?;
+        let mut bits = samples.iter().flat_map(|sample| (0..bits_per_channel).rev().map(move |bit| (sample >> bit) & 1));
+        let mut next_byte = || (0..8).try_fold(0u8, |byte, _| Some((byte << 1) | bits.next()?));
+
+        let length_bytes: Vec<u8> = core::iter::from_fn(&mut next_byte).take(LSB_LENGTH_SIZE).collect();
+        let length = u32::from_be_bytes(length_bytes.try_into()
+            .map_err(|_| PolyglotError::ValidationFailed("image too small to hold an LSB payload".to_string()))?) as usize;
+        let capacity = lsb_payload_capacity(samples.len(), bits_per_channel);
+        if length > capacity {
+            return Err(PolyglotError::ValidationFailed(format!(
+                "LSB length field says {} bytes but the image holds at most {}; no payload embedded?", length, capacity
+            )));
+        }
+
+        Ok(core::iter::from_fn(next_byte).take(length).collect())
+    }
+
+    #[cfg(feature = "compression")]
+    /// Decode the image with the given transformations, ignoring anything appended to its zlib stream
+    fn decode_image(&self, transformations: ::png::Transformations) -> PolyglotResult<(::png::OutputInfo, Vec<u8>)> {
+        let image_data = self.idat_data()[..self.compute_idat_zlib_end()?].to_vec();
+        let mut chunks: Vec<Chunk> = Vec::with_capacity(self.parsed.chunks.len());
+        for chunk in &self.parsed.chunks {
+            match &chunk.chunk_type {
+                b"IDAT" if chunks.iter().any(|chunk| chunk.chunk_type == *b"IDAT") => {}
+                b"IDAT" => chunks.push(Chunk { length: image_data.len() as u32, data: image_data.clone(), ..chunk.clone() }),
+                _ => chunks.push(chunk.clone()),
+            }
+        }
+        let mut clean = self.clone();
+        clean.rebuild_from_chunks(&chunks)?;
+
+        let mut decoder = ::png::Decoder::new(std::io::Cursor::new(clean.raw_data));
+        decoder.set_transformations(transformations);
+        let mut reader = decoder.read_info()?;
+        let mut pixels = vec![0u8; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut pixels)?;
+        pixels.truncate(frame.buffer_size());
+
+        Ok((frame, pixels))
+    }
+
+    #[cfg(feature = "compression")]
+    /// Decompress the concatenated IDAT data
+    fn inflate_idat(&self) -> PolyglotResult<Vec<u8>> {
+        let idat = self.idat_data();
+        let mut decompress = Decompress::new(true);
+        let mut raw = Vec::with_capacity(idat.len() * 4);
+        loop {
+            let consumed = decompress.total_in() as usize;
+            let status = decompress.decompress_vec(&idat[consumed..], &mut raw, FlushDecompress::Finish)?;
+            if status == Status::StreamEnd {
+                return Ok(raw);
+            }
+            if decompress.total_in() as usize == idat.len() && raw.len() < raw.capacity() {
+                return Err(PolyglotError::DecodeError("zlib: IDAT stream is truncated".to_string()));
+            }
+            raw.reserve(64 * 1024);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    /// Give an IDAT-less carrier a blank IDAT sized to its IHDR, returning whether one was added
+    ///
+    /// IDAT embedding needs image data to append to; a carrier with only IHDR and IEND
+    /// otherwise has to fall back to a text chunk.
+    pub fn synthesize_idat(&mut self) -> PolyglotResult<bool> {
+        if parser::find_first_idat(&self.parsed).is_ok() {
+            return Ok(false);
+        }
+
+        let idat = blank_idat_data(&parser::parse_ihdr(&self.parsed)?)?;
+        let iend_index = self.parsed.chunks.iter()
+            .position(|chunk| chunk.chunk_type == *b"IEND")
+            .ok_or_else(|| PolyglotError::ChunkNotFound("IEND".to_string()))?;
+
+        let mut chunks = self.parsed.chunks.clone();
+        chunks.insert(iend_index, Chunk {
+            length: idat.len() as u32,
+            chunk_type: *b"IDAT",
+            crc: 0, // Recomputed by the rebuild
+            data_offset: 0,
+            data: idat,
+        });
+        self.rebuild_from_chunks(&chunks)?;
+
+        Ok(true)
+    }
+
+    /// Set the last-modification time, replacing any existing tIME chunk or adding one after IHDR
+    pub fn set_time_chunk(&mut self, year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> PolyglotResult<()> {
+        let time = ModificationTime { year, month, day, hour, minute, second };
+        if !time.is_valid() {
+            return Err(PolyglotError::InvalidInput(format!("invalid tIME value {}", time)));
+        }
+
+        let mut chunks = self.parsed.chunks.clone();
+        match chunks.iter_mut().find(|chunk| chunk.chunk_type == *b"tIME") {
+            Some(chunk) => {
+                chunk.data = time.to_bytes().to_vec();
+                self.rebuild_from_chunks(&chunks)
+            }
+            None => self.insert_chunk_after_ihdr(b"tIME", &time.to_bytes()).map(|_| ()),
+        }
+    }
+
+    /// Last-modification time from the tIME chunk, if present
+    pub fn time_chunk(&self) -> PolyglotResult<Option<ModificationTime>> {
+        parser::parse_time(&self.parsed)
+    }
+
+    /// Set the pixel density, replacing any existing pHYs chunk or adding one after IHDR
+    ///
+    /// `unit` is 1 for pixels per metre (300 DPI is 11811) or 0 when only the aspect ratio is known.
+    pub fn set_phys(&mut self, x_ppu: u32, y_ppu: u32, unit: u8) -> PolyglotResult<()> {
+        if unit > 1 {
+            return Err(PolyglotError::InvalidInput(format!("invalid pHYs unit {}, expected 0 or 1", unit)));
+        }
+        let phys = PhysicalDimensions { x_ppu, y_ppu, unit };
+
+        let mut chunks = self.parsed.chunks.clone();
+        match chunks.iter_mut().find(|chunk| chunk.chunk_type == *b"pHYs") {
+            Some(chunk) => {
+                chunk.data = phys.to_bytes().to_vec();
+                self.rebuild_from_chunks(&chunks)
+            }
+            None => self.insert_chunk_after_ihdr(b"pHYs", &phys.to_bytes()).map(|_| ()),
+        }
+    }
+
+    /// Pixel density from the pHYs chunk, if present
+    pub fn get_phys(&self) -> PolyglotResult<Option<PhysicalDimensions>> {
+        parser::parse_phys(&self.parsed)
+    }
+
+    /// Hide a payload in an `sPLT` suggested-palette chunk named `name`, placed before the image data
+    ///
+    /// The chunk is a well-formed 8-bit palette: the payload, prefixed with its big-endian
+    /// length and zero-padded, fills whole 6-byte entries (RGBA plus frequency).
+    pub fn add_splt_payload(&mut self, name: &str, data: &[u8]) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(data)?;
+        let payload_len = u32::try_from(data.len())
+            .map_err(|_| PolyglotError::InvalidInput(format!("sPLT payload of {} bytes is too large", data.len())))?;
+
+        let mut chunk_data = latin1_keyword(name)?;
+        chunk_data.push(0); // Name terminator
+        chunk_data.push(8); // Sample depth
+        let entries_start = chunk_data.len();
+        chunk_data.extend_from_slice(&payload_len.to_be_bytes());
+        chunk_data.extend_from_slice(data);
+        let entries_len = chunk_data.len() - entries_start;
+        chunk_data.resize(entries_start + entries_len.div_ceil(SPLT_ENTRY_SIZE) * SPLT_ENTRY_SIZE, 0);
+
+        self.insert_chunk_after_ihdr(b"sPLT", &chunk_data)?;
+        Ok(())
+    }
+
+    /// Recover a payload hidden with `add_splt_payload`, if an `sPLT` chunk with that name exists
+    pub fn extract_splt_payload(&self, name: &str) -> PolyglotResult<Option<Vec<u8>>> {
+        let prefix = [latin1_keyword(name)?.as_slice(), &[0, 8]].concat();
+        let Some(chunk) = self.parsed.chunks.iter()
+            .find(|chunk| chunk.chunk_type == *b"sPLT" && chunk.data.starts_with(&prefix)) else {
+            return Ok(None);
+        };
+
+        let entries = &chunk.data[prefix.len()..];
+        let truncated = || PolyglotError::PngParse("sPLT payload is truncated".to_string());
+        let length_bytes: [u8; 4] = entries.get(0..4).ok_or_else(truncated)?.try_into().expect("four bytes");
+        let payload_len = u32::from_be_bytes(length_bytes) as usize;
+        let payload = entries.get(4..4 + payload_len).ok_or_else(truncated)?;
+
+        Ok(Some(payload.to_vec()))
+    }
+
+    #[cfg(feature = "compression")]
+    /// Hide a payload as the zlib-compressed profile of an `iCCP` chunk named `name`, placed before the image data
+    ///
+    /// A PNG may carry at most one embedded ICC profile, so carriers that already have one are rejected.
+    pub fn add_iccp_payload(&mut self, name: &str, data: &[u8]) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(data)?;
+        if self.parsed.chunks.iter().any(|chunk| chunk.chunk_type == *b"iCCP") {
+            return Err(PolyglotError::InvalidInput("PNG already has an iCCP chunk; only one is allowed".to_string()));
+        }
+
+        let mut chunk_data = latin1_keyword(name)?;
+        chunk_data.push(0); // Name terminator
+        chunk_data.push(0); // Compression method: zlib
+        let mut encoder = flate2::write::ZlibEncoder::new(chunk_data, Compression::best());
+        std::io::Write::write_all(&mut encoder, data)?;
+        let chunk_data = encoder.finish()?;
+
+        self.insert_chunk_after_ihdr(b"iCCP", &chunk_data)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "compression")]
+    /// Inflate the profile of the `iCCP` chunk, recovering a payload hidden with `add_iccp_payload`
+    pub fn extract_iccp_payload(&self) -> PolyglotResult<Option<Vec<u8>>> {
+        let Some(chunk) = self.parsed.chunks.iter().find(|chunk| chunk.chunk_type == *b"iCCP") else {
+            return Ok(None);
+        };
+
+        let name_end = chunk.data.iter().position(|&byte| byte == 0)
+            .ok_or_else(|| PolyglotError::PngParse("iCCP profile name is not terminated".to_string()))?;
+        match chunk.data.get(name_end + 1) {
+            Some(0) => {}
+            Some(method) => return Err(PolyglotError::PngParse(format!("unknown iCCP compression method {}", method))),
+            None => return Err(PolyglotError::PngParse("iCCP chunk is truncated".to_string())),
+        }
+
+        let mut profile = Vec::new();
+        std::io::Read::read_to_end(&mut flate2::read::ZlibDecoder::new(&chunk.data[name_end + 2..]), &mut profile)
+            .map_err(|e| PolyglotError::DecodeError(format!("zlib: iCCP profile: {}", e)))?;
+
+        Ok(Some(profile))
+    }
+
+    /// Remove every tEXt chunk with the given keyword, returning whether any was removed
+    pub fn remove_text_chunk(&mut self, keyword: &[u8]) -> PolyglotResult<bool> {
+        let prefix = [keyword, &[0]].concat();
+        let is_match = |chunk: &Chunk| chunk.chunk_type == *b"tEXt" && chunk.data.starts_with(&prefix);
+
+        if !self.parsed.chunks.iter().any(is_match) {
+            return Ok(false);
+        }
+
+        let kept: Vec<Chunk> = self.parsed.chunks.iter()
+            .filter(|chunk| !is_match(chunk))
+            .cloned()
+            .collect();
+        self.rebuild_from_chunks(&kept)?;
+
+        Ok(true)
+    }
+
+    /// Truncate the IDAT data to its first `image_len` bytes, returning the stripped payload
+    ///
+    /// `image_len` counts across all IDAT chunks, like `compute_idat_zlib_end`: the chunk it
+    /// falls in is cut there, and the IDAT chunks after it, holding only payload, are dropped.
+    pub fn strip_appended_idat_payload(&mut self, image_len: usize) -> PolyglotResult<Vec<u8>> {
+        let idat = self.idat_data();
+        if image_len > idat.len() {
+            return Err(PolyglotError::InvalidInput(format!(
+                "IDAT has {} bytes, cannot keep {}", idat.len(), image_len
+            )));
+        }
+        let payload = idat[image_len..].to_vec();
+
+        let mut remaining = image_len;
+        let mut chunks = Vec::with_capacity(self.parsed.chunks.len());
+        for chunk in &self.parsed.chunks {
+            if chunk.chunk_type != *b"IDAT" {
+                chunks.push(chunk.clone());
+                continue;
+            }
+            if remaining == 0 {
+                continue;
+            }
+            let mut chunk = chunk.clone();
+            chunk.data.truncate(remaining);
+            remaining -= chunk.data.len();
+            chunks.push(chunk);
+        }
+        self.rebuild_from_chunks(&chunks)?;
+
+        Ok(payload)
+    }
+
+    /// Replace the file contents with the given chunks, recomputing lengths and CRCs
+    pub(crate) fn rebuild_from_chunks(&mut self, chunks: &[Chunk]) -> PolyglotResult<()> {
+        let mut new_data = self.raw_data[0..8].to_vec();
+        for chunk in chunks {
+            write_chunk(&mut new_data, &chunk.chunk_type, &chunk.data);
+        }
+
+        self.raw_data = new_data;
         self.parsed = parser::parse_png_chunks(&self.raw_data)?;
 
         Ok(())
     }
 
+    /// Concatenated data of all IDAT chunks
+    pub fn idat_data(&self) -> Vec<u8> {
+        parser::find_all_idat(&self.parsed)
+            .iter()
+            .flat_map(|chunk| chunk.data.iter().copied())
+            .collect()
+    }
+
     /// Recalculate CRC for all chunks
     pub fn recalculate_crcs(&mut self) -> PolyglotResult<()> {
         let mut offset = 8; // Skip PNG signature
@@ -145,10 +843,7 @@ impl PngFile {
             offset += 4; // Skip CRC
 
             // Recalculate CRC
-            let mut crc_data = Vec::with_capacity(4 + chunk.length as usize);
-            crc_data.extend_from_slice(&chunk.chunk_type);
-            crc_data.extend_from_slice(&self.raw_data[data_start..data_end]);
-            let new_crc = crate::utils::calculate_crc32(&crc_data);
+            let new_crc = chunk_crc(&chunk.chunk_type, &self.raw_data[data_start..data_end]);
 
             write_u32_be(&mut self.raw_data, crc_offset, new_crc);
         }
@@ -156,6 +851,7 @@ impl PngFile {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
     /// Write the modified PNG to a file
     pub fn write_to_file(&self, path: &Path) -> PolyglotResult<()> {
         fs::write(path, &self.raw_data)?;
@@ -168,78 +864,377 @@ impl PngFile {
     }
 }
 
+/// Stand-ins for the zlib and decoding APIs, so code calling them still builds without
+/// the `compression` feature
+#[cfg(all(feature = "std", not(feature = "compression")))]
+impl PngFile {
+    /// Needs `compression` to re-deflate IDAT; returns `feature_disabled` without it
+    pub fn embed_in_zlib_stream(&mut self, _payload: &[u8]) -> PolyglotResult<()> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to inflate IDAT; returns `feature_disabled` without it
+    pub fn extract_zlib_payload(&self) -> PolyglotResult<Vec<u8>> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to walk the zlib stream; returns `feature_disabled` without it
+    pub fn compute_idat_zlib_end(&self) -> PolyglotResult<usize> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to decode pixels; returns `feature_disabled` without it
+    pub fn decode_to_rgba(&self) -> PolyglotResult<(u32, u32, Vec<u8>)> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to decode and re-encode pixels; returns `feature_disabled` without it
+    pub fn embed_lsb(&mut self, _payload: &[u8]) -> PolyglotResult<()> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to decode pixels; returns `feature_disabled` without it
+    pub fn extract_lsb(&self) -> PolyglotResult<Vec<u8>> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to decode and re-encode pixels; returns `feature_disabled` without it
+    pub fn embed_lsb_with_bits(&mut self, _payload: &[u8], _bits_per_channel: u8) -> PolyglotResult<()> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to decode pixels; returns `feature_disabled` without it
+    pub fn extract_lsb_with_bits(&self, _bits_per_channel: u8) -> PolyglotResult<Vec<u8>> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// A carrier that already has image data needs no compression
+    pub fn synthesize_idat(&mut self) -> PolyglotResult<bool> {
+        match parser::find_first_idat(&self.parsed) {
+            Ok(_) => Ok(false),
+            Err(_) => Err(PolyglotError::feature_disabled("compression")),
+        }
+    }
+
+    /// Needs `compression` to deflate the profile; returns `feature_disabled` without it
+    pub fn add_iccp_payload(&mut self, _name: &str, _data: &[u8]) -> PolyglotResult<()> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+
+    /// Needs `compression` to inflate the profile; returns `feature_disabled` without it
+    pub fn extract_iccp_payload(&self) -> PolyglotResult<Option<Vec<u8>>> {
+        Err(PolyglotError::feature_disabled("compression"))
+    }
+}
+
+/// Needs `compression` to deflate the blank scanlines; returns `feature_disabled` without it
+#[cfg(all(feature = "std", not(feature = "compression")))]
+pub fn blank_idat_data(_header: &ImageHeader) -> PolyglotResult<Vec<u8>> {
+    Err(PolyglotError::feature_disabled("compression"))
+}
+
+/// Reject LSB widths outside 1-8 bits per channel
+fn check_lsb_bits(bits_per_channel: u8) -> PolyglotResult<()> {
+    if !(1..=8).contains(&bits_per_channel) {
+        return Err(PolyglotError::InvalidInput(format!("LSB bits per channel must be 1-8, got {}", bits_per_channel)));
+    }
+    Ok(())
+}
+
+/// Payload bytes that fit in `samples` channels at `bits_per_channel`, after the length field
+fn lsb_payload_capacity(samples: usize, bits_per_channel: u8) -> usize {
+    (samples.saturating_mul(bits_per_channel as usize) / 8).saturating_sub(LSB_LENGTH_SIZE)
+}
+
+/// Encode a chunk keyword as Latin-1, enforcing the PNG rules: 1-79 printable characters,
+/// no leading, trailing or consecutive spaces
+pub(crate) fn latin1_keyword(name: &str) -> PolyglotResult<Vec<u8>> {
+    let invalid = |reason: &str| PolyglotError::InvalidInput(format!("invalid PNG keyword {:?}: {}", name, reason));
+
+    let bytes = name.chars()
+        .map(|c| match c as u32 {
+            code @ (32..=126 | 161..=255) => Ok(code as u8),
+            _ => Err(invalid("only printable Latin-1 characters are allowed")),
+        })
+        .collect::<PolyglotResult<Vec<u8>>>()?;
+
+    if bytes.is_empty() || bytes.len() > 79 {
+        return Err(invalid("must be 1-79 bytes"));
+    }
+    if bytes.starts_with(b" ") || bytes.ends_with(b" ") || bytes.windows(2).any(|pair| pair == b"  ") {
+        return Err(invalid("no leading, trailing or consecutive spaces"));
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(feature = "compression")]
+/// Zlib stream for an all-zero image matching the header: filter type 0 and zeroed
+/// (black, palette index 0, or fully transparent) pixels on every scanline
+///
+/// Returns `SizeOverflow` when the image would exceed `MAX_BLANK_IMAGE_SIZE` once decompressed.
+pub fn blank_idat_data(header: &ImageHeader) -> PolyglotResult<Vec<u8>> {
+    use std::io::Read;
+
+    // raw_data_len saturates, so dimensions too large to address also land above the cap
+    let raw_len = header.raw_data_len();
+    if raw_len > MAX_BLANK_IMAGE_SIZE {
+        return Err(PolyglotError::SizeOverflow);
+    }
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Compression::best());
+    std::io::copy(&mut std::io::repeat(0).take(raw_len as u64), &mut encoder)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(feature = "compression")]
+/// Build a complete 8-bit RGBA PNG from `rgba`, 4 bytes per pixel, rows top to bottom
+///
+/// Every scanline is written with filter type 0 and the whole image goes in one IDAT.
+pub fn make_minimal_png(width: u32, height: u32, rgba: &[u8]) -> PolyglotResult<Vec<u8>> {
+    use std::io::Write;
+
+    // The spec limits both dimensions to 2^31 - 1
+    if width == 0 || height == 0 || width > i32::MAX as u32 || height > i32::MAX as u32 {
+        return Err(PolyglotError::InvalidInput(format!("invalid PNG dimensions {}x{}", width, height)));
+    }
+    let row_len = (width as usize).checked_mul(4).ok_or(PolyglotError::SizeOverflow)?;
+    let expected_len = row_len.checked_mul(height as usize).ok_or(PolyglotError::SizeOverflow)?;
+    if rgba.len() != expected_len {
+        return Err(PolyglotError::InvalidInput(format!(
+            "a {}x{} RGBA image needs {} bytes of pixels, got {}", width, height, expected_len, rgba.len()
+        )));
+    }
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgba.chunks(row_len) {
+        encoder.write_all(&[0])?; // Filter type: None
+        encoder.write_all(row)?;
+    }
+    let idat = encoder.finish()?;
+
+    let mut ihdr = [0u8; 13];
+    write_u32_be(&mut ihdr, 0, width);
+    write_u32_be(&mut ihdr, 4, height);
+    ihdr[8] = 8; // Bit depth
+    ihdr[9] = 6; // Color type: RGBA
+
+    let mut png = crate::utils::PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &idat);
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+/// Needs `compression` to encode the image; returns `feature_disabled` without it
+#[cfg(all(feature = "std", not(feature = "compression")))]
+pub fn make_minimal_png(_width: u32, _height: u32, _rgba: &[u8]) -> PolyglotResult<Vec<u8>> {
+    Err(PolyglotError::feature_disabled("compression"))
+}
+
+/// CRC of a chunk as stored after its data: CRC-32 over the type followed by the data
+///
+/// The length field is not covered.
+pub fn chunk_crc(chunk_type: &[u8; 4], data: &[u8]) -> u32 {
+    let mut hasher = crate::utils::Crc32Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Write a complete chunk (length, type, data, CRC) to the output buffer
+pub(crate) fn write_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(data);
+    output.extend_from_slice(&chunk_crc(chunk_type, data).to_be_bytes());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::minimal_png;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
-    // Helper to create a minimal PNG for testing
-    fn create_test_png() -> Vec<u8> {
-        // Minimal PNG header
-        let mut png = vec![
-            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
-        ];
-
-        // IHDR chunk
-        let ihdr_data = [
-            0x00, 0x00, 0x00, 0x01, // width = 1
-            0x00, 0x00, 0x00, 0x01, // height = 1
-            0x08, // bit depth = 8
-            0x02, // color type = 2 (RGB)
-            0x00, // compression = 0
-            0x00, // filter = 0
-            0x00, // interlace = 0
-        ];
-
-        let ihdr_length = ihdr_data.len() as u32;
-        png.extend_from_slice(&ihdr_length.to_be_bytes());
-        png.extend_from_slice(b"IHDR");
-        png.extend_from_slice(&ihdr_data);
-        let ihdr_crc_data = [b"IHDR".as_slice(), &ihdr_data].concat();
-        let ihdr_crc = crate::utils::calculate_crc32(&ihdr_crc_data);
-        png.extend_from_slice(&ihdr_crc.to_be_bytes());
-
-        // IDAT chunk with minimal compressed data
-        let idat_data = [
-            0x78, 0x9C, 0xED, 0xC1, 0x01, 0x01, 0x00, 0x00, 0x00, 0x80, 0x90, 0xFE, 0x37, 0x10
-        ];
-        let idat_length = idat_data.len() as u32;
-        png.extend_from_slice(&idat_length.to_be_bytes());
-        png.extend_from_slice(b"IDAT");
-        png.extend_from_slice(&idat_data);
-        let idat_crc_data = [b"IDAT".as_slice(), &idat_data].concat();
-        let idat_crc = crate::utils::calculate_crc32(&idat_crc_data);
-        png.extend_from_slice(&idat_crc.to_be_bytes());
-
-        // IEND chunk
-        png.extend_from_slice(&0u32.to_be_bytes());
-        png.extend_from_slice(b"IEND");
-        let iend_crc = crate::utils::calculate_crc32(b"IEND");
-        png.extend_from_slice(&iend_crc.to_be_bytes());
-
-        png
+    #[test]
+    fn test_strip_ancillary_keeps_critical_chunks_and_payload() {
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        file.insert_chunk_after_ihdr(b"tIME", &[0x07, 0xEA, 1, 2, 3, 4, 5]).unwrap();
+        file.insert_chunk_before_iend(b"tEXt", b"Author\0Someone").unwrap();
+        file.insert_chunk_before_iend(b"eXIf", b"MM\0\x2a\0\0\0\x08").unwrap();
+        file.insert_chunk_after_ihdr(b"tRNS", &[0, 0, 0, 0, 0, 0]).unwrap();
+
+        assert_eq!(file.strip_ancillary(&[*b"tRNS"]).unwrap(), 3);
+        let types: Vec<[u8; 4]> = file.parsed.chunks.iter().map(|chunk| chunk.chunk_type).collect();
+        assert_eq!(types, [*b"IHDR", *b"tRNS", *b"IDAT", *b"IEND"]);
+
+        // With nothing kept, only critical chunks and the payload added afterwards remain
+        assert_eq!(file.strip_ancillary(&[]).unwrap(), 1);
+        file.add_zip_text_chunk(b"PK payload").unwrap();
+        let types: Vec<[u8; 4]> = file.parsed.chunks.iter().map(|chunk| chunk.chunk_type).collect();
+        assert_eq!(types, [*b"IHDR", *b"IDAT", *b"tEXt", *b"IEND"]);
+        assert!(PngFile::from_data(file.raw_data.clone()).is_ok());
     }
 
     #[test]
     fn test_png_file_load() {
-        let png_data = create_test_png();
+        let png_data = minimal_png();
         let file = PngFile::from_data(png_data).unwrap();
         assert_eq!(file.parsed.chunks.len(), 3); // IHDR, IDAT, IEND
     }
 
     #[test]
     fn test_idat_finding() {
-        let png_data = create_test_png();
+        let png_data = minimal_png();
         let file = PngFile::from_data(png_data).unwrap();
         let (offset, length) = file.find_first_idat().unwrap();
         assert!(offset > 0);
         assert!(length > 0);
     }
 
+    #[test]
+    fn test_single_idat_fast_path_matches_rebuild() {
+        let mut png_data = minimal_png();
+        png_data.extend_from_slice(b"trailing bytes after IEND");
+        let file = PngFile::from_data(png_data).unwrap();
+        let payload = b"PK\x03\x04 payload for the only IDAT";
+
+        let spliced = file.splice_into_single_idat(payload);
+        assert_eq!(spliced, file.rebuild_with_appended_idat(payload, DEFAULT_MAX_IDAT_SIZE));
+        assert_eq!(spliced.capacity(), spliced.len());
+
+        let mut appended = file.clone();
+        appended.append_to_idat(payload).unwrap();
+        assert_eq!(appended.raw_data, spliced);
+    }
+
+    /// Timing of the single-IDAT fast path against the chunk-by-chunk rebuild
+    ///
+    /// Run with `cargo test --release --lib bench_single_idat_fast_path -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_single_idat_fast_path() {
+        const RUNS: u32 = 50;
+
+        // An 8 MB IDAT carrying a 1 MB payload
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        let chunks: Vec<Chunk> = file.parsed.chunks.iter().cloned().map(|mut chunk| {
+            if chunk.chunk_type == *b"IDAT" {
+                chunk.data = vec![0x5A; DEFAULT_MAX_IDAT_SIZE];
+            }
+            chunk
+        }).collect();
+        file.rebuild_from_chunks(&chunks).unwrap();
+        let payload: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+        let mean = |build: &dyn Fn() -> Vec<u8>| {
+            let start = std::time::Instant::now();
+            for _ in 0..RUNS {
+                std::hint::black_box(build());
+            }
+            start.elapsed() / RUNS
+        };
+        let spliced = mean(&|| file.splice_into_single_idat(&payload));
+        let rebuilt = mean(&|| file.rebuild_with_appended_idat(&payload, DEFAULT_MAX_IDAT_SIZE));
+
+        println!("splice: {:?}, rebuild: {:?} (mean of {} runs)", spliced, rebuilt, RUNS);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_iend_check_sees_chunks_after_iend() {
+        let mut carrier = minimal_png();
+        write_chunk(&mut carrier, b"tEXt", b"after\0IEND");
+        let file = PngFile::from_data(carrier).unwrap();
+
+        // The tEXt payload goes before the first IEND, leaving the stray chunk behind it
+        match file.clone().add_zip_text_chunk(b"PK payload") {
+            Err(PolyglotError::ValidationFailed(message)) => {
+                assert_eq!(message, "IEND is not the final chunk; tEXt follows it");
+            }
+            other => panic!("expected the IEND check to fail, got {:?}", other),
+        }
+
+        // Appending to IDAT rebuilds the file up to IEND, so nothing follows it
+        let mut appended = file;
+        appended.append_to_idat(b"PK payload").unwrap();
+        let options = parser::ParseOptions { stop_at_iend: false, ..parser::ParseOptions::default() };
+        parser::parse_png_chunks_opts(&appended.raw_data, options).unwrap().assert_iend_last().unwrap();
+    }
+
+    #[test]
+    fn test_iend_check_ignores_appended_bytes() {
+        let mut carrier = minimal_png();
+        carrier.extend_from_slice(b" trailing bytes that are not a chunk");
+        let mut file = PngFile::from_data(carrier.clone()).unwrap();
+        file.add_zip_text_chunk(b"PK payload").unwrap();
+
+        // A chunk after IEND is still caught when other bytes follow it
+        write_chunk(&mut carrier, b"tEXt", b"after\0IEND");
+        carrier.extend_from_slice(b"more trailing bytes");
+        let chunks = parser::parse_trailing_chunks(&carrier, minimal_png().len());
+        assert!(chunks.is_empty(), "the first trailing bytes aren't a chunk");
+        let trailing_chunk_at = minimal_png().len() + b" trailing bytes that are not a chunk".len();
+        let chunks = parser::parse_trailing_chunks(&carrier, trailing_chunk_at);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].chunk_type, *b"tEXt");
+    }
+
+    #[test]
+    fn test_consuming_builders_skip_the_reparse() {
+        let payload = b"PK\x03\x04 payload";
+        let file = PngFile::from_data(minimal_png()).unwrap();
+
+        let mut text = file.clone();
+        text.add_zip_text_chunk(payload).unwrap();
+        assert_eq!(file.clone().into_bytes_with_zip_text_chunk(payload).unwrap(), text.raw_data);
+        let mut idat = file.clone();
+        idat.append_to_idat(payload).unwrap();
+        assert_eq!(file.clone().into_bytes_with_appended_idat(payload, DEFAULT_MAX_IDAT_SIZE).unwrap(), idat.raw_data);
+
+        // A bad IHDR CRC is copied through untouched, so only a re-parse would notice it
+        let mut stale = file;
+        stale.raw_data[29] ^= 0xFF;
+        assert!(stale.clone().add_zip_text_chunk(payload).is_err());
+        assert!(stale.clone().append_to_idat(payload).is_err());
+        assert!(stale.clone().into_bytes_with_zip_text_chunk(payload).is_ok());
+        assert!(stale.into_bytes_with_appended_idat(payload, DEFAULT_MAX_IDAT_SIZE).is_ok());
+    }
+
+    #[test]
+    fn test_append_to_idat_keeps_large_payload_contiguous() {
+        let limit = 8 * 1024 * 1024;
+        let payload: Vec<u8> = (0..20 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+        // A carrier whose image data alone needs two chunks under the limit
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        let image_data = vec![0x5A; limit + 100];
+        let chunks: Vec<Chunk> = file.parsed.chunks.iter().cloned().map(|mut chunk| {
+            if chunk.chunk_type == *b"IDAT" {
+                chunk.data = image_data.clone();
+            }
+            chunk
+        }).collect();
+        file.rebuild_from_chunks(&chunks).unwrap();
+        let payload_offset = file.appended_idat_offset(limit).unwrap();
+
+        file.append_to_idat_with_limit(&payload, limit).unwrap();
+
+        // The image data is split, but the 20 MB payload sits whole in the last IDAT
+        let idat_chunks = parser::find_all_idat(&file.parsed);
+        assert_eq!(idat_chunks.len(), 2);
+        assert_eq!(idat_chunks[0].data.len(), limit);
+        assert_eq!(idat_chunks[1].data.len(), 100 + payload.len());
+        assert_eq!(&file.raw_data[payload_offset..payload_offset + payload.len()], payload.as_slice());
+
+        // and concatenating all IDAT data still gives image data then payload
+        assert_eq!(file.idat_data(), [image_data, payload].concat());
+    }
+
     #[test]
     fn test_append_to_idat() {
-        let png_data = create_test_png();
+        let png_data = minimal_png();
         let mut file = PngFile::from_data(png_data.clone()).unwrap();
 
         // Alternative test with real PNG file
@@ -276,4 +1271,271 @@ mod tests {
         let (offset, length) = file.find_first_idat().unwrap();
         assert!(length > additional_data.len()); // Original length + additional
     }
+
+    #[cfg(feature = "compression")]
+    fn encode_rgb_png(width: u32, height: u32) -> (Vec<u8>, Vec<u8>) {
+        let pixels: Vec<u8> = (0..width * height * 3).map(|i| (i * 37 % 251) as u8).collect();
+        let mut png_data = Vec::new();
+        let mut encoder = ::png::Encoder::new(&mut png_data, width, height);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.set_depth(::png::BitDepth::Eight);
+        encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+        (png_data, pixels)
+    }
+
+    #[cfg(feature = "compression")]
+    fn decode_pixels(png_data: &[u8]) -> Vec<u8> {
+        let decoder = ::png::Decoder::new(std::io::Cursor::new(png_data));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        pixels.truncate(info.buffer_size());
+        pixels
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_make_minimal_png_round_trips_pixels() {
+        let (width, height) = (3, 2);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i * 11) as u8).collect();
+
+        let png_data = make_minimal_png(width, height, &rgba).unwrap();
+        assert_eq!(decode_pixels(&png_data), rgba);
+        let header = parser::parse_ihdr(&parser::parse_png_chunks(&png_data).unwrap()).unwrap();
+        assert_eq!((header.width, header.height, header.bit_depth, header.color_type), (3, 2, 8, 6));
+
+        assert!(matches!(make_minimal_png(width, height, &rgba[1..]), Err(PolyglotError::InvalidInput(_))));
+        assert!(matches!(make_minimal_png(0, height, &[]), Err(PolyglotError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_chunk_crc_matches_hash_of_concatenation() {
+        let chunk_data = b"ZIP Archive\0PK\x05\x06";
+        let concatenated = [b"tEXt".as_slice(), chunk_data].concat();
+        assert_eq!(chunk_crc(b"tEXt", chunk_data), crate::utils::calculate_crc32(&concatenated));
+        assert_eq!(chunk_crc(b"IEND", &[]), crate::utils::calculate_crc32(b"IEND"));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_chunk_crc_matches_real_png() {
+        // IEND is identical in every PNG: AE 42 60 82
+        assert_eq!(chunk_crc(b"IEND", &[]), 0xAE426082);
+
+        // IHDR of a 2x2 RGB image written by the png crate
+        let (png_data, _) = encode_rgb_png(2, 2);
+        let ihdr = &PngFile::from_data(png_data.clone()).unwrap().parsed.chunks[0];
+        let stored_crc = u32::from_be_bytes(png_data[29..33].try_into().unwrap());
+        assert_eq!(chunk_crc(b"IHDR", &ihdr.data), stored_crc);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_lsb_round_trip() {
+        let (png_data, pixels) = encode_rgb_png(64, 64);
+        let mut file = PngFile::from_data(png_data).unwrap();
+        file.add_zip_text_chunk(b"kept").unwrap();
+
+        // 64 * 64 * 3 channels hold 1536 bytes, 4 of them the length
+        let payload: Vec<u8> = (0..1532u32).map(|i| (i * 7 % 256) as u8).collect();
+        assert!(file.clone().embed_lsb(&[payload.as_slice(), &[0]].concat()).is_err());
+        file.embed_lsb(&payload).unwrap();
+
+        assert_eq!(file.extract_lsb().unwrap(), payload);
+        assert!(file.parsed.chunks.iter().any(|chunk| chunk.chunk_type == *b"tEXt"));
+
+        // No channel moves by more than one step
+        let stego = decode_pixels(&file.raw_data);
+        assert_eq!(stego.len(), pixels.len());
+        assert!(stego.iter().zip(&pixels).all(|(a, b)| a.abs_diff(*b) <= 1));
+        assert_ne!(stego, pixels);
+    }
+
+    #[test]
+    fn test_oversized_ihdr_does_not_overflow_info() {
+        let with_size = |size: u32| {
+            let mut png_data = minimal_png();
+            png_data[16..24].copy_from_slice(&[size.to_be_bytes(), size.to_be_bytes()].concat());
+            let crc = chunk_crc(b"IHDR", &png_data[16..29]);
+            png_data[29..33].copy_from_slice(&crc.to_be_bytes());
+            PngFile::from_data(png_data).unwrap()
+        };
+
+        let huge = with_size(u32::MAX);
+        assert!(huge.lsb_capacity().is_err());
+        let info = crate::cli::format_png_info(&huge).unwrap();
+        assert!(!info.contains("LSB capacity"));
+
+        // The largest legal dimensions saturate instead of overflowing
+        let largest = with_size(i32::MAX as u32);
+        assert!(largest.lsb_capacity_with_bits(8).unwrap() > 0);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_lsb_capacity_matches_embedding() {
+        let (png_data, _) = encode_rgb_png(20, 10);
+        let file = PngFile::from_data(png_data).unwrap();
+
+        // 20 * 10 pixels * 3 channels, less the 4-byte length
+        assert_eq!(file.lsb_capacity().unwrap(), 71);
+        assert_eq!(file.lsb_capacity_with_bits(2).unwrap(), 146);
+        assert!(file.lsb_capacity_with_bits(0).is_err());
+        assert!(crate::cli::format_png_info(&file).unwrap().contains("LSB capacity: 71 bytes"));
+
+        for bits in [1, 2] {
+            let capacity = file.lsb_capacity_with_bits(bits).unwrap();
+            let payload = vec![0xA5; capacity];
+            let mut stego = file.clone();
+            stego.embed_lsb_with_bits(&payload, bits).unwrap();
+            assert_eq!(stego.extract_lsb_with_bits(bits).unwrap(), payload);
+            assert!(file.clone().embed_lsb_with_bits(&[payload.as_slice(), &[0]].concat(), bits).is_err());
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decode_carrier_of_idat_polyglot() {
+        let mut carrier = Vec::new();
+        let mut encoder = ::png::Encoder::new(&mut carrier, 1, 1);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.write_header().unwrap().write_image_data(&[0x12, 0x34, 0x56]).unwrap();
+
+        let mut file = PngFile::from_data(carrier).unwrap();
+        let image_end = file.compute_idat_zlib_end().unwrap();
+        file.append_to_idat(b"PK\x03\x04 appended payload").unwrap();
+        assert_eq!(file.compute_idat_zlib_end().unwrap(), image_end);
+
+        assert_eq!(file.decode_to_rgba().unwrap(), (1, 1, vec![0x12, 0x34, 0x56, 0xFF]));
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_compression_apis_report_disabled_feature() {
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        let message = "feature `compression` not enabled";
+
+        assert_eq!(file.decode_to_rgba().unwrap_err().to_string(), message);
+        assert_eq!(file.embed_in_zlib_stream(b"payload").unwrap_err().to_string(), message);
+        assert_eq!(file.add_iccp_payload("profile", b"payload").unwrap_err().to_string(), message);
+        assert_eq!(file.embed_lsb(b"payload").unwrap_err().to_string(), message);
+        assert!(!file.synthesize_idat().unwrap()); // Already has image data
+    }
+
+    #[test]
+    fn test_time_chunk_round_trip() {
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        assert_eq!(file.time_chunk().unwrap(), None);
+
+        file.set_time_chunk(2024, 2, 29, 23, 59, 60).unwrap();
+        let time_chunk = &file.parsed.chunks[1];
+        assert_eq!(time_chunk.chunk_type, *b"tIME");
+        assert_eq!(time_chunk.data, [0x07, 0xE8, 2, 29, 23, 59, 60]); // 2024 big-endian
+
+        // Setting again replaces rather than adding a second tIME
+        file.set_time_chunk(1999, 12, 31, 0, 0, 0).unwrap();
+        let reloaded = PngFile::from_data(file.raw_data.clone()).unwrap();
+        assert_eq!(reloaded.parsed.chunks.iter().filter(|chunk| chunk.chunk_type == *b"tIME").count(), 1);
+        assert_eq!(reloaded.time_chunk().unwrap().unwrap().to_string(), "1999-12-31 00:00:00 UTC");
+
+        assert!(matches!(file.set_time_chunk(2024, 13, 1, 0, 0, 0), Err(PolyglotError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_phys_survives_embedding() {
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        assert_eq!(file.get_phys().unwrap(), None);
+
+        file.set_phys(2835, 2835, 1).unwrap();
+        file.set_phys(11811, 11811, 1).unwrap(); // 300 DPI, replacing the first
+        assert!(matches!(file.set_phys(1, 1, 2), Err(PolyglotError::InvalidInput(_))));
+
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(file.raw_data, crate::testutil::minimal_zip()).unwrap();
+        let polyglot = PngFile::from_data(creator.create_polyglot_in_memory_with_method(crate::polyglot::EmbeddingMethod::Text).unwrap()).unwrap();
+
+        let phys_index = polyglot.parsed.chunks.iter().position(|chunk| chunk.chunk_type == *b"pHYs").unwrap();
+        let idat_index = polyglot.parsed.chunks.iter().position(|chunk| chunk.chunk_type == *b"IDAT").unwrap();
+        assert!(phys_index < idat_index);
+        assert_eq!(polyglot.parsed.chunks.iter().filter(|chunk| chunk.chunk_type == *b"pHYs").count(), 1);
+        assert_eq!(polyglot.get_phys().unwrap(), Some(PhysicalDimensions { x_ppu: 11811, y_ppu: 11811, unit: 1 }));
+    }
+
+    #[test]
+    fn test_splt_payload_round_trip() {
+        let payload: Vec<u8> = (0..=255u8).chain(0..7).collect(); // Not a multiple of the entry size
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        file.add_splt_payload("Café palette", &payload).unwrap();
+
+        // Placed before IDAT, with the entries a whole number of 6-byte palette entries
+        assert_eq!(file.parsed.chunks[1].chunk_type, *b"sPLT");
+        let entries = file.parsed.chunks[1].data.len() - "Café palette".chars().count() - 2;
+        assert_eq!(entries % SPLT_ENTRY_SIZE, 0);
+
+        let reloaded = PngFile::from_data(file.raw_data.clone()).unwrap();
+        assert_eq!(reloaded.extract_splt_payload("Café palette").unwrap(), Some(payload));
+        assert_eq!(reloaded.extract_splt_payload("other").unwrap(), None);
+
+        for name in ["", " leading", "two  spaces", "snow ☃", &"x".repeat(80)] {
+            assert!(matches!(file.add_splt_payload(name, b"x"), Err(PolyglotError::InvalidInput(_))), "{name:?}");
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_iccp_payload_round_trip() {
+        let payload = b"PK\x03\x04 compressible compressible compressible".repeat(20);
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        assert_eq!(file.extract_iccp_payload().unwrap(), None);
+        file.add_iccp_payload("sRGB profile", &payload).unwrap();
+
+        let iccp = &file.parsed.chunks[1];
+        assert_eq!(iccp.chunk_type, *b"iCCP");
+        assert!(iccp.data.starts_with(b"sRGB profile\0\0"));
+        assert!(iccp.data.len() < payload.len());
+
+        let reloaded = PngFile::from_data(file.raw_data.clone()).unwrap();
+        assert_eq!(reloaded.extract_iccp_payload().unwrap(), Some(payload));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_second_iccp_is_rejected() {
+        let mut file = PngFile::from_data(minimal_png()).unwrap();
+        file.add_iccp_payload("first", b"one").unwrap();
+        let before = file.raw_data.clone();
+
+        let result = file.add_iccp_payload("second", b"two");
+        assert!(matches!(result, Err(PolyglotError::InvalidInput(_))));
+        assert_eq!(file.raw_data, before);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_zlib_stream_embedding_keeps_pixels() {
+        let (png_data, pixels) = encode_rgb_png(5, 3);
+        let payload: Vec<u8> = (0..70_000u32).map(|i| (i % 256) as u8).collect(); // Two stored blocks
+
+        let mut file = PngFile::from_data(png_data).unwrap();
+        file.embed_in_zlib_stream(&payload).unwrap();
+
+        // IDAT is one complete zlib stream and the image decodes unchanged
+        assert_eq!(decode_pixels(file.as_bytes()), pixels);
+
+        // Payload comes back out
+        assert_eq!(file.extract_zlib_payload().unwrap(), payload);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_invalid_idat_stream_is_a_decode_error() {
+        let mut png_data = minimal_png();
+        let idat_offset = png_data.windows(4).position(|w| w == b"IDAT").unwrap() + 4;
+        png_data[idat_offset + 2] = 0xFF; // Reserved deflate block type
+        let crc_offset = idat_offset + crate::utils::read_u32_be(&png_data, idat_offset - 8) as usize;
+        let crc = crate::utils::calculate_crc32(&png_data[idat_offset - 4..crc_offset]);
+        png_data[crc_offset..crc_offset + 4].copy_from_slice(&crc.to_be_bytes());
+
+        let file = PngFile::from_data(png_data).unwrap();
+        assert!(matches!(file.extract_zlib_payload(), Err(PolyglotError::DecodeError(_))));
+    }
 }
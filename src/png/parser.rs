@@ -1,5 +1,6 @@
 //! Low-level PNG chunk parsing using manual byte slicing
 
+use alloc::{format, string::{String, ToString}, vec::Vec};
 use crate::utils::{read_u32_be};
 use crate::PolyglotError;
 
@@ -10,7 +11,7 @@ pub struct Chunk {
     pub chunk_type: [u8; 4],
     pub data: Vec<u8>,
     pub crc: u32,
-    pub data_offset: usize, // Offset of chunk data in file
+    pub data_offset: usize, // Absolute file offset of the chunk data
 }
 
 /// PNG file representation
@@ -19,8 +20,188 @@ pub struct ParsedPng {
     pub chunks: Vec<Chunk>,
 }
 
+impl Chunk {
+    /// Absolute file offset of the chunk's length field
+    pub fn start_offset(&self) -> usize {
+        self.data_offset - 8
+    }
+
+    /// Absolute file offset just past the chunk's CRC
+    pub fn end_offset(&self) -> usize {
+        self.data_offset + self.data.len() + 4
+    }
+}
+
+impl ParsedPng {
+    /// Chunk containing the absolute file offset, with the offset's position relative to the chunk start
+    pub fn chunk_at_offset(&self, offset: usize) -> Option<(&Chunk, usize)> {
+        self.chunks.iter()
+            .find(|chunk| (chunk.start_offset()..chunk.end_offset()).contains(&offset))
+            .map(|chunk| (chunk, offset - chunk.start_offset()))
+    }
+
+    /// Check that there is exactly one IEND chunk and that it comes last
+    pub fn assert_iend_last(&self) -> Result<(), PolyglotError> {
+        let iend_count = self.chunks.iter().filter(|chunk| chunk.chunk_type == *b"IEND").count();
+        if iend_count != 1 {
+            return Err(PolyglotError::ValidationFailed(format!("expected one IEND chunk, found {}", iend_count)));
+        }
+        match self.chunks.last() {
+            Some(chunk) if chunk.chunk_type == *b"IEND" => Ok(()),
+            Some(chunk) => Err(PolyglotError::ValidationFailed(format!(
+                "IEND is not the final chunk; {} follows it",
+                String::from_utf8_lossy(&chunk.chunk_type)
+            ))),
+            None => unreachable!("an IEND chunk was counted"),
+        }
+    }
+}
+
+/// Image properties declared in the IHDR chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageHeader {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub interlace: u8,
+}
+
+impl ImageHeader {
+    /// Bits per pixel for the colour type and bit depth
+    pub fn bits_per_pixel(&self) -> usize {
+        let channels = match self.color_type {
+            2 => 3, // RGB
+            4 => 2, // Greyscale + alpha
+            6 => 4, // RGBA
+            _ => 1, // Greyscale, palette
+        };
+        channels * self.bit_depth as usize
+    }
+
+    /// Size of the decompressed image data: filtered scanlines, summed over Adam7 passes if interlaced
+    ///
+    /// Saturates at `usize::MAX` for dimensions too large to address.
+    pub fn raw_data_len(&self) -> usize {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let scanlines_len = |w: usize, h: usize| {
+            if w == 0 || h == 0 { 0 } else { h.saturating_mul(1 + w.saturating_mul(self.bits_per_pixel()).div_ceil(8)) }
+        };
+
+        if self.interlace == 0 {
+            return scanlines_len(width, height);
+        }
+
+        // Adam7 passes as (x start, y start, x step, y step)
+        const PASSES: [(usize, usize, usize, usize); 7] = [
+            (0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4),
+            (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2),
+        ];
+        PASSES.iter()
+            .map(|&(x0, y0, dx, dy)| {
+                let pass_width = width.saturating_sub(x0).div_ceil(dx);
+                let pass_height = height.saturating_sub(y0).div_ceil(dy);
+                scanlines_len(pass_width, pass_height)
+            })
+            .fold(0, usize::saturating_add)
+    }
+}
+
+/// Last modification time from a tIME chunk, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModificationTime {
+    pub year: u16,
+    pub month: u8,  // 1-12
+    pub day: u8,    // 1-31
+    pub hour: u8,   // 0-23
+    pub minute: u8, // 0-59
+    pub second: u8, // 0-60, allowing a leap second
+}
+
+impl ModificationTime {
+    /// The 7-byte tIME chunk data: big-endian year, then month, day, hour, minute, second
+    pub fn to_bytes(&self) -> [u8; 7] {
+        let [year_high, year_low] = self.year.to_be_bytes();
+        [year_high, year_low, self.month, self.day, self.hour, self.minute, self.second]
+    }
+
+    /// Check each field against the ranges the PNG spec allows
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month) && (1..=31).contains(&self.day)
+            && self.hour <= 23 && self.minute <= 59 && self.second <= 60
+    }
+}
+
+impl core::fmt::Display for ModificationTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+/// Pixel density from a pHYs chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub x_ppu: u32, // Pixels per unit, X axis
+    pub y_ppu: u32, // Pixels per unit, Y axis
+    pub unit: u8,   // 0 unknown (aspect ratio only), 1 metre
+}
+
+impl PhysicalDimensions {
+    /// The 9-byte pHYs chunk data: big-endian X and Y densities, then the unit
+    pub fn to_bytes(&self) -> [u8; 9] {
+        let mut bytes = [0u8; 9];
+        bytes[0..4].copy_from_slice(&self.x_ppu.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.y_ppu.to_be_bytes());
+        bytes[8] = self.unit;
+        bytes
+    }
+}
+
+impl core::fmt::Display for PhysicalDimensions {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.unit {
+            1 => write!(f, "{} x {} pixels per metre", self.x_ppu, self.y_ppu),
+            _ => write!(f, "{}:{} pixel aspect ratio", self.x_ppu, self.y_ppu),
+        }
+    }
+}
+
+/// Checks `parse_png_chunks_opts` applies while walking the chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Fail with `CrcMismatch` when a chunk's stored CRC is wrong
+    pub verify_crc: bool,
+    /// Stop at the first IEND instead of parsing chunks that follow it
+    pub stop_at_iend: bool,
+    /// Accept chunk types that aren't ASCII letters, and don't second-guess chunk lengths
+    pub lenient: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { verify_crc: true, stop_at_iend: true, lenient: false }
+    }
+}
+
 /// Parse PNG chunks from byte data
 pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
+    parse_png_chunks_opts(data, ParseOptions::default())
+}
+
+/// Parse PNG chunks, accepting chunk types that aren't ASCII letters
+pub fn parse_png_chunks_lenient(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
+    parse_png_chunks_opts(data, ParseOptions { lenient: true, ..ParseOptions::default() })
+}
+
+/// Check that a chunk type consists of ASCII letters as the PNG spec requires
+pub fn is_valid_chunk_type(chunk_type: &[u8; 4]) -> bool {
+    chunk_type.iter().all(|b| b.is_ascii_alphabetic())
+}
+
+/// Parse PNG chunks, choosing whether CRCs are checked, whether parsing ends at IEND and
+/// whether odd chunk types are accepted
+pub fn parse_png_chunks_opts(data: &[u8], options: ParseOptions) -> Result<ParsedPng, PolyglotError> {
     if !crate::utils::is_png_signature(data) {
         return Err(PolyglotError::PngParse("Invalid PNG signature".to_string()));
     }
@@ -29,6 +210,7 @@ pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
     let mut chunks = Vec::new();
 
     while offset + 12 <= data.len() {
+        let chunk_start = offset;
         let length = read_u32_be(data, offset);
         offset += 4;
 
@@ -38,10 +220,37 @@ pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
         let chunk_type = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
         offset += 4;
 
+        if !options.lenient && !is_valid_chunk_type(&chunk_type) {
+            return Err(PolyglotError::PngParse(format!(
+                "unexpected chunk type 0x{:08x} at offset {}",
+                u32::from_be_bytes(chunk_type),
+                chunk_start
+            )));
+        }
+
         let data_end = offset + length as usize;
         if data_end > data.len() {
             return Err(PolyglotError::PngParse("Chunk data extends beyond file".to_string()));
         }
+        if data_end + 4 > data.len() {
+            return Err(PolyglotError::PngParse("Chunk CRC extends beyond file".to_string()));
+        }
+
+        // A wrong declared length shows up as garbage where the next chunk's type should be,
+        // which is a clearer report than the CRC mismatch it also causes
+        let next_type_offset = data_end + 8;
+        if !options.lenient && &chunk_type != b"IEND" && next_type_offset + 8 <= data.len() {
+            let next_type = [data[next_type_offset], data[next_type_offset + 1], data[next_type_offset + 2], data[next_type_offset + 3]];
+            if !is_valid_chunk_type(&next_type) {
+                return Err(PolyglotError::PngParse(format!(
+                    "{} chunk at offset {} declares {} data bytes, but no chunk type follows at offset {}; its length is likely wrong",
+                    String::from_utf8_lossy(&chunk_type),
+                    chunk_start,
+                    length,
+                    next_type_offset
+                )));
+            }
+        }
 
         let chunk_data = data[offset..data_end].to_vec();
         offset = data_end;
@@ -50,15 +259,14 @@ pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
         offset += 4;
 
         // Verify CRC
-        let mut crc_data = Vec::with_capacity(4 + length as usize);
-        crc_data.extend_from_slice(&chunk_type);
-        crc_data.extend_from_slice(&chunk_data);
-        let calculated_crc = crate::utils::calculate_crc32(&crc_data);
+        if options.verify_crc {
+            let calculated_crc = super::chunk_crc(&chunk_type, &chunk_data);
 
-        if crc != calculated_crc {
-            return Err(PolyglotError::CrcMismatch(
-                String::from_utf8_lossy(&chunk_type).to_string()
-            ));
+            if crc != calculated_crc {
+                return Err(PolyglotError::CrcMismatch(
+                    String::from_utf8_lossy(&chunk_type).to_string()
+                ));
+            }
         }
 
         chunks.push(Chunk {
@@ -66,11 +274,11 @@ pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
             chunk_type,
             data: chunk_data,
             crc,
-            data_offset: offset - length as usize - 8, // Start of data relative to chunk start
+            data_offset: chunk_start + 8, // After the length and type fields
         });
 
         // IEND indicates end of PNG chunks
-        if &chunk_type == b"IEND" {
+        if options.stop_at_iend && &chunk_type == b"IEND" {
             break;
         }
     }
@@ -82,6 +290,30 @@ pub fn parse_png_chunks(data: &[u8]) -> Result<ParsedPng, PolyglotError> {
     Ok(ParsedPng { chunks })
 }
 
+/// Chunks that still parse from `offset` on, stopping at the first bytes that aren't one
+///
+/// Meant for data after IEND, which may be more chunks or anything else appended to the file:
+/// a bad chunk type, a length past the end of the data or a wrong CRC ends the list.
+pub fn parse_trailing_chunks(data: &[u8], mut offset: usize) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    while offset + 12 <= data.len() {
+        let length = read_u32_be(data, offset);
+        let chunk_type = [data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]];
+        let data_offset = offset + 8;
+        let Some(crc_offset) = data_offset.checked_add(length as usize).filter(|end| end + 4 <= data.len()) else {
+            break;
+        };
+        let chunk_data = &data[data_offset..crc_offset];
+        let crc = read_u32_be(data, crc_offset);
+        if !is_valid_chunk_type(&chunk_type) || crc != super::chunk_crc(&chunk_type, chunk_data) {
+            break;
+        }
+        chunks.push(Chunk { length, chunk_type, data: chunk_data.to_vec(), crc, data_offset });
+        offset = crc_offset + 4;
+    }
+    chunks
+}
+
 /// Find the first IDAT chunk in parsed PNG
 pub fn find_first_idat(png: &ParsedPng) -> Result<&Chunk, PolyglotError> {
     for chunk in &png.chunks {
@@ -92,6 +324,78 @@ pub fn find_first_idat(png: &ParsedPng) -> Result<&Chunk, PolyglotError> {
     Err(PolyglotError::NoIdatChunk)
 }
 
+/// Parse the IHDR chunk, which must come first
+pub fn parse_ihdr(png: &ParsedPng) -> Result<ImageHeader, PolyglotError> {
+    let chunk = png.chunks.first()
+        .filter(|chunk| &chunk.chunk_type == b"IHDR" && chunk.data.len() >= 13)
+        .ok_or_else(|| PolyglotError::ChunkNotFound("IHDR".to_string()))?;
+
+    let (bit_depth, color_type) = (chunk.data[8], chunk.data[9]);
+    let allowed_depths: &[u8] = match color_type {
+        0 => &[1, 2, 4, 8, 16], // Greyscale
+        2 => &[8, 16],          // RGB
+        3 => &[1, 2, 4, 8],     // Palette
+        4 => &[8, 16],          // Greyscale + alpha
+        6 => &[8, 16],          // RGBA
+        _ => return Err(PolyglotError::PngParse(format!("IHDR has invalid color type {}", color_type))),
+    };
+    if !allowed_depths.contains(&bit_depth) {
+        return Err(PolyglotError::PngParse(format!(
+            "IHDR bit depth {} is not allowed for color type {} (allowed: {:?})", bit_depth, color_type, allowed_depths
+        )));
+    }
+
+    // The spec limits both dimensions to 2^31-1, like every other PNG four-byte integer
+    let (width, height) = (read_u32_be(&chunk.data, 0), read_u32_be(&chunk.data, 4));
+    if width > i32::MAX as u32 || height > i32::MAX as u32 {
+        return Err(PolyglotError::PngParse(format!("IHDR dimensions {}x{} exceed 2^31-1", width, height)));
+    }
+
+    Ok(ImageHeader {
+        width,
+        height,
+        bit_depth,
+        color_type,
+        interlace: chunk.data[12],
+    })
+}
+
+/// Parse the tIME chunk, if the image has one
+pub fn parse_time(png: &ParsedPng) -> Result<Option<ModificationTime>, PolyglotError> {
+    let Some(chunk) = png.chunks.iter().find(|chunk| &chunk.chunk_type == b"tIME") else {
+        return Ok(None);
+    };
+    if chunk.data.len() != 7 {
+        return Err(PolyglotError::PngParse(format!("tIME chunk has {} bytes, expected 7", chunk.data.len())));
+    }
+
+    let data = &chunk.data;
+    Ok(Some(ModificationTime {
+        year: u16::from_be_bytes([data[0], data[1]]),
+        month: data[2],
+        day: data[3],
+        hour: data[4],
+        minute: data[5],
+        second: data[6],
+    }))
+}
+
+/// Parse the pHYs chunk, if the image has one
+pub fn parse_phys(png: &ParsedPng) -> Result<Option<PhysicalDimensions>, PolyglotError> {
+    let Some(chunk) = png.chunks.iter().find(|chunk| &chunk.chunk_type == b"pHYs") else {
+        return Ok(None);
+    };
+    if chunk.data.len() != 9 {
+        return Err(PolyglotError::PngParse(format!("pHYs chunk has {} bytes, expected 9", chunk.data.len())));
+    }
+
+    Ok(Some(PhysicalDimensions {
+        x_ppu: read_u32_be(&chunk.data, 0),
+        y_ppu: read_u32_be(&chunk.data, 4),
+        unit: chunk.data[8],
+    }))
+}
+
 /// Get all IDAT chunks
 pub fn find_all_idat(png: &ParsedPng) -> Vec<&Chunk> {
     png.chunks.iter().filter(|c| &c.chunk_type == b"IDAT").collect()
@@ -101,12 +405,187 @@ pub fn find_all_idat(png: &ParsedPng) -> Vec<&Chunk> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_data_offset_is_absolute() {
+        let png_data = crate::testutil::minimal_png();
+        let parsed = parse_png_chunks(&png_data).unwrap();
+
+        for chunk in &parsed.chunks {
+            let end = chunk.data_offset + chunk.length as usize;
+            assert_eq!(&png_data[chunk.data_offset..end], chunk.data.as_slice());
+            assert_eq!(&png_data[chunk.data_offset - 4..chunk.data_offset], &chunk.chunk_type);
+        }
+    }
+
+    #[test]
+    fn test_ihdr_depth_and_color_type_combinations() {
+        let with_ihdr = |bit_depth: u8, color_type: u8| {
+            let mut png = parse_png_chunks(&crate::testutil::minimal_png()).unwrap();
+            png.chunks[0].data[8] = bit_depth;
+            png.chunks[0].data[9] = color_type;
+            parse_ihdr(&png)
+        };
+
+        let header = with_ihdr(8, 2).unwrap();
+        assert_eq!((header.bit_depth, header.color_type), (8, 2));
+
+        match with_ihdr(16, 3) {
+            Err(PolyglotError::PngParse(message)) => {
+                assert_eq!(message, "IHDR bit depth 16 is not allowed for color type 3 (allowed: [1, 2, 4, 8])");
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+        assert!(with_ihdr(8, 5).is_err());
+    }
+
+    #[test]
+    fn test_oversized_ihdr_dimensions_are_rejected() {
+        let with_size = |width: u32, height: u32| {
+            let mut png = parse_png_chunks(&crate::testutil::minimal_png()).unwrap();
+            png.chunks[0].data[0..4].copy_from_slice(&width.to_be_bytes());
+            png.chunks[0].data[4..8].copy_from_slice(&height.to_be_bytes());
+            parse_ihdr(&png)
+        };
+
+        assert!(with_size(i32::MAX as u32, i32::MAX as u32).is_ok());
+        match with_size(u32::MAX, u32::MAX) {
+            Err(PolyglotError::PngParse(message)) => assert_eq!(message, "IHDR dimensions 4294967295x4294967295 exceed 2^31-1"),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+        assert!(with_size(1, 1 << 31).is_err());
+    }
+
+    #[test]
+    fn test_chunk_at_offset() {
+        let png_data = crate::testutil::minimal_png();
+        let parsed = parse_png_chunks(&png_data).unwrap();
+        let idat = find_first_idat(&parsed).unwrap();
+
+        // Inside the IDAT data: 8 bytes of length and type precede it
+        let (chunk, position) = parsed.chunk_at_offset(idat.data_offset + 3).unwrap();
+        assert_eq!(&chunk.chunk_type, b"IDAT");
+        assert_eq!(position, 8 + 3);
+
+        // Signature bytes belong to no chunk, nor does anything past IEND
+        assert!(parsed.chunk_at_offset(4).is_none());
+        assert!(parsed.chunk_at_offset(png_data.len()).is_none());
+        assert_eq!(&parsed.chunk_at_offset(8).unwrap().0.chunk_type, b"IHDR");
+    }
+
+    #[test]
+    fn test_assert_iend_last() {
+        let parsed = parse_png_chunks(&crate::testutil::minimal_png()).unwrap();
+        parsed.assert_iend_last().unwrap();
+
+        // A chunk inserted after IEND
+        let mut misplaced = parsed.clone();
+        let idat = find_first_idat(&parsed).unwrap().clone();
+        misplaced.chunks.push(idat);
+        match misplaced.assert_iend_last() {
+            Err(PolyglotError::ValidationFailed(message)) => {
+                assert_eq!(message, "IEND is not the final chunk; IDAT follows it");
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+
+        // A duplicated IEND, and none at all
+        let mut duplicated = parsed.clone();
+        duplicated.chunks.push(parsed.chunks.last().unwrap().clone());
+        assert!(duplicated.assert_iend_last().is_err());
+        let mut missing = parsed.clone();
+        missing.chunks.pop();
+        assert!(missing.assert_iend_last().is_err());
+    }
+
+    #[test]
+    fn test_short_idat_length_is_diagnosed() {
+        let mut png_data = crate::testutil::minimal_png();
+        let idat_start = find_first_idat(&parse_png_chunks(&png_data).unwrap()).unwrap().start_offset();
+
+        // Declare two bytes fewer than the IDAT holds
+        let declared = read_u32_be(&png_data, idat_start) - 2;
+        png_data[idat_start..idat_start + 4].copy_from_slice(&declared.to_be_bytes());
+
+        match parse_png_chunks(&png_data) {
+            Err(PolyglotError::PngParse(message)) => assert_eq!(message, format!(
+                "IDAT chunk at offset {} declares {} data bytes, but no chunk type follows at offset {}; its length is likely wrong",
+                idat_start, declared, idat_start + 8 + declared as usize + 8
+            )),
+            other => panic!("expected a length diagnostic, got {:?}", other.map(|_| ())),
+        }
+
+        // Lenient parsing doesn't second-guess lengths and stops at the CRC instead
+        assert!(matches!(parse_png_chunks_lenient(&png_data), Err(PolyglotError::CrcMismatch(_))));
+    }
+
+    #[test]
+    fn test_crc_check_can_be_skipped() {
+        let mut png_data = crate::testutil::minimal_png();
+        let idat = find_first_idat(&parse_png_chunks(&png_data).unwrap()).unwrap().clone();
+        png_data[idat.end_offset() - 1] ^= 0xFF;
+
+        assert!(matches!(parse_png_chunks(&png_data), Err(PolyglotError::CrcMismatch(_))));
+        let options = ParseOptions { verify_crc: false, ..ParseOptions::default() };
+        let parsed = parse_png_chunks_opts(&png_data, options).unwrap();
+        let damaged = find_first_idat(&parsed).unwrap();
+        assert_eq!(damaged.data, idat.data);
+        assert_eq!(damaged.crc, idat.crc ^ 0xFF);
+    }
+
+    #[test]
+    fn test_chunks_after_iend_can_be_kept() {
+        let mut png_data = crate::testutil::minimal_png();
+        let chunk_data = b"trailing";
+        png_data.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+        png_data.extend_from_slice(b"tEXt");
+        png_data.extend_from_slice(chunk_data);
+        png_data.extend_from_slice(&crate::utils::calculate_crc32(&[b"tEXt".as_slice(), chunk_data].concat()).to_be_bytes());
+
+        assert_eq!(parse_png_chunks(&png_data).unwrap().chunks.last().unwrap().chunk_type, *b"IEND");
+        let options = ParseOptions { stop_at_iend: false, ..ParseOptions::default() };
+        let parsed = parse_png_chunks_opts(&png_data, options).unwrap();
+        let trailing = parsed.chunks.last().unwrap();
+        assert_eq!(trailing.chunk_type, *b"tEXt");
+        assert_eq!(trailing.data, chunk_data);
+        assert!(parsed.assert_iend_last().is_err());
+    }
+
     #[test]
     fn test_parse_empty_png() {
         let result = parse_png_chunks(&[0, 1, 2]);
         assert!(matches!(result, Err(PolyglotError::PngParse(_))));
     }
 
+    #[test]
+    fn test_invalid_chunk_type_diagnostic() {
+        let mut png = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+        // Chunk with a null byte in its type, but a correct CRC
+        let chunk_type = *b"ID\0T";
+        let chunk_data = [1u8, 2, 3];
+        png.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+        png.extend_from_slice(&chunk_type);
+        png.extend_from_slice(&chunk_data);
+        let crc = crate::utils::calculate_crc32(&[chunk_type.as_slice(), &chunk_data].concat());
+        png.extend_from_slice(&crc.to_be_bytes());
+
+        let result = parse_png_chunks(&png);
+        match result {
+            Err(PolyglotError::PngParse(message)) => {
+                assert_eq!(message, "unexpected chunk type 0x49440054 at offset 8");
+            }
+            other => panic!("expected chunk type error, got {:?}", other),
+        }
+
+        // Lenient parsing records the chunk anyway
+        let parsed = parse_png_chunks_lenient(&png).unwrap();
+        assert_eq!(parsed.chunks[0].chunk_type, chunk_type);
+
+        // and combines with the other options
+        let options = ParseOptions { lenient: true, verify_crc: false, ..ParseOptions::default() };
+        assert_eq!(parse_png_chunks_opts(&png, options).unwrap().chunks[0].chunk_type, chunk_type);
+    }
+
     #[test]
     fn test_invalid_signature() {
         let invalid_png = [0x00, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]; // Invalid first byte
@@ -0,0 +1,204 @@
+//! Incremental PNG chunk parsing for data arriving in pieces
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+use crate::utils::{calculate_crc32, is_png_signature, read_u32_be};
+use crate::{PolyglotError, PolyglotResult};
+use super::parser::{is_valid_chunk_type, Chunk};
+
+/// Largest chunk length the PNG spec allows, 2^31-1
+pub const MAX_CHUNK_LENGTH: u32 = i32::MAX as u32;
+
+/// Push parser that emits each chunk as soon as its CRC has arrived
+///
+/// Each slice passed to `feed` is buffered whole, and parsed chunks are dropped from the
+/// buffer, so memory use is bounded by the chunk-size cap plus the largest slice fed rather
+/// than the file size; a declared length over the cap is rejected as soon as it is read, without
+/// waiting for its data. Bytes after IEND are ignored.
+#[derive(Debug)]
+pub struct StreamParser {
+    buffer: Vec<u8>,        // Bytes of the incomplete signature or chunk
+    buffer_offset: usize,   // File offset of buffer[0]
+    max_chunk_length: u32,  // Largest declared chunk length accepted
+    signature_checked: bool,
+    complete: bool,         // IEND has been parsed
+}
+
+impl Default for StreamParser {
+    fn default() -> Self {
+        Self::with_max_chunk_length(MAX_CHUNK_LENGTH)
+    }
+}
+
+impl StreamParser {
+    /// Create a parser expecting the PNG signature first
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser that rejects chunks declaring more than `max_chunk_length` data bytes
+    ///
+    /// The cap bounds the memory buffered for one chunk; it never goes above the spec's 2^31-1.
+    pub fn with_max_chunk_length(max_chunk_length: u32) -> Self {
+        Self {
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            max_chunk_length: max_chunk_length.min(MAX_CHUNK_LENGTH),
+            signature_checked: false,
+            complete: false,
+        }
+    }
+
+    /// Feed the next bytes, returning every chunk they complete
+    pub fn feed(&mut self, data: &[u8]) -> PolyglotResult<Vec<Chunk>> {
+        let mut chunks = Vec::new();
+        if self.complete {
+            return Ok(chunks);
+        }
+        self.buffer.extend_from_slice(data);
+
+        if !self.signature_checked {
+            if self.buffer.len() < 8 {
+                return Ok(chunks);
+            }
+            if !is_png_signature(&self.buffer) {
+                return Err(PolyglotError::PngParse("Invalid PNG signature".to_string()));
+            }
+            self.consume(8);
+            self.signature_checked = true;
+        }
+
+        while let Some(chunk) = self.next_chunk()? {
+            let is_iend = chunk.chunk_type == *b"IEND";
+            chunks.push(chunk);
+
+            // IEND indicates end of PNG chunks
+            if is_iend {
+                self.complete = true;
+                self.buffer.clear();
+                break;
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    /// Whether the IEND chunk has been parsed
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Total bytes accepted so far, excluding anything after IEND
+    pub fn bytes_parsed(&self) -> usize {
+        self.buffer_offset
+    }
+
+    /// Parse one chunk from the front of the buffer if all of it has arrived
+    fn next_chunk(&mut self) -> PolyglotResult<Option<Chunk>> {
+        if self.buffer.len() < 8 {
+            return Ok(None);
+        }
+
+        let length = read_u32_be(&self.buffer, 0);
+        let chunk_type = [self.buffer[4], self.buffer[5], self.buffer[6], self.buffer[7]];
+
+        // Reject bad types before buffering a bogus length's worth of data
+        if !is_valid_chunk_type(&chunk_type) {
+            return Err(PolyglotError::PngParse(format!(
+                "unexpected chunk type 0x{:08x} at offset {}",
+                u32::from_be_bytes(chunk_type),
+                self.buffer_offset
+            )));
+        }
+
+        if length > self.max_chunk_length {
+            return Err(PolyglotError::PngParse(format!(
+                "chunk length {} at offset {} exceeds the limit of {}",
+                length, self.buffer_offset, self.max_chunk_length
+            )));
+        }
+
+        let chunk_size = 12 + length as usize;
+        if self.buffer.len() < chunk_size {
+            return Ok(None);
+        }
+
+        let data = self.buffer[8..8 + length as usize].to_vec();
+        let crc = read_u32_be(&self.buffer, 8 + length as usize);
+        if crc != calculate_crc32(&self.buffer[4..8 + length as usize]) {
+            return Err(PolyglotError::CrcMismatch(String::from_utf8_lossy(&chunk_type).to_string()));
+        }
+
+        let chunk = Chunk {
+            length,
+            chunk_type,
+            data,
+            crc,
+            data_offset: self.buffer_offset + 8, // After the length and type fields
+        };
+        self.consume(chunk_size);
+
+        Ok(Some(chunk))
+    }
+
+    /// Drop bytes from the front of the buffer
+    fn consume(&mut self, count: usize) {
+        self.buffer.drain(..count);
+        self.buffer_offset += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::parser::parse_png_chunks;
+    use crate::testutil::minimal_png;
+
+    #[test]
+    fn test_byte_at_a_time_matches_parse_png_chunks() {
+        let png_data = minimal_png();
+        let expected = parse_png_chunks(&png_data).unwrap().chunks;
+
+        let mut parser = StreamParser::new();
+        let mut chunks = Vec::new();
+        for byte in &png_data {
+            chunks.extend(parser.feed(std::slice::from_ref(byte)).unwrap());
+        }
+
+        assert!(parser.is_complete());
+        assert_eq!(parser.bytes_parsed(), png_data.len());
+        assert_eq!(chunks.len(), expected.len());
+        for (chunk, expected) in chunks.iter().zip(&expected) {
+            assert_eq!(chunk.chunk_type, expected.chunk_type);
+            assert_eq!(chunk.length, expected.length);
+            assert_eq!(chunk.data, expected.data);
+            assert_eq!(chunk.crc, expected.crc);
+            assert_eq!(chunk.data_offset, expected.data_offset);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_crc_is_reported() {
+        let mut png_data = minimal_png();
+        png_data[30] ^= 0xFF; // Inside the IHDR CRC
+
+        let mut parser = StreamParser::new();
+        let result = parser.feed(&png_data[..20]).and_then(|_| parser.feed(&png_data[20..]));
+        assert!(matches!(result, Err(PolyglotError::CrcMismatch(_))));
+    }
+
+    #[test]
+    fn test_oversized_length_is_rejected_before_buffering() {
+        let png_data = minimal_png();
+        let mut header = png_data[..8].to_vec();
+        header.extend_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+        header.extend_from_slice(b"IDAT");
+
+        let result = StreamParser::new().feed(&header);
+        assert!(matches!(result, Err(PolyglotError::PngParse(_))));
+
+        // A lower cap rejects chunks the spec would allow; IHDR's 13 bytes are over a cap of 8
+        let result = StreamParser::with_max_chunk_length(8).feed(&png_data);
+        assert!(matches!(result, Err(PolyglotError::PngParse(_))));
+        assert!(StreamParser::with_max_chunk_length(1 << 20).feed(&png_data).is_ok());
+    }
+}
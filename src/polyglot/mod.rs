@@ -4,12 +4,193 @@ use std::path::Path;
 use crate::png::PngFile;
 use crate::zip::ZipArchive;
 use crate::flac::FlacFile;
+use crate::utils::{expect_format, FileFormat, ZIP_CENTRAL_DIRECTORY_SIGNATURE, ZIP_EOCD_SIGNATURE, ZIP_LOCAL_SIGNATURE};
 use crate::{PolyglotError, PolyglotResult};
 
+/// Embedding methods supported by the `create` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingMethod {
+    Idat,
+    Text,
+    Chunk,
+    Zip,
+    Comment,
+    Bidirectional,
+}
+
+impl EmbeddingMethod {
+    /// All methods, in the order they are listed to users
+    pub const ALL: [EmbeddingMethod; 6] = [
+        EmbeddingMethod::Text,
+        EmbeddingMethod::Chunk,
+        EmbeddingMethod::Zip,
+        EmbeddingMethod::Comment,
+        EmbeddingMethod::Idat,
+        EmbeddingMethod::Bidirectional,
+    ];
+
+    /// Name accepted by `--method`
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmbeddingMethod::Idat => "idat",
+            EmbeddingMethod::Text => "text",
+            EmbeddingMethod::Chunk => "chunk",
+            EmbeddingMethod::Zip => "zip",
+            EmbeddingMethod::Comment => "comment",
+            EmbeddingMethod::Bidirectional => "bidirectional",
+        }
+    }
+
+    /// Look up a method by its `--method` name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|method| method.name() == name)
+    }
+
+    /// Names of all methods, in `ALL` order
+    pub fn names() -> [&'static str; 6] {
+        Self::ALL.map(|method| method.name())
+    }
+
+    /// Format combinations the method applies to
+    pub fn formats(&self) -> &'static str {
+        match self {
+            EmbeddingMethod::Idat => "PNG+ZIP, PNG+WAV",
+            EmbeddingMethod::Text => "PNG+ZIP",
+            EmbeddingMethod::Chunk => "PNG+ZIP",
+            EmbeddingMethod::Zip => "PNG+ZIP (ZIP-dominant)",
+            EmbeddingMethod::Comment => "PNG+ZIP (empty ZIP)",
+            EmbeddingMethod::Bidirectional => "PNG+WAV",
+        }
+    }
+
+    /// Practical payload size limit
+    pub fn size_limit(&self) -> &'static str {
+        match self {
+            EmbeddingMethod::Idat => "split into 8 MB IDAT chunks, 4 GB total",
+            EmbeddingMethod::Text => "2 GB (PNG chunk length)",
+            EmbeddingMethod::Chunk => "2 GB (PNG chunk length)",
+            EmbeddingMethod::Zip => "4 GB (no ZIP64)",
+            EmbeddingMethod::Comment => "64 KB (ZIP comment length)",
+            EmbeddingMethod::Bidirectional => "4 GB (RIFF size field)",
+        }
+    }
+
+    /// Fixed bytes the method adds beyond the data it stores: chunk headers, CRCs and ZIP records
+    ///
+    /// PNG-dominant methods store the carrier and payload; `zip` and `comment` store only the
+    /// PNG, and `bidirectional` only the WAV, so those add their fixed framing to that alone.
+    /// This is a lower bound: splitting a large first IDAT (`idat`) and the colour chunks the
+    /// fabricated image copies (`bidirectional`) add more, which `overhead_bytes_for` includes.
+    pub fn overhead_bytes(&self) -> usize {
+        match self {
+            EmbeddingMethod::Idat => 0,
+            // tEXt chunk framing and its keyword
+            EmbeddingMethod::Text => 12 + b"ZIP Archive\0".len(),
+            EmbeddingMethod::Chunk => 12,
+            // Local header, central directory entry and EOCD around the stored PNG
+            EmbeddingMethod::Zip => 30 + 46 + 22 + 2 * b"image.png".len(),
+            EmbeddingMethod::Comment => 22,
+            // Signature, IHDR, IDAT framing and IEND of the fabricated image
+            EmbeddingMethod::Bidirectional => 8 + 25 + 12 + 12,
+        }
+    }
+
+    /// Exact bytes the method adds for this carrier, with IDAT data split at `DEFAULT_MAX_IDAT_SIZE`
+    ///
+    /// The payload's size never matters: `idat` keeps it whole in one chunk, so only the framing
+    /// of the chunks the carrier's first IDAT is split into is added to `overhead_bytes`, and
+    /// `bidirectional` adds the gAMA/cHRM/sRGB/iCCP chunks copied from the carrier.
+    pub fn overhead_bytes_for(&self, carrier: &PngFile) -> PolyglotResult<usize> {
+        let extra = match self {
+            EmbeddingMethod::Idat => {
+                let (idat_offset, idat_length) = carrier.find_first_idat()?;
+                carrier.appended_idat_offset(crate::png::DEFAULT_MAX_IDAT_SIZE)? - (idat_offset + idat_length)
+            }
+            EmbeddingMethod::Bidirectional => carrier.parsed.chunks.iter()
+                .filter(|chunk| COLOUR_CHUNK_TYPES.contains(&chunk.chunk_type))
+                .map(|chunk| 12 + chunk.data.len())
+                .sum(),
+            _ => 0,
+        };
+        Ok(self.overhead_bytes() + extra)
+    }
+
+    /// Absolute offset the payload will occupy in the output, computed before embedding
+    ///
+    /// For `zip` and `comment` the PNG is the embedded data, so its offset is returned.
+    pub fn projected_payload_offset(&self, carrier: &PngFile) -> PolyglotResult<usize> {
+        match self {
+            // Appended right after the first IDAT's data, behind any chunks that data is split into
+            EmbeddingMethod::Idat => carrier.appended_idat_offset(crate::png::DEFAULT_MAX_IDAT_SIZE),
+            // The tEXt or plZP chunk takes IEND's place; all of its overhead but the trailing
+            // CRC comes before the payload. Neither overhead depends on the payload's size.
+            EmbeddingMethod::Text | EmbeddingMethod::Chunk => {
+                let iend = carrier.parsed.chunks.iter()
+                    .find(|chunk| chunk.chunk_type == *b"IEND")
+                    .ok_or_else(|| PolyglotError::ChunkNotFound("IEND".to_string()))?;
+                Ok(iend.start_offset() + self.overhead_bytes() - 4)
+            }
+            // PNG stored after the local file header and its "image.png" name
+            EmbeddingMethod::Zip => Ok(30 + b"image.png".len()),
+            // PNG follows the EOCD record, which is the method's whole overhead
+            EmbeddingMethod::Comment => Ok(self.overhead_bytes()),
+            EmbeddingMethod::Bidirectional => Err(PolyglotError::InvalidInput(
+                "payload offset projection is not supported for the bidirectional method".to_string()
+            )),
+        }
+    }
+
+    /// Format the output is read as first, or `None` when it works under either extension
+    pub fn dominant_format(&self) -> Option<FileFormat> {
+        match self {
+            EmbeddingMethod::Idat | EmbeddingMethod::Text | EmbeddingMethod::Chunk => Some(FileFormat::Png),
+            EmbeddingMethod::Zip | EmbeddingMethod::Comment => Some(FileFormat::Zip),
+            EmbeddingMethod::Bidirectional => None,
+        }
+    }
+
+    /// Whether the carrier still renders or plays after embedding
+    pub fn carrier_behaviour(&self) -> &'static str {
+        match self {
+            EmbeddingMethod::Idat => "PNG renders with one IDAT; corrupted if the payload lands before later IDAT data",
+            EmbeddingMethod::Text => "PNG renders normally",
+            EmbeddingMethod::Chunk => "PNG renders normally, payload in a private plZP chunk",
+            EmbeddingMethod::Zip => "ZIP opens normally, PNG must be extracted",
+            EmbeddingMethod::Comment => "ZIP lists as empty, PNG starts at byte 22",
+            EmbeddingMethod::Bidirectional => "experimental, fabricated PNG image",
+        }
+    }
+}
+
+impl std::str::FromStr for EmbeddingMethod {
+    type Err = PolyglotError;
+
+    fn from_str(name: &str) -> PolyglotResult<Self> {
+        Self::from_name(name).ok_or_else(|| PolyglotError::InvalidInput(format!("Unknown embedding method: {}", name)))
+    }
+}
+
+impl std::fmt::Display for EmbeddingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// `S_IFREG`, the file type bits of a regular file in a Unix mode
+const UNIX_REGULAR_FILE: u32 = 0o100000;
+
+/// Source chunks the bidirectional builder copies so the fabricated image keeps its colour handling
+const COLOUR_CHUNK_TYPES: [[u8; 4]; 4] = [*b"gAMA", *b"cHRM", *b"sRGB", *b"iCCP"];
+
 /// Core orchestrator for creating PNG/ZIP polyglots
 pub struct PolyglotCreator {
     png: PngFile,
     zip: ZipArchive,
+    max_idat_size: usize,
+    max_output_size: Option<usize>,
+    hmac_key: Option<Vec<u8>>,
+    entry_mode: u32,
+    entry_is_text: bool,
 }
 
 /// Core orchestrator for creating PNG/WAV bidirectional polyglots (PNG-dominant - embeds WAV in PNG)
@@ -31,11 +212,316 @@ pub struct TrueBidirectionalPngWavCreator {
     wav: crate::wav::WavFile,
 }
 
+/// Embeds several payloads in one PNG carrier, applying them together with a single re-parse
+pub struct MultiPayloadBuilder {
+    png: PngFile,
+    payloads: Vec<(FileFormat, EmbeddingMethod, Vec<u8>)>,
+    named_payloads: Vec<(String, FileFormat, Vec<u8>)>,
+    write_index: bool,
+}
+
+/// Machine-readable record of how a polyglot was built
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreationLog {
+    pub method: String,
+    pub carrier_size: usize,
+    pub carrier_sha256: [u8; 32],
+    pub payload_size: usize,
+    pub payload_sha256: [u8; 32],
+    pub payload_offset: usize, // Offset of the payload within the output
+    pub output_size: usize,
+    pub output_sha256: [u8; 32],
+}
+
+impl CreationLog {
+    /// Render the record as JSON
+    pub fn to_json(&self) -> String {
+        format!(
+            concat!(
+                "{{\n",
+                "  \"method\": \"{}\",\n",
+                "  \"carrier\": {{ \"size\": {}, \"sha256\": \"{}\" }},\n",
+                "  \"payload\": {{ \"size\": {}, \"sha256\": \"{}\" }},\n",
+                "  \"payload_offset\": {},\n",
+                "  \"output\": {{ \"size\": {}, \"sha256\": \"{}\" }}\n",
+                "}}\n"
+            ),
+            self.method,
+            self.carrier_size, to_hex(&self.carrier_sha256),
+            self.payload_size, to_hex(&self.payload_sha256),
+            self.payload_offset,
+            self.output_size, to_hex(&self.output_sha256),
+        )
+    }
+
+    /// Write the JSON record to a file
+    pub fn write_to_file(&self, path: &Path) -> PolyglotResult<()> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+}
+
+/// SHA-256 digest of the data, fingerprinting the log's inputs and output
+fn sha256(data: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data).into()
+}
+
+/// Lowercase hex rendering of a digest
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Create a polyglot in memory, extract the payload in memory, and check both match
+///
+/// The payload is compared against what was actually embedded: the IDAT method rewrites
+/// the ZIP's offsets for its new position, and the ZIP method stores the carrier PNG
+/// inside a fresh archive, so the carrier is what must come back out.
+pub fn verify_roundtrip(carrier: &[u8], payload: &[u8], method: EmbeddingMethod) -> PolyglotResult<()> {
+    let (embedded, polyglot) = match method {
+        EmbeddingMethod::Idat | EmbeddingMethod::Text | EmbeddingMethod::Chunk => {
+            let mut creator = PolyglotCreator::from_data(carrier.to_vec(), payload.to_vec())?;
+            let polyglot = creator.create_polyglot_in_memory_with_method(method)?;
+            (creator.zip().as_bytes().to_vec(), polyglot)
+        }
+        EmbeddingMethod::Zip => {
+            let mut creator = PolyglotCreator::from_data(carrier.to_vec(), payload.to_vec())?;
+            let polyglot = creator.create_polyglot_in_memory_with_method(method)?;
+            (carrier.to_vec(), polyglot)
+        }
+        EmbeddingMethod::Comment => (carrier.to_vec(), build_zip_comment_polyglot(carrier)?),
+        EmbeddingMethod::Bidirectional => {
+            let creator = TrueBidirectionalPngWavCreator::from_data(carrier.to_vec(), payload.to_vec())?;
+            (payload.to_vec(), creator.build_bidirectional_polyglot())
+        }
+    };
+
+    verify_embedded_payload(&polyglot, &embedded, method)
+}
+
+/// Extract the payload `method` placed in `polyglot` and check it matches `embedded` byte for byte
+///
+/// `embedded` is what `verify_roundtrip` compares against: the offset-adjusted ZIP for the
+/// PNG-dominant methods, the carrier PNG for the zip and comment methods, the WAV for bidirectional.
+pub fn verify_embedded_payload(polyglot: &[u8], embedded: &[u8], method: EmbeddingMethod) -> PolyglotResult<()> {
+    let extracted = match method {
+        EmbeddingMethod::Idat | EmbeddingMethod::Text | EmbeddingMethod::Chunk => crate::extract::extract_zip_from_png_data(polyglot)?,
+        EmbeddingMethod::Zip => crate::extract::extract_png_from_zip_data(polyglot)?,
+        EmbeddingMethod::Comment => crate::extract::extract_png_from_zip_comment_data(polyglot)?,
+        EmbeddingMethod::Bidirectional => crate::extract::extract_wav_from_png_data(polyglot)?,
+    };
+
+    if embedded != extracted {
+        let first_difference = embedded.iter()
+            .zip(&extracted)
+            .position(|(a, b)| a != b)
+            .unwrap_or(embedded.len().min(extracted.len()));
+        return Err(PolyglotError::ValidationFailed(format!(
+            "{} round trip mismatch: embedded {} bytes, extracted {} bytes, first difference at byte {}",
+            method.name(), embedded.len(), extracted.len(), first_difference
+        )));
+    }
+
+    Ok(())
+}
+
+/// Build an empty ZIP whose EOCD comment is the PNG
+///
+/// The EOCD record is the whole archive, so the PNG signature lands at byte 22, the
+/// earliest position a ZIP allows. Viewers that insist on a signature at byte 0 need the
+/// PNG extracted first.
+pub fn build_zip_comment_polyglot(png_data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    crate::utils::ensure_payload_not_empty(png_data)?;
+    PngFile::from_data(png_data.to_vec())?;
+
+    let mut archive = ZipArchive::empty();
+    archive.set_comment(png_data)?;
+    Ok(archive.data)
+}
+
+/// Create an empty ZIP carrying the PNG in its EOCD comment
+pub fn create_zip_comment_polyglot(png_path: &Path, output_path: &Path) -> PolyglotResult<()> {
+    let png_data = read_input(png_path, FileFormat::Png, "png", "payload")?;
+    let polyglot = build_zip_comment_polyglot(&png_data)?;
+    std::fs::write(output_path, &polyglot)?;
+
+    println!("ZIP-comment polyglot created: {} bytes", polyglot.len());
+    Ok(())
+}
+
+/// Build a PNG carrying a second, complete PNG in its private `plZP` chunk
+///
+/// The carrier renders as itself; `extract::extract_png_from_png_data` recovers the inner
+/// image byte for byte. Both must be valid PNGs.
+pub fn build_png_in_png_polyglot(carrier_data: &[u8], inner_data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    crate::utils::ensure_payload_not_empty(inner_data)?;
+    PngFile::from_data(inner_data.to_vec())?;
+
+    let mut carrier = PngFile::from_data(carrier_data.to_vec())?;
+    carrier.add_payload_chunk(inner_data)?;
+    Ok(carrier.raw_data)
+}
+
+/// Create a PNG carrying a second PNG in a private chunk
+pub fn create_png_in_png_polyglot(carrier_path: &Path, inner_path: &Path, output_path: &Path) -> PolyglotResult<()> {
+    let carrier_data = read_input(carrier_path, FileFormat::Png, "png", "carrier")?;
+    let inner_data = read_input(inner_path, FileFormat::Png, "inner-png", "payload")?;
+    let polyglot = build_png_in_png_polyglot(&carrier_data, &inner_data)?;
+    std::fs::write(output_path, &polyglot)?;
+
+    println!("PNG-in-PNG polyglot created: {} bytes", polyglot.len());
+    Ok(())
+}
+
+/// Marks the head of a split payload, appended to the IDAT data
+///
+/// Layout: `plSP`, total payload length (u32 BE), head length (u32 BE), then the head bytes.
+pub const SPLIT_HEAD_MAGIC: [u8; 4] = *b"plSP";
+
+/// Marks the tail of a split payload, directly after IEND and followed by the remaining bytes
+pub const SPLIT_TAIL_MAGIC: [u8; 4] = *b"plST";
+
+/// Build a PNG whose payload is split: the first `head_len` bytes in IDAT, the rest after IEND
+///
+/// Both regions are framed (see `SPLIT_HEAD_MAGIC`) so `extract::extract_split_payload`
+/// can find and reassemble them. Anything the carrier had after IEND is dropped.
+pub fn build_split_payload_polyglot(png_data: &[u8], payload: &[u8], head_len: usize) -> PolyglotResult<Vec<u8>> {
+    crate::utils::ensure_payload_not_empty(payload)?;
+    if head_len > payload.len() {
+        return Err(PolyglotError::InvalidInput(format!(
+            "head of {} bytes is longer than the {}-byte payload", head_len, payload.len()
+        )));
+    }
+    let total_len = u32::try_from(payload.len())
+        .map_err(|_| PolyglotError::InvalidInput("split payloads are limited to 4 GB".to_string()))?;
+
+    let (head, tail) = payload.split_at(head_len);
+    let mut head_frame = SPLIT_HEAD_MAGIC.to_vec();
+    head_frame.extend_from_slice(&total_len.to_be_bytes());
+    head_frame.extend_from_slice(&(head_len as u32).to_be_bytes());
+    head_frame.extend_from_slice(head);
+
+    let png = PngFile::from_data(png_data.to_vec())?;
+    let mut polyglot = png.into_bytes_with_appended_idat(&head_frame, crate::png::DEFAULT_MAX_IDAT_SIZE)?;
+    polyglot.extend_from_slice(&SPLIT_TAIL_MAGIC);
+    polyglot.extend_from_slice(tail);
+    Ok(polyglot)
+}
+
+/// Private ancillary PNG chunk that carries the PDF document in a PNG+ZIP+PDF polyglot
+pub const PDF_CHUNK_TYPE: [u8; 4] = *b"pdFx";
+
+/// Build a file that is a PNG, a ZIP and a PDF at once
+///
+/// The PNG keeps its signature first. The PDF goes in a chunk right after IHDR so
+/// `%PDF` sits within the header search window, with its xref offsets rebased. The
+/// ZIP is appended after IEND with its offsets rebased, and its EOCD comment (any
+/// existing comment is replaced) repeats the PDF trailer so `startxref` and `%%EOF`
+/// are also found at the end of the file.
+pub fn build_triple_polyglot(png_data: &[u8], zip_data: &[u8], pdf_data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    let mut carrier = PngFile::from_data(png_data.to_vec())?;
+
+    // The chunk lands right after the signature and IHDR, so its data offset is known upfront
+    let pdf_offset = carrier.parsed.chunks.first()
+        .filter(|chunk| chunk.chunk_type == *b"IHDR")
+        .map(|chunk| chunk.end_offset() + 8)
+        .ok_or_else(|| PolyglotError::ChunkNotFound("IHDR".to_string()))?;
+    let xref_offset = crate::pdf::parse_xref(pdf_data)?.offset + pdf_offset;
+    let pdf = crate::pdf::shift_offsets(pdf_data, pdf_offset)?;
+    carrier.insert_chunk_after_ihdr(&PDF_CHUNK_TYPE, &pdf)?;
+
+    let mut zip = ZipArchive::from_data(zip_data.to_vec())?;
+    zip.update_central_directory_offsets(carrier.raw_data.len() as u64)?;
+    zip.set_comment(&crate::pdf::trailer_for(xref_offset))?;
+
+    let mut polyglot = carrier.raw_data;
+    polyglot.extend_from_slice(zip.as_bytes());
+    Ok(polyglot)
+}
+
+/// Create a file that is a PNG, a ZIP and a PDF at once
+pub fn create_triple(png_path: &Path, zip_path: &Path, pdf_path: &Path, output_path: &Path) -> PolyglotResult<()> {
+    let png_data = read_input(png_path, FileFormat::Png, "png", "carrier")?;
+    let zip_data = read_input(zip_path, FileFormat::Zip, "zip", "payload")?;
+    let pdf_data = read_input(pdf_path, FileFormat::Pdf, "pdf", "payload")?;
+
+    let polyglot = build_triple_polyglot(&png_data, &zip_data, &pdf_data)?;
+    std::fs::write(output_path, &polyglot)?;
+
+    println!("PNG+ZIP+PDF polyglot created: {} bytes", polyglot.len());
+    Ok(())
+}
+
+/// Keyword of the tEXt chunk holding a merged payload that has no method of its own
+pub const MERGED_PAYLOAD_KEYWORD: &str = "Merged Payload";
+
+/// Combine the payloads of two PNG-dominant polyglots into one PNG, using `a`'s carrier
+///
+/// Each payload takes the first free slot: a ZIP goes to the tEXt method, anything else
+/// (or a second ZIP) to IDAT, and a payload left over to a tEXt chunk keyed
+/// `MERGED_PAYLOAD_KEYWORD`. A payload index is written, and the result is checked to
+/// give both payloads back before it is returned.
+pub fn merge_polyglots(a: &[u8], b: &[u8]) -> PolyglotResult<Vec<u8>> {
+    let (carrier, payload_a) = crate::extract::split_polyglot_data(a)?;
+    let (_, payload_b) = crate::extract::split_polyglot_data(b)?;
+
+    let mut builder = MultiPayloadBuilder::new(PngFile::from_data(carrier)?);
+    builder.set_write_index(true);
+    let mut expected = Vec::new();
+    for payload in [payload_a, payload_b] {
+        let format = crate::utils::sniff_format(&payload);
+        let used = |method| builder.payloads.iter().any(|(_, queued, _)| *queued == method);
+        let name = if format == FileFormat::Zip && !used(EmbeddingMethod::Text) {
+            builder.add_payload(format, EmbeddingMethod::Text, payload.clone())?;
+            EmbeddingMethod::Text.name()
+        } else if !used(EmbeddingMethod::Idat) {
+            builder.add_payload(format, EmbeddingMethod::Idat, payload.clone())?;
+            EmbeddingMethod::Idat.name()
+        } else {
+            builder.add_named_payload(MERGED_PAYLOAD_KEYWORD, payload.clone())?;
+            MERGED_PAYLOAD_KEYWORD
+        };
+        expected.push((name, payload));
+    }
+    let merged = builder.build()?;
+
+    // A ZIP in IDAT is stored rebased to where it landed, so it is compared rebased the same way
+    let extracted = crate::extract::extract_payloads(&merged)?;
+    for (name, payload) in &expected {
+        let intact = match extracted.iter().find(|(entry, _)| entry.name == *name) {
+            Some((entry, data)) if *name == EmbeddingMethod::Idat.name() && entry.format == FileFormat::Zip => {
+                let mut rebased = ZipArchive::from_data(payload.clone())?;
+                rebased.reseat(entry.offset)?;
+                data.as_slice() == rebased.as_bytes()
+            }
+            Some((_, data)) => data == payload,
+            None => false,
+        };
+        if !intact {
+            return Err(PolyglotError::ValidationFailed(format!("merged {} payload does not extract back", name)));
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Combine the payloads of two polyglot PNGs into one file
+pub fn merge(a: &Path, b: &Path, out: &Path) -> PolyglotResult<()> {
+    let a_data = read_input(a, FileFormat::Png, "first", "polyglot")?;
+    let b_data = read_input(b, FileFormat::Png, "second", "polyglot")?;
+
+    let merged = merge_polyglots(&a_data, &b_data)?;
+    std::fs::write(out, &merged)?;
+
+    println!("Merged polyglot created: {} bytes", merged.len());
+    Ok(())
+}
+
 /// Create truly bidirectional PNG+WAV polyglot (experimental novel format)
 /// Creates a custom container that can be interpreted as both formats
 pub fn create_true_bidirectional_png_wav_polyglot(png_path: &Path, wav_path: &Path, output_path: &Path) -> PolyglotResult<()> {
-    let png = PngFile::from_file(png_path)?;
-    let wav = crate::wav::WavFile::from_file(wav_path)?;
+    let png = PngFile::from_data(read_input(png_path, FileFormat::Png, "png", "carrier")?)?;
+    let wav = crate::wav::WavFile::from_data(read_input(wav_path, FileFormat::Wav, "wav", "payload")?)?;
 
     let mut creator = TrueBidirectionalPngWavCreator { png, wav };
     creator.create_bidirectional_polyglot(output_path)
@@ -46,19 +532,25 @@ pub fn create_png_wav_polyglot(png_path: &Path, wav_path: &Path, output_path: &P
     // Choose approach based on output extension:
     // .png → PNG-dominant (PNG + embedded WAV)
     // .wav → WAV-dominant (WAV + embedded PNG)
-    let png_dominant = output_path.extension().is_some_and(|ext| ext == "png");
+    let png_dominant = crate::cli::check_output_name(output_path, None).dominant == Some(FileFormat::Png);
+
+    // Check both inputs before parsing either, so swapped files get a clear error
+    let png_data = std::fs::read(png_path)?;
+    let wav_data = std::fs::read(wav_path)?;
 
     if png_dominant {
         // PNG-dominant approach
-        let png = PngFile::from_file(png_path)?;
-        let wav = crate::wav::WavFile::from_file(wav_path)?;
+        check_png_wav_inputs(&png_data, &wav_data, "carrier", "payload")?;
+        let png = PngFile::from_data(png_data)?;
+        let wav = crate::wav::WavFile::from_data(wav_data)?;
 
         let mut creator = PngWavPolyglotCreator { png, wav };
         creator.create_polyglot(output_path)
     } else {
         // WAV-dominant approach
-        let png = PngFile::from_file(png_path)?;
-        let wav = crate::wav::WavFile::from_file(wav_path)?;
+        check_png_wav_inputs(&png_data, &wav_data, "payload", "carrier")?;
+        let png = PngFile::from_data(png_data)?;
+        let wav = crate::wav::WavFile::from_data(wav_data)?;
 
         let mut creator = WavPngPolyglotCreator { wav, png };
         creator.create_polyglot(output_path)
@@ -67,8 +559,8 @@ pub fn create_png_wav_polyglot(png_path: &Path, wav_path: &Path, output_path: &P
 
 /// Create PNG+FLAC parasitic polyglot by embedding PNG in FLAC PADDING blocks
 pub fn create_png_flac_polyglot(png_path: &Path, flac_path: &Path, output_path: &Path) -> PolyglotResult<()> {
-    let png = PngFile::from_file(png_path)?;
-    let mut flac = FlacFile::from_file(flac_path)?;
+    let png = PngFile::from_data(read_input(png_path, FileFormat::Png, "png", "payload")?)?;
+    let mut flac = FlacFile::from_data(read_input(flac_path, FileFormat::Flac, "flac", "carrier")?)?;
 
     // Inject PNG data into FLAC's PADDING metadata blocks (parasitic)
     flac.inject_png_to_padding(png.as_bytes())?;
@@ -78,51 +570,226 @@ pub fn create_png_flac_polyglot(png_path: &Path, flac_path: &Path, output_path:
     Ok(())
 }
 
+/// Check the `--png` and `--wav` inputs are in the right slots, naming the mix-up when they aren't
+fn check_png_wav_inputs(png_data: &[u8], wav_data: &[u8], png_role: &str, wav_role: &str) -> PolyglotResult<()> {
+    let png_is_wav = crate::utils::sniff_format(png_data) == FileFormat::Wav;
+    let wav_is_png = crate::utils::sniff_format(wav_data) == FileFormat::Png;
+
+    if png_is_wav && wav_is_png {
+        return Err(PolyglotError::InvalidInput(
+            "the --png file is actually a WAV and the --wav file is actually a PNG; swap the two inputs".to_string()
+        ));
+    }
+    if wav_is_png {
+        return Err(PolyglotError::InvalidInput(format!("the --wav file is actually a PNG; --wav expects the WAV {}", wav_role)));
+    }
+    if png_is_wav {
+        return Err(PolyglotError::InvalidInput(format!("the --png file is actually a WAV; --png expects the PNG {}", png_role)));
+    }
+
+    expect_format(png_data, FileFormat::Png, "png", png_role)?;
+    expect_format(wav_data, FileFormat::Wav, "wav", wav_role)
+}
+
+/// Error for methods `PolyglotCreator` doesn't build: they take other inputs than a PNG and a ZIP
+fn unsupported_method(method: EmbeddingMethod) -> PolyglotError {
+    PolyglotError::InvalidInput(format!("the {} method is not supported for PNG+ZIP polyglots", method))
+}
+
+/// Read an input file, rejecting it early if it is not the format its flag expects
+fn read_input(path: &Path, expected: FileFormat, flag: &str, role: &str) -> PolyglotResult<Vec<u8>> {
+    let data = std::fs::read(path)?;
+    expect_format(&data, expected, flag, role)?;
+    Ok(data)
+}
+
 impl PolyglotCreator {
     /// Create a new polyglot creator with PNG and ZIP files
     pub fn new(png_path: &Path, zip_path: &Path) -> PolyglotResult<Self> {
-        let png = PngFile::from_file(png_path)?;
+        let png = PngFile::from_data(read_input(png_path, FileFormat::Png, "png", "carrier")?)?;
         let zip = ZipArchive::read_zip(zip_path)?;
 
-        Ok(Self { png, zip })
+        Ok(Self { png, zip, max_idat_size: crate::png::DEFAULT_MAX_IDAT_SIZE, max_output_size: None, hmac_key: None, entry_mode: crate::zip::DEFAULT_ENTRY_MODE, entry_is_text: false })
+    }
+
+    /// Create a new polyglot creator that archives a directory as the ZIP payload
+    pub fn from_directory(png_path: &Path, dir_path: &Path) -> PolyglotResult<Self> {
+        let png = PngFile::from_data(read_input(png_path, FileFormat::Png, "png", "carrier")?)?;
+        let zip = crate::zip::create_zip_from_directory(dir_path)?;
+
+        Ok(Self { png, zip, max_idat_size: crate::png::DEFAULT_MAX_IDAT_SIZE, max_output_size: None, hmac_key: None, entry_mode: crate::zip::DEFAULT_ENTRY_MODE, entry_is_text: false })
     }
 
     /// Create polyglot from raw data
     pub fn from_data(png_data: Vec<u8>, zip_data: Vec<u8>) -> PolyglotResult<Self> {
+        crate::utils::ensure_payload_not_empty(&zip_data)?;
         let png = PngFile::from_data(png_data)?;
         let zip = ZipArchive::from_data(zip_data)?;
 
-        Ok(Self { png, zip })
+        Ok(Self { png, zip, max_idat_size: crate::png::DEFAULT_MAX_IDAT_SIZE, max_output_size: None, hmac_key: None, entry_mode: crate::zip::DEFAULT_ENTRY_MODE, entry_is_text: false })
+    }
+
+    /// Set the largest IDAT chunk the IDAT method may produce before splitting
+    pub fn set_max_idat_size(&mut self, max_idat_size: usize) {
+        self.max_idat_size = max_idat_size;
+    }
+
+    /// Refuse to build outputs whose projected size exceeds `max_output_size` bytes
+    pub fn set_max_output_size(&mut self, max_output_size: Option<usize>) {
+        self.max_output_size = max_output_size;
+    }
+
+    /// Sign the payload with this key, storing an HMAC-SHA256 tag in a `plHM` chunk (PNG-dominant methods)
+    pub fn set_hmac_key(&mut self, hmac_key: Option<Vec<u8>>) {
+        self.hmac_key = hmac_key;
+    }
+
+    /// Unix permissions stored for the image entry of a ZIP-dominant polyglot (default 0644)
+    pub fn set_entry_mode(&mut self, mode: u32) -> PolyglotResult<()> {
+        if mode > 0o777 {
+            return Err(PolyglotError::InvalidInput(format!("entry mode {:o} has bits beyond 0777", mode)));
+        }
+        self.entry_mode = mode;
+        Ok(())
+    }
+
+    /// Set the text flag in the internal attributes of the ZIP-dominant image entry (default binary)
+    pub fn set_entry_text(&mut self, is_text: bool) {
+        self.entry_is_text = is_text;
+    }
+
+    /// Size the output will have for the given method, computed without building it
+    pub fn projected_size(&self, method: EmbeddingMethod) -> PolyglotResult<usize> {
+        let signature_size = if self.hmac_key.is_some() { 12 + crate::signing::HMAC_LEN } else { 0 };
+        self.projected_unsigned_size(method).map(|size| size + signature_size)
+    }
+
+    fn projected_unsigned_size(&self, method: EmbeddingMethod) -> PolyglotResult<usize> {
+        let png_size = self.png.as_bytes().len();
+        let zip_size = self.zip.size();
+
+        match method {
+            EmbeddingMethod::Zip => Ok(png_size + method.overhead_bytes()),
+            EmbeddingMethod::Idat => {
+                if self.max_idat_size == 0 {
+                    return Err(PolyglotError::InvalidInput("IDAT size limit must be greater than zero".to_string()));
+                }
+                // The payload stays whole; only the image data may be split into more chunks
+                let (idat_offset, idat_length) = self.png.find_first_idat()?;
+                let split_framing = self.png.appended_idat_offset(self.max_idat_size)? - (idat_offset + idat_length);
+                Ok(png_size + zip_size + split_framing)
+            }
+            EmbeddingMethod::Text | EmbeddingMethod::Chunk => Ok(png_size + zip_size + method.overhead_bytes()),
+            EmbeddingMethod::Comment | EmbeddingMethod::Bidirectional => Err(unsupported_method(method)),
+        }
+    }
+
+    /// Drop the carrier's ancillary metadata (text, EXIF, timestamps, ...) before embedding
+    ///
+    /// Transparency is part of the image, so tRNS is kept. Returns the number of chunks removed.
+    pub fn strip_carrier_metadata(&mut self) -> PolyglotResult<usize> {
+        self.png.strip_ancillary(&[*b"tRNS"])
+    }
+
+    /// Add a blank IDAT sized to IHDR if the carrier has none, so the `idat` method can be used
+    pub fn synthesize_idat(&mut self) -> PolyglotResult<bool> {
+        self.png.synthesize_idat()
+    }
+
+    /// Method to actually use: `idat` falls back to `text` when the carrier has no IDAT chunk
+    pub fn effective_method(&self, method: EmbeddingMethod) -> EmbeddingMethod {
+        if method == EmbeddingMethod::Idat && self.png.find_first_idat().is_err() {
+            EmbeddingMethod::Text
+        } else {
+            method
+        }
     }
 
     /// Execute the complete polyglot creation workflow with specified embedding method
     pub fn create_polyglot(&mut self, output_path: &Path) -> PolyglotResult<()> {
-        self.create_polyglot_with_method(output_path, "idat")
+        self.create_polyglot_with_method(output_path, EmbeddingMethod::Idat)
     }
 
     /// Execute the complete polyglot creation workflow
-    pub fn create_polyglot_with_method(&mut self, output_path: &Path, method: &str) -> PolyglotResult<()> {
+    pub fn create_polyglot_with_method(&mut self, output_path: &Path, method: EmbeddingMethod) -> PolyglotResult<()> {
         match method {
-            "zip" => {
-                println!("Creating ZIP-dominant polyglot (PNG embedded in ZIP)...");
-                self.create_zip_dominant_polyglot(output_path)
-            }
-            "idat" => {
-                println!("Creating PNG-dominant polyglot (ZIP embedded in IDAT - parasitic)...");
-                self.create_png_dominant_polyglot_idat(output_path)
-            }
-            "text" => {
-                println!("Creating PNG-dominant polyglot (ZIP embedded in text chunk - parasitic)...");
-                self.create_png_dominant_polyglot_text(output_path)
+            EmbeddingMethod::Zip => println!("Creating ZIP-dominant polyglot (PNG embedded in ZIP)..."),
+            EmbeddingMethod::Idat => println!("Creating PNG-dominant polyglot (ZIP embedded in IDAT - parasitic)..."),
+            EmbeddingMethod::Text => println!("Creating PNG-dominant polyglot (ZIP embedded in text chunk - parasitic)..."),
+            EmbeddingMethod::Chunk => println!("Creating PNG-dominant polyglot (ZIP in a private chunk after the image data)..."),
+            EmbeddingMethod::Comment | EmbeddingMethod::Bidirectional => {}
+        }
+
+        let (polyglot_data, _) = self.build_with_method(method)?;
+        std::fs::write(output_path, &polyglot_data)?;
+
+        match method {
+            EmbeddingMethod::Zip => println!("ZIP-dominant polyglot created: {} bytes", polyglot_data.len()),
+            _ => println!("PNG-dominant polyglot ({} method) created: {} bytes", method, polyglot_data.len()),
+        }
+        Ok(())
+    }
+
+    /// Create the polyglot and return a record of how it was built
+    pub fn create_polyglot_with_log(&mut self, output_path: &Path, method: EmbeddingMethod) -> PolyglotResult<CreationLog> {
+        // Capture input fingerprints before the components are modified
+        let carrier_sha256 = sha256(self.png.as_bytes());
+        let carrier_size = self.png.as_bytes().len();
+        let payload_sha256 = sha256(self.zip.as_bytes());
+        let payload_size = self.zip.size();
+
+        let (polyglot_data, payload_offset) = self.build_with_method(method)?;
+        std::fs::write(output_path, &polyglot_data)?;
+
+        Ok(CreationLog {
+            method: method.name().to_string(),
+            carrier_size,
+            carrier_sha256,
+            payload_size,
+            payload_sha256,
+            payload_offset,
+            output_size: polyglot_data.len(),
+            output_sha256: sha256(&polyglot_data),
+        })
+    }
+
+    /// Build polyglot data for the given method, returning it with the payload offset
+    fn build_with_method(&mut self, method: EmbeddingMethod) -> PolyglotResult<(Vec<u8>, usize)> {
+        if let Some(max_output_size) = self.max_output_size {
+            let projected_size = self.projected_size(method)?;
+            if projected_size > max_output_size {
+                return Err(PolyglotError::InvalidInput(format!(
+                    "projected output of {} bytes exceeds the maximum size of {} bytes",
+                    projected_size, max_output_size
+                )));
             }
-            _ => {
-                Err(PolyglotError::InvalidInput(format!("Unknown embedding method: {}", method)))
+        }
+
+        if self.hmac_key.is_some() && method == EmbeddingMethod::Zip {
+            return Err(PolyglotError::InvalidInput("HMAC signing needs a PNG-dominant method (text, chunk or idat)".to_string()));
+        }
+
+        let (data, payload_offset) = match method {
+            EmbeddingMethod::Zip => Ok(self.build_zip_dominant_polyglot()),
+            EmbeddingMethod::Idat => self.build_png_dominant_polyglot_idat(),
+            EmbeddingMethod::Text => self.build_png_dominant_polyglot_text(),
+            EmbeddingMethod::Chunk => self.build_png_dominant_polyglot_chunk(),
+            EmbeddingMethod::Comment | EmbeddingMethod::Bidirectional => Err(unsupported_method(method)),
+        }?;
+
+        // The tag follows the payload, so signing moves nothing that was already placed
+        match &self.hmac_key {
+            Some(key) => {
+                let tag = crate::signing::payload_hmac(key, self.zip.as_bytes());
+                self.png.insert_chunk_before_iend(&crate::signing::HMAC_CHUNK_TYPE, &tag)?;
+                Ok((self.png.raw_data.clone(), payload_offset))
             }
+            None => Ok((data, payload_offset)),
         }
     }
 
-    /// Create ZIP-dominant polyglot (traditional method)
-    fn create_zip_dominant_polyglot(&mut self, output_path: &Path) -> PolyglotResult<()> {
+    /// Build ZIP-dominant polyglot (traditional method)
+    fn build_zip_dominant_polyglot(&self) -> (Vec<u8>, usize) {
         // Step 1: Create new ZIP structure
         let original_png_data = self.png.as_bytes();
         let mut new_zip_data = Vec::new();
@@ -132,7 +799,7 @@ impl PolyglotCreator {
         let png_data = original_png_data;
 
         // Local File Header
-        new_zip_data.extend_from_slice(&[0x50, 0x4B, 0x03, 0x04]); // Signature
+        new_zip_data.extend_from_slice(&ZIP_LOCAL_SIGNATURE);
         new_zip_data.extend_from_slice(&[0x0A, 0x00]); // Version needed
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // GPB flag
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // Compression method
@@ -150,7 +817,7 @@ impl PolyglotCreator {
 
         // Update the file header with correct sizes
         let file_header_pos = 14;
-        let crc = crc32fast::hash(png_data);
+        let crc = crate::utils::calculate_crc32(png_data);
         let compressed_size = png_data.len() as u32; // No compression, so same as uncompressed
         let uncompressed_size = png_data.len() as u32;
 
@@ -163,7 +830,7 @@ impl PolyglotCreator {
 
         // Central Directory Header
         let cd_offset = new_zip_data.len();
-        new_zip_data.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]); // Signature
+        new_zip_data.extend_from_slice(&ZIP_CENTRAL_DIRECTORY_SIGNATURE); // Signature
         new_zip_data.extend_from_slice(&[0x0A, 0x03]); // Version made by
         new_zip_data.extend_from_slice(&[0x0A, 0x00]); // Version needed
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // GPB flag
@@ -176,61 +843,61 @@ impl PolyglotCreator {
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // Extra field length
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // File comment length
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // Disk number
-        new_zip_data.extend_from_slice(&[0x00, 0x00]); // Internal attributes
-        new_zip_data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // External attributes
-        new_zip_data.extend_from_slice(&(png_offset as u32).to_le_bytes()); // Local header offset
+        new_zip_data.extend_from_slice(&u16::from(self.entry_is_text).to_le_bytes()); // Internal attributes: bit 0 marks text
+        new_zip_data.extend_from_slice(&((UNIX_REGULAR_FILE | self.entry_mode) << 16).to_le_bytes()); // External attributes: Unix mode
+        new_zip_data.extend_from_slice(&0u32.to_le_bytes()); // Local header offset: the header starts the archive
         new_zip_data.extend_from_slice(png_filename); // Filename
 
         // End of Central Directory
         let eocd_pos = new_zip_data.len();
-        new_zip_data.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06]); // Signature
+        new_zip_data.extend_from_slice(&ZIP_EOCD_SIGNATURE);
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // Disk number
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // CD disk number
         new_zip_data.extend_from_slice(&[0x01, 0x00]); // Entries on this disk
         new_zip_data.extend_from_slice(&[0x01, 0x00]); // Total entries
-        new_zip_data.extend_from_slice(&((new_zip_data.len() - cd_offset) as u32).to_le_bytes()); // CD size
+        new_zip_data.extend_from_slice(&((eocd_pos - cd_offset) as u32).to_le_bytes()); // CD size
         new_zip_data.extend_from_slice(&(cd_offset as u32).to_le_bytes()); // CD offset
         new_zip_data.extend_from_slice(&[0x00, 0x00]); // Comment length
 
-        // Write the ZIP-based polyglot
-        std::fs::write(output_path, &new_zip_data)?;
-
-        println!("ZIP-dominant polyglot created: {} bytes", new_zip_data.len());
-        Ok(())
+        (new_zip_data, png_offset)
     }
 
-    /// Create PNG-dominant polyglot with ZIP in IDAT chunk
-    fn create_png_dominant_polyglot_idat(&mut self, output_path: &Path) -> PolyglotResult<()> {
-        let (idat_offset, idat_length) = self.png.find_first_idat()?;
-        let embed_position = idat_offset as u64 + idat_length as u64 + 8;
+    /// Build PNG-dominant polyglot with ZIP in IDAT chunk
+    fn build_png_dominant_polyglot_idat(&mut self) -> PolyglotResult<(Vec<u8>, usize)> {
+        // The ZIP is appended right after the existing IDAT data
+        let payload_offset = self.png.appended_idat_offset(self.max_idat_size)?;
 
-        self.zip.update_central_directory_offsets(embed_position)?;
-        self.png.append_to_idat(self.zip.as_bytes())?;
+        self.zip.update_central_directory_offsets(payload_offset as u64)?;
+        self.png.append_to_idat_with_limit(self.zip.as_bytes(), self.max_idat_size)?;
 
-        self.png.write_to_file(output_path)?;
-        println!("PNG-dominant polyglot (IDAT method) created: {} bytes", self.png.as_bytes().len());
-        Ok(())
+        Ok((self.png.raw_data.clone(), payload_offset))
     }
 
-    /// Create PNG-dominant polyglot with ZIP in text chunk
-    fn create_png_dominant_polyglot_text(&mut self, output_path: &Path) -> PolyglotResult<()> {
+    /// Build PNG-dominant polyglot with ZIP in text chunk
+    fn build_png_dominant_polyglot_text(&mut self) -> PolyglotResult<(Vec<u8>, usize)> {
+        let payload_offset = EmbeddingMethod::Text.projected_payload_offset(&self.png)?;
         self.png.add_zip_text_chunk(self.zip.as_bytes())?;
 
-        self.png.write_to_file(output_path)?;
-        println!("PNG-dominant polyglot (text method) created: {} bytes", self.png.as_bytes().len());
-        Ok(())
+        Ok((self.png.raw_data.clone(), payload_offset))
+    }
+
+    /// Build PNG-dominant polyglot with ZIP in a private chunk after the IDATs
+    fn build_png_dominant_polyglot_chunk(&mut self) -> PolyglotResult<(Vec<u8>, usize)> {
+        let payload_offset = EmbeddingMethod::Chunk.projected_payload_offset(&self.png)?;
+        self.zip.update_central_directory_offsets(payload_offset as u64)?;
+        self.png.add_payload_chunk(self.zip.as_bytes())?;
+
+        Ok((self.png.raw_data.clone(), payload_offset))
     }
 
     /// Get final polyglot data without writing to file
     pub fn create_polyglot_in_memory(&mut self) -> PolyglotResult<Vec<u8>> {
-        // Same steps as create_polyglot but return data instead of writing
-        let (idat_offset, idat_length) = self.png.find_first_idat()?;
-        let embed_position = idat_offset as u64 + idat_length as u64 + 8;
-
-        self.zip.update_central_directory_offsets(embed_position)?;
-        self.png.append_to_idat(self.zip.as_bytes())?;
+        self.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat)
+    }
 
-        Ok(self.png.raw_data.clone())
+    /// Get final polyglot data for the given method without writing to file
+    pub fn create_polyglot_in_memory_with_method(&mut self, method: EmbeddingMethod) -> PolyglotResult<Vec<u8>> {
+        self.build_with_method(method).map(|(data, _)| data)
     }
 
     /// Get PNG component
@@ -292,16 +959,34 @@ impl WavPngPolyglotCreator {
 }
 
 impl TrueBidirectionalPngWavCreator {
+    /// Create from raw PNG and WAV data
+    pub fn from_data(png_data: Vec<u8>, wav_data: Vec<u8>) -> PolyglotResult<Self> {
+        crate::utils::ensure_payload_not_empty(&wav_data)?;
+        let png = PngFile::from_data(png_data)?;
+        let wav = crate::wav::WavFile::from_data(wav_data)?;
+
+        Ok(Self { png, wav })
+    }
+
     /// Create truly bidirectional PNG+WAV polyglot using novel custom format
     pub fn create_bidirectional_polyglot(&mut self, output_path: &Path) -> PolyglotResult<()> {
+        let result = self.build_bidirectional_polyglot();
+
+        // Write the truly bidirectional file
+        std::fs::write(output_path, &result)?;
+        println!("Truly bidirectional PNG+WAV polyglot created: {} bytes", result.len());
+        Ok(())
+    }
+
+    /// Build the truly bidirectional PNG+WAV polyglot data in memory
+    pub fn build_bidirectional_polyglot(&self) -> Vec<u8> {
         // Create a custom container that satisfies both PNG and WAV parsers simultaneously
         // This is a novel approach where the same byte sequence works for both formats
 
         let mut result = Vec::new();
 
         // Part 1: PNG Structure (visible to PNG parsers)
-        result.extend_from_slice(b"\x89PNG"); // PNG signature start
-        result.extend_from_slice(b"\r\n\x1a\n"); // PNG signature end
+        result.extend_from_slice(&crate::utils::PNG_SIGNATURE);
 
         // IHDR chunk - minimal image header
         let ihdr_data = [
@@ -324,9 +1009,18 @@ impl TrueBidirectionalPngWavCreator {
         result.extend_from_slice(&ihdr_length.to_be_bytes());
         result.extend_from_slice(b"IHDR");
         result.extend_from_slice(&ihdr_data);
-        let ihdr_crc = crate::utils::calculate_crc32(&[b"IHDR".as_slice(), &ihdr_data].concat());
+        let ihdr_crc = crate::png::chunk_crc(b"IHDR", &ihdr_data);
         result.extend_from_slice(&ihdr_crc.to_be_bytes());
 
+        // Colour-management chunks from the source image, kept in their original order (all must precede IDAT)
+        for chunk in self.png.parsed.chunks.iter().filter(|chunk| COLOUR_CHUNK_TYPES.contains(&chunk.chunk_type)) {
+            result.extend_from_slice(&(chunk.data.len() as u32).to_be_bytes());
+            result.extend_from_slice(&chunk.chunk_type);
+            result.extend_from_slice(&chunk.data);
+            let crc = crate::png::chunk_crc(&chunk.chunk_type, &chunk.data);
+            result.extend_from_slice(&crc.to_be_bytes());
+        }
+
         // Part 2: Dual-purpose data (WAV RIFF structure interpreted as PNG IDAT)
         // Embedding WAV data in a way that PNG parsers tolerate as compressed image data
         let wav_bytes = self.wav.as_bytes();
@@ -337,19 +1031,16 @@ impl TrueBidirectionalPngWavCreator {
         result.extend_from_slice(&idat_length.to_be_bytes());
         result.extend_from_slice(b"IDAT");
         result.extend_from_slice(wav_bytes);
-        let idat_crc = crate::utils::calculate_crc32(&[b"IDAT".as_slice(), wav_bytes].concat());
+        let idat_crc = crate::png::chunk_crc(b"IDAT", wav_bytes);
         result.extend_from_slice(&idat_crc.to_be_bytes());
 
         // IEND chunk
         result.extend_from_slice(&0u32.to_be_bytes());
         result.extend_from_slice(b"IEND");
-        let iend_crc = crate::utils::calculate_crc32(b"IEND");
+        let iend_crc = crate::png::chunk_crc(b"IEND", &[]);
         result.extend_from_slice(&iend_crc.to_be_bytes());
 
-        // Write the truly bidirectional file
-        std::fs::write(output_path, &result)?;
-        println!("Truly bidirectional PNG+WAV polyglot created: {} bytes", result.len());
-        Ok(())
+        result
     }
 
     /// Get PNG component
@@ -363,133 +1054,156 @@ impl TrueBidirectionalPngWavCreator {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::PathBuf;
-    use tempfile::TempDir;
+impl MultiPayloadBuilder {
+    /// Start from a PNG carrier with no payloads
+    pub fn new(png: PngFile) -> Self {
+        Self { png, payloads: Vec::new(), named_payloads: Vec::new(), write_index: false }
+    }
 
-    // Helper functions from PNG and ZIP tests
-    fn create_test_png() -> Vec<u8> {
-        let mut png = vec![
-            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
-        ];
+    /// Also write a trailing `index::PayloadIndex` so extractors can skip scanning
+    pub fn set_write_index(&mut self, write_index: bool) {
+        self.write_index = write_index;
+    }
 
-        // IHDR chunk
-        let ihdr_data = [
-            0x00, 0x00, 0x00, 0x01, // width = 1
-            0x00, 0x00, 0x00, 0x01, // height = 1
-            0x08, // bit depth = 8
-            0x02, // color type = 2 (RGB)
-            0x00, // compression = 0
-            0x00, // filter = 0
-            0x00, // interlace = 0
-        ];
+    /// Queue a payload of any format in its own tEXt chunk, using `name` as the keyword
+    ///
+    /// Any number of these can be combined; they are told apart by name, so names must be unique.
+    pub fn add_named_payload(&mut self, name: &str, data: Vec<u8>) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(&data)?;
+        crate::png::latin1_keyword(name)?;
+        if name.as_bytes() == b"ZIP Archive" || self.named_payloads.iter().any(|(queued, _, _)| queued == name) {
+            return Err(PolyglotError::InvalidInput(format!("payload name {:?} is already in use", name)));
+        }
 
-        let ihdr_length = ihdr_data.len() as u32;
-        png.extend_from_slice(&ihdr_length.to_be_bytes());
-        png.extend_from_slice(b"IHDR");
-        png.extend_from_slice(&ihdr_data);
-        let ihdr_crc_data = [b"IHDR".as_slice(), &ihdr_data].concat();
-        let ihdr_crc = crate::utils::calculate_crc32(&ihdr_crc_data);
-        png.extend_from_slice(&ihdr_crc.to_be_bytes());
-
-        // IDAT chunk with minimal compressed data
-        let idat_data = [
-            0x78, 0x9C, 0xED, 0xC1, 0x01, 0x01, 0x00, 0x00, 0x00, 0x80, 0x90, 0xFE, 0x37, 0x10
-        ];
-        let idat_length = idat_data.len() as u32;
-        png.extend_from_slice(&idat_length.to_be_bytes());
-        png.extend_from_slice(b"IDAT");
-        png.extend_from_slice(&idat_data);
-        let idat_crc_data = [b"IDAT".as_slice(), &idat_data].concat();
-        let idat_crc = crate::utils::calculate_crc32(&idat_crc_data);
-        png.extend_from_slice(&idat_crc.to_be_bytes());
+        let format = crate::utils::sniff_format(&data);
+        self.named_payloads.push((name.to_string(), format, data));
+        Ok(())
+    }
 
-        // IEND chunk
-        png.extend_from_slice(&0u32.to_be_bytes());
-        png.extend_from_slice(b"IEND");
-        let iend_crc = crate::utils::calculate_crc32(b"IEND");
-        png.extend_from_slice(&iend_crc.to_be_bytes());
-
-        png
-    }
-
-    fn create_test_zip() -> Vec<u8> {
-        // Minimal ZIP file with one empty file
-        let mut zip = vec![0x50, 0x4B, 0x03, 0x04]; // LFHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(b"test"); // Filename
-        // Data (empty)
-
-        // Central directory header
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x01, 0x02]); // CDHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version made by
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // File comment length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Internal attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // External attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Local header offset
-        zip.extend_from_slice(b"test"); // Filename
-
-        // End of central directory
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x05, 0x06]); // EOCDS
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // CD disk number
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Entries on this disk
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Total entries
-        zip.extend_from_slice(&vec![0x16, 0x00, 0x00, 0x00]); // CD size
-        zip.extend_from_slice(&vec![0x1A, 0x00, 0x00, 0x00]); // CD offset
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Comment length
-
-        zip
-    }
-
-    // Helper to create a minimal WAV for testing
-    fn create_test_wav() -> Vec<u8> {
-        let mut wav = vec![];
-
-        // RIFF header
-        wav.extend_from_slice(b"RIFF");
-        wav.extend_from_slice(&(40u32).to_le_bytes()); // File size = total - 8 bytes for RIFF header/WAVE signature
-        wav.extend_from_slice(b"WAVE");
-
-        // fmt chunk (16 bytes)
-        wav.extend_from_slice(b"fmt ");
-        wav.extend_from_slice(&(16u32).to_le_bytes()); // Chunk size
-        wav.extend_from_slice(&(1u16).to_le_bytes()); // Audio format (PCM)
-        wav.extend_from_slice(&(1u16).to_le_bytes()); // Channels
-        wav.extend_from_slice(&(44100u32).to_le_bytes()); // Sample rate
-        wav.extend_from_slice(&(88200u32).to_le_bytes()); // Byte rate
-        wav.extend_from_slice(&(2u16).to_le_bytes()); // Block align
-        wav.extend_from_slice(&(16u16).to_le_bytes()); // Bits per sample
-
-        // data chunk (minimal audio data)
-        wav.extend_from_slice(b"data");
-        wav.extend_from_slice(&(4u32).to_le_bytes()); // Data size
-        wav.extend_from_slice(&(0u16).to_le_bytes()); // Minimal audio data
-        wav.extend_from_slice(&(0u16).to_le_bytes());
-
-        wav
+    /// Queue a payload, rejecting it if it conflicts with one already queued
+    ///
+    /// Only `idat` (any format) and `text` (ZIP, under the usual keyword) can be
+    /// combined, and each at most once: two payloads in IDAT would run together.
+    pub fn add_payload(&mut self, format: FileFormat, method: EmbeddingMethod, data: Vec<u8>) -> PolyglotResult<()> {
+        crate::utils::ensure_payload_not_empty(&data)?;
+        let actual = crate::utils::sniff_format(&data);
+        if actual != format {
+            return Err(PolyglotError::InvalidInput(format!(
+                "payload declared as {} is {}", format.name(), actual.name()
+            )));
+        }
+
+        match method {
+            EmbeddingMethod::Idat => {}
+            EmbeddingMethod::Text if format == FileFormat::Zip => {}
+            EmbeddingMethod::Text => {
+                return Err(PolyglotError::InvalidInput(format!("the text method only carries ZIP payloads, not {}", format.name())));
+            }
+            other => {
+                return Err(PolyglotError::InvalidInput(format!("the {} method cannot be combined with other payloads", other.name())));
+            }
+        }
+
+        if self.payloads.iter().any(|(_, queued, _)| *queued == method) {
+            return Err(PolyglotError::InvalidInput(format!("only one payload can use the {} method", method.name())));
+        }
+
+        self.payloads.push((format, method, data));
+        Ok(())
     }
 
+    /// Apply all queued payloads and return the polyglot data
+    pub fn build(mut self) -> PolyglotResult<Vec<u8>> {
+        let mut chunks = self.png.parsed.chunks.clone();
+        // Where each payload lands: chunk index, start within the chunk data, length, format, name
+        let mut placements = Vec::new();
+
+        for (format, method, data) in &self.payloads {
+            match method {
+                EmbeddingMethod::Idat => {
+                    // Only chunks after the first IDAT change, so its parsed offset still holds
+                    let idat_index = chunks.iter()
+                        .position(|chunk| chunk.chunk_type == *b"IDAT")
+                        .ok_or(PolyglotError::NoIdatChunk)?;
+                    let idat = &mut chunks[idat_index];
+                    let start = idat.data.len();
+
+                    if *format == FileFormat::Zip {
+                        let mut zip = ZipArchive::from_data(data.clone())?;
+                        zip.update_central_directory_offsets((idat.data_offset + idat.data.len()) as u64)?;
+                        idat.data.extend_from_slice(zip.as_bytes());
+                    } else {
+                        idat.data.extend_from_slice(data);
+                    }
+                    placements.push((idat_index, start, data.len(), *format, method.name().to_string()));
+                }
+                _ => {
+                    let (index, start) = insert_text_chunk(&mut chunks, b"ZIP Archive", data)?;
+                    placements.push((index, start, data.len(), *format, method.name().to_string()));
+                }
+            }
+        }
+
+        for (name, format, data) in &self.named_payloads {
+            let (index, start) = insert_text_chunk(&mut chunks, name.as_bytes(), data)?;
+            placements.push((index, start, data.len(), *format, name.clone()));
+        }
+
+        self.png.rebuild_from_chunks(&chunks)?;
+
+        if self.write_index {
+            // Inserted after every payload chunk, so their offsets stay put
+            let entries = placements.into_iter()
+                .map(|(index, start, length, format, name)| crate::index::IndexEntry {
+                    offset: (self.png.parsed.chunks[index].data_offset + start) as u64,
+                    length: length as u64,
+                    format,
+                    name,
+                })
+                .collect();
+            let index_data = crate::index::PayloadIndex { entries }.to_bytes()?;
+            insert_chunk_before_iend(&mut chunks, crate::index::INDEX_CHUNK_TYPE, index_data)?;
+            self.png.rebuild_from_chunks(&chunks)?;
+        }
+
+        Ok(self.png.raw_data)
+    }
+}
+
+/// Insert a tEXt chunk before IEND, returning its index and where the payload starts in its data
+fn insert_text_chunk(chunks: &mut Vec<crate::png::parser::Chunk>, keyword: &[u8], data: &[u8]) -> PolyglotResult<(usize, usize)> {
+    let mut text = keyword.to_vec();
+    text.push(0);
+    let start = text.len();
+    text.extend_from_slice(data);
+    let index = insert_chunk_before_iend(chunks, *b"tEXt", text)?;
+    Ok((index, start))
+}
+
+/// Insert a chunk before IEND, returning its index
+fn insert_chunk_before_iend(chunks: &mut Vec<crate::png::parser::Chunk>, chunk_type: [u8; 4], data: Vec<u8>) -> PolyglotResult<usize> {
+    let iend_index = chunks.iter()
+        .position(|chunk| chunk.chunk_type == *b"IEND")
+        .ok_or_else(|| PolyglotError::ChunkNotFound("IEND".to_string()))?;
+
+    chunks.insert(iend_index, crate::png::parser::Chunk {
+        length: data.len() as u32,
+        chunk_type,
+        data,
+        crc: 0, // Recomputed on rebuild
+        data_offset: 0,
+    });
+    Ok(iend_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{minimal_pdf, minimal_png, minimal_wav, minimal_zip};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[cfg(feature = "compression")]
     #[test]
     fn test_png_wav_polyglot_creation_and_extraction() {
         use crate::png::PngFile;
@@ -498,8 +1212,8 @@ mod tests {
         use std::io::Write;
 
         // Create test files
-        let wav_data = create_test_wav();
-        let png_data = create_test_png();
+        let wav_data = minimal_wav();
+        let png_data = minimal_png();
 
         // Write to temp files
         let mut wav_file = NamedTempFile::new().unwrap();
@@ -547,10 +1261,647 @@ mod tests {
         println!("Original WAV size: {} bytes", wav_data.len());
     }
 
+    #[test]
+    fn test_creation_log_matches_detected_payload() {
+        use tempfile::NamedTempFile;
+
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        let output_file = NamedTempFile::with_suffix(".png").unwrap();
+
+        let log = creator.create_polyglot_with_log(output_file.path(), EmbeddingMethod::Text).unwrap();
+        let json = log.to_json();
+
+        // Logged offset should agree with what detection finds in the output
+        let output_data = std::fs::read(output_file.path()).unwrap();
+        let detected = crate::extract::detect_payloads(&output_data);
+        let zip_payload = detected.iter().find(|p| p.format == "zip").unwrap();
+
+        assert!(json.contains("\"method\": \"text\""));
+        assert!(json.contains(&format!("\"payload_offset\": {},", zip_payload.offset)));
+        assert_eq!(log.output_size, output_data.len());
+
+        // Inputs and output are fingerprinted with SHA-256
+        assert_eq!(log.payload_sha256, sha256(&minimal_zip()));
+        assert_eq!(log.output_sha256, sha256(&output_data));
+        assert!(json.contains(&format!("\"sha256\": \"{}\"", to_hex(&log.output_sha256))));
+        assert_eq!(to_hex(&sha256(b"abc"))[..16], *"ba7816bf8f01cfea");
+    }
+
+    #[test]
+    fn test_idat_less_carrier_falls_back_to_text() {
+        // IHDR and IEND only
+        let png_data = minimal_png();
+        let idat_start = png_data.windows(4).position(|w| w == b"IDAT").unwrap() - 4;
+        let iend_start = png_data.windows(4).position(|w| w == b"IEND").unwrap() - 4;
+        let carrier = [&png_data[..idat_start], &png_data[iend_start..]].concat();
+
+        let mut creator = PolyglotCreator::from_data(carrier, minimal_zip()).unwrap();
+        assert_eq!(creator.effective_method(EmbeddingMethod::Idat), EmbeddingMethod::Text);
+        assert_eq!(creator.effective_method(EmbeddingMethod::Zip), EmbeddingMethod::Zip);
+
+        let polyglot = creator.create_polyglot_in_memory_with_method(creator.effective_method(EmbeddingMethod::Idat)).unwrap();
+        assert_eq!(crate::extract::extract_zip_from_png_data(&polyglot).unwrap(), minimal_zip());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_idat_less_carrier_gets_synthesized_idat() {
+        let png_data = minimal_png();
+        let idat_start = png_data.windows(4).position(|w| w == b"IDAT").unwrap() - 4;
+        let iend_start = png_data.windows(4).position(|w| w == b"IEND").unwrap() - 4;
+        let carrier = [&png_data[..idat_start], &png_data[iend_start..]].concat();
+
+        let mut creator = PolyglotCreator::from_data(carrier, minimal_zip()).unwrap();
+        assert!(creator.synthesize_idat().unwrap());
+        assert!(!creator.synthesize_idat().unwrap());
+        assert_eq!(creator.effective_method(EmbeddingMethod::Idat), EmbeddingMethod::Idat);
+
+        // The synthesized IDAT decodes to the blank image IHDR describes
+        let decoder = ::png::Decoder::new(std::io::Cursor::new(creator.png().as_bytes().to_vec()));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0xAA; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, vec![0; 3]);
+
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+        let output = PngFile::from_data(polyglot.clone()).unwrap();
+        assert!(output.find_first_idat().is_ok());
+        assert_eq!(crate::extract::extract_zip_from_png_data(&polyglot).unwrap(), creator.zip().as_bytes());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_oversized_idat_less_carrier_falls_back_to_text() {
+        let png_data = minimal_png();
+        let idat_start = png_data.windows(4).position(|w| w == b"IDAT").unwrap() - 4;
+        let iend_start = png_data.windows(4).position(|w| w == b"IEND").unwrap() - 4;
+        let mut carrier = [&png_data[..idat_start], &png_data[iend_start..]].concat();
+
+        // 65535x65535 RGBA at 8 bits is ~16 GB of image data
+        carrier[16..24].copy_from_slice(&[0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF]);
+        carrier[24..26].copy_from_slice(&[8, 6]);
+        let crc = crate::png::chunk_crc(b"IHDR", &carrier[16..29]);
+        carrier[29..33].copy_from_slice(&crc.to_be_bytes());
+
+        let mut creator = PolyglotCreator::from_data(carrier, minimal_zip()).unwrap();
+        assert!(matches!(creator.synthesize_idat(), Err(PolyglotError::SizeOverflow)));
+        assert_eq!(creator.effective_method(EmbeddingMethod::Idat), EmbeddingMethod::Text);
+    }
+
+    #[test]
+    fn test_png_flac_polyglot_survives_round_trip() {
+        // Carrier whose PADDING block is large enough for the PNG
+        let png = minimal_png();
+        let mut flac_data = crate::testutil::minimal_flac();
+        let padding_header = 4 + 4 + 34;
+        flac_data[padding_header + 1..padding_header + 4].copy_from_slice(&(png.len() as u32).to_be_bytes()[1..]);
+        flac_data.splice(padding_header + 4..padding_header + 12, vec![0u8; png.len()]);
+
+        let temp_dir = TempDir::new().unwrap();
+        let png_path = temp_dir.path().join("cover.png");
+        let flac_path = temp_dir.path().join("audio.flac");
+        let output_path = temp_dir.path().join("out.flac");
+        std::fs::write(&png_path, &png).unwrap();
+        std::fs::write(&flac_path, &flac_data).unwrap();
+        create_png_flac_polyglot(&png_path, &flac_path, &output_path).unwrap();
+
+        let original = FlacFile::from_data(flac_data).unwrap();
+        let output = FlacFile::from_file(&output_path).unwrap();
+        assert_eq!(output.structure.streaminfo, original.structure.streaminfo);
+        assert_eq!(output.as_bytes()[padding_header], 0x81); // PADDING, last block
+        assert_eq!(output.structure.audio_data, original.structure.audio_data);
+        assert_eq!(output.extract_embedded_png().unwrap(), Some(png));
+    }
+
+    #[test]
+    fn test_wav_in_png_carrier_slot_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let wav_path = temp_dir.path().join("carrier.wav");
+        let zip_path = temp_dir.path().join("a.zip");
+        std::fs::write(&wav_path, minimal_wav()).unwrap();
+        std::fs::write(&zip_path, minimal_zip()).unwrap();
+
+        let result = PolyglotCreator::new(&wav_path, &zip_path);
+        match result {
+            Err(PolyglotError::InvalidInput(message)) => {
+                assert_eq!(message, "--png expects a PNG carrier but the file is a WAV");
+            }
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("WAV accepted as PNG carrier"),
+        }
+    }
+
+    #[test]
+    fn test_png_wav_dominance_ignores_extension_case() {
+        let temp_dir = TempDir::new().unwrap();
+        let png_path = temp_dir.path().join("in.png");
+        let wav_path = temp_dir.path().join("in.wav");
+        std::fs::write(&png_path, minimal_png()).unwrap();
+        std::fs::write(&wav_path, minimal_wav()).unwrap();
+
+        let png_output = temp_dir.path().join("out.PNG");
+        create_png_wav_polyglot(&png_path, &wav_path, &png_output).unwrap();
+        assert!(crate::utils::is_png_signature(&std::fs::read(&png_output).unwrap()));
+
+        let wav_output = temp_dir.path().join("out.WAV");
+        create_png_wav_polyglot(&png_path, &wav_path, &wav_output).unwrap();
+        assert!(std::fs::read(&wav_output).unwrap().starts_with(b"RIFF"));
+    }
+
+    #[test]
+    fn test_swapped_png_wav_inputs_are_named() {
+        let temp_dir = TempDir::new().unwrap();
+        let png_path = temp_dir.path().join("image.png");
+        let wav_path = temp_dir.path().join("audio.wav");
+        std::fs::write(&png_path, minimal_png()).unwrap();
+        std::fs::write(&wav_path, minimal_wav()).unwrap();
+        let png_output = temp_dir.path().join("out.png");
+        let wav_output = temp_dir.path().join("out.wav");
+
+        let message = |result: PolyglotResult<()>| match result {
+            Err(PolyglotError::InvalidInput(message)) => message,
+            other => panic!("expected an input error, got {:?}", other),
+        };
+
+        assert_eq!(
+            message(create_png_wav_polyglot(&wav_path, &png_path, &png_output)),
+            "the --png file is actually a WAV and the --wav file is actually a PNG; swap the two inputs"
+        );
+        assert_eq!(
+            message(create_png_wav_polyglot(&png_path, &png_path, &wav_output)),
+            "the --wav file is actually a PNG; --wav expects the WAV carrier"
+        );
+        assert_eq!(
+            message(create_png_wav_polyglot(&wav_path, &wav_path, &png_output)),
+            "the --png file is actually a WAV; --png expects the PNG carrier"
+        );
+        assert!(!png_output.exists() && !wav_output.exists());
+    }
+
+    #[test]
+    fn test_zip_comment_polyglot_is_empty_zip_with_png() {
+        let png_data = minimal_png();
+        let polyglot = build_zip_comment_polyglot(&png_data).unwrap();
+
+        let archive = ::zip::ZipArchive::new(std::io::Cursor::new(polyglot.clone())).unwrap();
+        assert_eq!(archive.len(), 0);
+        assert_eq!(&polyglot[22..30], b"\x89PNG\r\n\x1a\n");
+
+        let extracted = crate::extract::extract_png_from_zip_comment_data(&polyglot).unwrap();
+        assert_eq!(extracted, png_data);
+
+        // Too large for a ZIP comment
+        let mut large_png = png_data.clone();
+        large_png.resize(70_000, 0);
+        assert!(build_zip_comment_polyglot(&large_png).is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_chunk_polyglot_keeps_image_and_payload() {
+        let mut carrier = Vec::new();
+        let mut encoder = ::png::Encoder::new(&mut carrier, 1, 1);
+        encoder.set_color(::png::ColorType::Rgb);
+        encoder.write_header().unwrap().write_image_data(&[0xC0, 0xFF, 0xEE]).unwrap();
+
+        let mut creator = PolyglotCreator::from_data(carrier, minimal_zip()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Chunk).unwrap();
+
+        // Payload chunk sits between the image data and IEND
+        let png = PngFile::from_data(polyglot.clone()).unwrap();
+        let types: Vec<[u8; 4]> = png.parsed.chunks.iter().map(|chunk| chunk.chunk_type).collect();
+        assert_eq!(&types[types.len() - 3..], &[*b"IDAT", crate::png::PAYLOAD_CHUNK_TYPE, *b"IEND"]);
+
+        assert_eq!(png.decode_to_rgba().unwrap(), (1, 1, vec![0xC0, 0xFF, 0xEE, 0xFF]));
+        assert_eq!(png.payload_chunk().unwrap(), creator.zip().as_bytes());
+        assert_eq!(crate::extract::extract_zip_from_png_data(&polyglot).unwrap(), creator.zip().as_bytes());
+        assert_eq!(crate::extract::extract_payload_from_reader(polyglot.as_slice()).unwrap(), creator.zip().as_bytes());
+        assert!(::zip::ZipArchive::new(std::io::Cursor::new(polyglot)).is_ok());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_png_in_png_round_trip() {
+        let encode = |size: u32| {
+            let pixels: Vec<u8> = (0..size * size * 3).map(|i| (i * 37 % 251) as u8).collect();
+            let mut png_data = Vec::new();
+            let mut encoder = ::png::Encoder::new(&mut png_data, size, size);
+            encoder.set_color(::png::ColorType::Rgb);
+            encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+            png_data
+        };
+        let (carrier, inner) = (encode(1), encode(32));
+        assert!(inner.len() > carrier.len());
+
+        let polyglot = build_png_in_png_polyglot(&carrier, &inner).unwrap();
+        let extracted = crate::extract::extract_png_from_png_data(&polyglot).unwrap();
+        assert_eq!(extracted, inner);
+
+        // Carrier and inner image each parse and decode on their own
+        let outer = PngFile::from_data(polyglot).unwrap();
+        assert_eq!(outer.decode_to_rgba().unwrap().0, 1);
+        let (width, height, _) = PngFile::from_data(extracted).unwrap().decode_to_rgba().unwrap();
+        assert_eq!((width, height), (32, 32));
+
+        assert!(build_png_in_png_polyglot(&carrier, &minimal_zip()).is_err());
+
+        // An inner PNG cut short before IEND is rejected
+        let mut chunks = outer.parsed.chunks.clone();
+        chunks.iter_mut()
+            .filter(|chunk| chunk.chunk_type == crate::png::PAYLOAD_CHUNK_TYPE)
+            .for_each(|chunk| chunk.data.truncate(40));
+        let mut damaged = outer.clone();
+        damaged.rebuild_from_chunks(&chunks).unwrap();
+        assert!(crate::extract::extract_png_from_png_data(&damaged.raw_data).is_err());
+    }
+
+    #[test]
+    fn test_bidirectional_keeps_source_srgb_chunk() {
+        let mut source = PngFile::from_data(minimal_png()).unwrap();
+        source.insert_chunk_after_ihdr(b"sRGB", &[0]).unwrap();
+        let creator = TrueBidirectionalPngWavCreator::from_data(source.as_bytes().to_vec(), minimal_wav()).unwrap();
+        let polyglot = creator.build_bidirectional_polyglot();
+
+        let chunks = PngFile::from_data(polyglot.clone()).unwrap().parsed.chunks;
+        let position = |chunk_type: &[u8; 4]| chunks.iter().position(|chunk| chunk.chunk_type == *chunk_type);
+        let srgb = position(b"sRGB").expect("sRGB chunk missing from output");
+        assert_eq!(chunks[srgb].data, [0]);
+        assert!(position(b"IHDR").unwrap() < srgb && srgb < position(b"IDAT").unwrap());
+        assert_eq!(crate::extract::extract_wav_from_png_data(&polyglot).unwrap(), minimal_wav());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_split_payload_is_reassembled() {
+        let payload = minimal_zip();
+        let polyglot = build_split_payload_polyglot(&minimal_png(), &payload, 10).unwrap();
+
+        // The head sits in IDAT and the rest after IEND
+        let png = PngFile::from_data(polyglot.clone()).unwrap();
+        assert!(png.idat_data().ends_with(&payload[..10]));
+        assert!(polyglot.ends_with(&payload[10..]));
+        assert_eq!(crate::extract::extract_split_payload(&polyglot).unwrap(), Some(payload.clone()));
+
+        // Everything in either region also works
+        for head_len in [0, payload.len()] {
+            let polyglot = build_split_payload_polyglot(&minimal_png(), &payload, head_len).unwrap();
+            assert_eq!(crate::extract::extract_split_payload(&polyglot).unwrap(), Some(payload.clone()));
+        }
+
+        // A lost tail byte is an error, a plain PNG is not split at all
+        assert!(crate::extract::extract_split_payload(&polyglot[..polyglot.len() - 1]).is_err());
+        assert_eq!(crate::extract::extract_split_payload(&minimal_png()).unwrap(), None);
+        let without_tail = &polyglot[..polyglot.len() - SPLIT_TAIL_MAGIC.len() - (payload.len() - 10)];
+        assert_eq!(crate::extract::extract_split_payload(without_tail).unwrap(), None);
+        assert!(build_split_payload_polyglot(&minimal_png(), &payload, payload.len() + 1).is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_split_payload_head_may_contain_magic() {
+        let mut payload = b"head plSP head".to_vec();
+        payload.extend_from_slice(&minimal_zip());
+        let polyglot = build_split_payload_polyglot(&minimal_png(), &payload, 14).unwrap();
+        assert_eq!(crate::extract::extract_split_payload(&polyglot).unwrap(), Some(payload));
+
+        // Damaged framing is reported rather than treated as "not split"
+        assert!(crate::extract::extract_split_payload(&polyglot[..polyglot.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_signed_payload_verifies_until_tampered() {
+        let key = b"polyglot signing key";
+        for method in [EmbeddingMethod::Text, EmbeddingMethod::Chunk, EmbeddingMethod::Idat] {
+            let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+            creator.set_hmac_key(Some(key.to_vec()));
+            let projected_size = creator.projected_size(method).unwrap();
+            let polyglot = creator.create_polyglot_in_memory_with_method(method).unwrap();
+            assert_eq!(polyglot.len(), projected_size, "{}", method);
+
+            let png = PngFile::from_data(polyglot.clone()).unwrap();
+            let mut payload = crate::extract::extract_zip_from_png_data(&polyglot).unwrap();
+            crate::signing::verify_payload_hmac(&png.parsed, &payload, key).unwrap();
+            assert!(crate::signing::verify_payload_hmac(&png.parsed, &payload, b"wrong key").is_err());
+
+            let last = payload.len() - 1;
+            payload[last] ^= 0x01;
+            assert!(matches!(
+                crate::signing::verify_payload_hmac(&png.parsed, &payload, key),
+                Err(PolyglotError::ValidationFailed(_))
+            ), "{}", method);
+        }
+
+        // Unsigned output and the ZIP-dominant method have nowhere to keep a tag
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        let unsigned = PngFile::from_data(creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap()).unwrap();
+        assert!(crate::signing::verify_payload_hmac(&unsigned.parsed, b"", key).is_err());
+        creator.set_hmac_key(Some(key.to_vec()));
+        assert!(creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).is_err());
+    }
+
+    #[test]
+    fn test_idat_polyglot_opens_as_zip() {
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+
+        // Central directory and local header offsets point at the embedded archive
+        let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(polyglot)).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.by_index(0).unwrap().name(), "test");
+    }
+
+    #[test]
+    fn test_triple_polyglot_accepted_by_all_three_parsers() {
+        let polyglot = build_triple_polyglot(&minimal_png(), &minimal_zip(), &minimal_pdf()).unwrap();
+
+        // PNG: signature first and every chunk CRC intact
+        let png = PngFile::from_data(polyglot.clone()).unwrap();
+        assert_eq!(png.parsed.chunks[1].chunk_type, PDF_CHUNK_TYPE);
+
+        // ZIP: EOCD plus its comment run to the end of the file
+        let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(polyglot.clone())).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "test");
+        let eocd = crate::zip::offsets::find_eocd(&polyglot).unwrap();
+        let eocd_offset = crate::zip::offsets::find_eocd_offset(&polyglot).unwrap();
+        assert_eq!(eocd_offset + 22 + eocd.comment_length as usize, polyglot.len());
+        assert!(polyglot.ends_with(b"%%EOF\n"));
+
+        // PDF: header near the start, trailer near the end, every object where xref says
+        crate::pdf::validate_pdf(&polyglot).unwrap();
+    }
+
+    #[test]
+    fn test_empty_payloads_are_rejected() {
+        let is_empty_error = |result: PolyglotResult<_>| matches!(
+            result, Err(PolyglotError::InvalidInput(message)) if message == "payload is empty; there is nothing to embed"
+        );
+
+        // Every create method
+        assert!(is_empty_error(PolyglotCreator::from_data(minimal_png(), Vec::new()).map(|_| ())));
+        assert!(is_empty_error(build_zip_comment_polyglot(&[]).map(|_| ())));
+        assert!(is_empty_error(TrueBidirectionalPngWavCreator::from_data(minimal_png(), Vec::new()).map(|_| ())));
+        assert!(is_empty_error(build_png_in_png_polyglot(&minimal_png(), &[]).map(|_| ())));
+
+        // And the primitives behind them
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        assert!(is_empty_error(png.add_zip_text_chunk(&[])));
+        assert!(is_empty_error(png.add_payload_chunk(&[]).map(|_| ())));
+        assert!(is_empty_error(png.append_to_idat(&[])));
+        assert!(is_empty_error(png.add_splt_payload("empty", &[])));
+        assert_eq!(png.as_bytes(), minimal_png());
+
+        let mut wav = crate::wav::WavFile::from_data(minimal_wav()).unwrap();
+        assert!(is_empty_error(wav.embed_png_data(&[])));
+        assert!(is_empty_error(wav.embed_png_in_junk(&[])));
+        assert_eq!(wav.as_bytes(), minimal_wav());
+
+        let mut flac = FlacFile::from_data(crate::testutil::minimal_flac()).unwrap();
+        assert!(is_empty_error(flac.inject_png_to_padding(&[])));
+
+        let mut builder = MultiPayloadBuilder::new(PngFile::from_data(minimal_png()).unwrap());
+        assert!(is_empty_error(builder.add_payload(FileFormat::Unknown, EmbeddingMethod::Idat, Vec::new())));
+        assert!(is_empty_error(builder.add_named_payload("empty", Vec::new())));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_empty_payloads_are_rejected_by_image_data_modes() {
+        let mut png = PngFile::from_data(minimal_png()).unwrap();
+        for result in [png.embed_in_zlib_stream(&[]), png.embed_lsb(&[]), png.add_iccp_payload("empty", &[])] {
+            assert!(matches!(result, Err(PolyglotError::InvalidInput(message)) if message.contains("payload is empty")));
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_merge_text_zip_and_idat_wav() {
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        let zip_polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Text).unwrap();
+        let mut wav_polyglot = PngFile::from_data(minimal_png()).unwrap();
+        wav_polyglot.append_wav_to_idat(&minimal_wav()).unwrap();
+
+        let merged = merge_polyglots(&zip_polyglot, wav_polyglot.as_bytes()).unwrap();
+        assert_eq!(crate::extract::extract_zip_from_png_data(&merged).unwrap(), minimal_zip());
+        assert_eq!(crate::extract::extract_wav_from_png_data(&merged).unwrap(), minimal_wav());
+
+        // Two ZIPs: the second lands in IDAT, rebased, and still opens
+        let merged = merge_polyglots(&zip_polyglot, &zip_polyglot).unwrap();
+        let names: Vec<String> = crate::extract::extract_payloads(&merged).unwrap()
+            .into_iter().map(|(entry, _)| entry.name).collect();
+        assert_eq!(names, ["text", "idat"]);
+
+        // Through files, as the CLI does
+        let dir = TempDir::new().unwrap();
+        let (a, b, out) = (dir.path().join("a.png"), dir.path().join("b.png"), dir.path().join("out.png"));
+        std::fs::write(&a, &zip_polyglot).unwrap();
+        std::fs::write(&b, wav_polyglot.as_bytes()).unwrap();
+        merge(&a, &b, &out).unwrap();
+        assert_eq!(crate::extract::extract_wav_from_png_data(&std::fs::read(&out).unwrap()).unwrap(), minimal_wav());
+        assert!(merge(&a, &dir.path().join("missing.png"), &out).is_err());
+    }
+
+    #[test]
+    fn test_multi_payload_zip_in_text_and_wav_in_idat() {
+        let mut builder = MultiPayloadBuilder::new(PngFile::from_data(minimal_png()).unwrap());
+        builder.add_payload(FileFormat::Zip, EmbeddingMethod::Text, minimal_zip()).unwrap();
+        builder.add_payload(FileFormat::Wav, EmbeddingMethod::Idat, minimal_wav()).unwrap();
+
+        // A second IDAT payload would run into the first
+        let conflict = builder.add_payload(FileFormat::Zip, EmbeddingMethod::Idat, minimal_zip());
+        assert!(matches!(conflict, Err(PolyglotError::InvalidInput(_))));
+
+        let polyglot = builder.build().unwrap();
+        PngFile::from_data(polyglot.clone()).unwrap();
+        assert_eq!(crate::extract::extract_wav_from_png_data(&polyglot).unwrap(), minimal_wav());
+        let (_, zip_payload) = crate::extract::split_polyglot_data(&polyglot).unwrap();
+        assert_eq!(zip_payload, minimal_zip());
+    }
+
+    #[test]
+    fn test_max_output_size_guard() {
+        for method in [EmbeddingMethod::Text, EmbeddingMethod::Idat, EmbeddingMethod::Zip] {
+            let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+            let projected = creator.projected_size(method).unwrap();
+
+            // One byte short of the projection aborts before building
+            creator.set_max_output_size(Some(projected - 1));
+            match creator.create_polyglot_in_memory_with_method(method) {
+                Err(PolyglotError::InvalidInput(message)) => assert!(message.contains("exceeds the maximum size"), "{}", message),
+                other => panic!("{method}: expected size error, got {:?}", other.map(|data| data.len())),
+            }
+
+            // Exactly at the limit succeeds, and the projection is exact
+            creator.set_max_output_size(Some(projected));
+            let polyglot = creator.create_polyglot_in_memory_with_method(method).unwrap();
+            assert_eq!(polyglot.len(), projected, "{method}");
+        }
+    }
+
+    #[test]
+    fn test_idat_payload_larger_than_limit_extracts_intact() {
+        let zip_data = minimal_zip();
+        let limit = zip_data.len() / 4;
+        let mut creator = PolyglotCreator::from_data(minimal_png(), zip_data.clone()).unwrap();
+        creator.set_max_idat_size(limit);
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+
+        // Same archive apart from the rebased central directory offset
+        let extracted = crate::extract::extract_zip_from_png_data(&polyglot).unwrap();
+        assert_eq!(extracted.len(), zip_data.len());
+        let cd_offset = crate::zip::offsets::find_eocd(&zip_data).unwrap().cd_offset as usize;
+        assert_eq!(extracted[..cd_offset], zip_data[..cd_offset]);
+
+        let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(polyglot)).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "test");
+    }
+
+    #[test]
+    fn test_projected_payload_offset_matches_detection() {
+        let carrier = PngFile::from_data(minimal_png()).unwrap();
+        let cases = [
+            (EmbeddingMethod::Idat, "zip"),
+            (EmbeddingMethod::Text, "zip"),
+            (EmbeddingMethod::Chunk, "zip"),
+            (EmbeddingMethod::Zip, "png"),
+            (EmbeddingMethod::Comment, "png"),
+        ];
+        for (method, payload_format) in cases {
+            let projected = method.projected_payload_offset(&carrier).unwrap();
+
+            let output = if method == EmbeddingMethod::Comment {
+                build_zip_comment_polyglot(carrier.as_bytes()).unwrap()
+            } else {
+                let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+                creator.create_polyglot_in_memory_with_method(method).unwrap()
+            };
+            let detected = crate::extract::detect_payloads(&output);
+            let payload = detected.iter().find(|p| p.format == payload_format).unwrap();
+
+            assert_eq!(projected, payload.offset, "{}", method.name());
+        }
+    }
+
+    #[test]
+    fn test_overhead_matches_output_size() {
+        let png_data = minimal_png();
+        let zip_data = minimal_zip();
+        let carrier = PngFile::from_data(png_data.clone()).unwrap();
+
+        for method in [EmbeddingMethod::Idat, EmbeddingMethod::Text, EmbeddingMethod::Chunk] {
+            let mut creator = PolyglotCreator::from_data(png_data.clone(), zip_data.clone()).unwrap();
+            let polyglot = creator.create_polyglot_in_memory_with_method(method).unwrap();
+            assert_eq!(polyglot.len() - png_data.len() - zip_data.len(), method.overhead_bytes_for(&carrier).unwrap(), "{}", method.name());
+        }
+
+        let mut creator = PolyglotCreator::from_data(png_data.clone(), zip_data.clone()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).unwrap();
+        assert_eq!(polyglot.len() - png_data.len(), EmbeddingMethod::Zip.overhead_bytes_for(&carrier).unwrap());
+
+        let polyglot = build_zip_comment_polyglot(&png_data).unwrap();
+        assert_eq!(polyglot.len() - png_data.len(), EmbeddingMethod::Comment.overhead_bytes_for(&carrier).unwrap());
+
+        let wav_data = minimal_wav();
+        let polyglot = TrueBidirectionalPngWavCreator::from_data(png_data, wav_data.clone()).unwrap().build_bidirectional_polyglot();
+        assert_eq!(polyglot.len() - wav_data.len(), EmbeddingMethod::Bidirectional.overhead_bytes_for(&carrier).unwrap());
+    }
+
+    #[test]
+    fn test_overhead_includes_colour_chunks_and_idat_splits() {
+        // Colour chunks copied into the fabricated image count towards the bidirectional overhead
+        let mut carrier = PngFile::from_data(minimal_png()).unwrap();
+        carrier.insert_chunk_after_ihdr(b"gAMA", &45455u32.to_be_bytes()).unwrap();
+        let wav_data = minimal_wav();
+        let polyglot = TrueBidirectionalPngWavCreator::from_data(carrier.raw_data.clone(), wav_data.clone()).unwrap().build_bidirectional_polyglot();
+        let overhead = EmbeddingMethod::Bidirectional.overhead_bytes_for(&carrier).unwrap();
+        assert_eq!(overhead, EmbeddingMethod::Bidirectional.overhead_bytes() + 16);
+        assert_eq!(polyglot.len() - wav_data.len(), overhead);
+
+        // A first IDAT over the limit is split, adding one chunk's framing per extra piece
+        let mut carrier = PngFile::from_data(minimal_png()).unwrap();
+        let chunks: Vec<crate::png::Chunk> = carrier.parsed.chunks.iter().cloned().map(|mut chunk| {
+            if chunk.chunk_type == *b"IDAT" {
+                chunk.data = vec![0x5A; 2 * crate::png::DEFAULT_MAX_IDAT_SIZE + 1];
+            }
+            chunk
+        }).collect();
+        carrier.rebuild_from_chunks(&chunks).unwrap();
+        let zip_data = minimal_zip();
+        let mut creator = PolyglotCreator::from_data(carrier.raw_data.clone(), zip_data.clone()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+        assert_eq!(EmbeddingMethod::Idat.overhead_bytes_for(&carrier).unwrap(), 24);
+        assert_eq!(polyglot.len() - carrier.raw_data.len() - zip_data.len(), 24);
+    }
+
+    #[test]
+    fn test_idat_overhead_with_multi_idat_carrier() {
+        // The carrier's zlib stream split across two IDATs
+        let png_data = minimal_png();
+        let idat_start = png_data.windows(4).position(|w| w == b"IDAT").unwrap() - 4;
+        let idat_len = crate::utils::read_u32_be(&png_data, idat_start) as usize;
+        let idat = &png_data[idat_start + 8..idat_start + 8 + idat_len];
+        let mut carrier = png_data[..idat_start].to_vec();
+        for part in [&idat[..6], &idat[6..]] {
+            carrier.extend_from_slice(&(part.len() as u32).to_be_bytes());
+            carrier.extend_from_slice(b"IDAT");
+            carrier.extend_from_slice(part);
+            carrier.extend_from_slice(&crate::png::chunk_crc(b"IDAT", part).to_be_bytes());
+        }
+        carrier.extend_from_slice(&png_data[idat_start + 12 + idat_len..]);
+
+        let zip_data = minimal_zip();
+        let mut creator = PolyglotCreator::from_data(carrier.clone(), zip_data.clone()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Idat).unwrap();
+
+        assert_eq!(polyglot.len() - carrier.len() - zip_data.len(), EmbeddingMethod::Idat.overhead_bytes_for(&PngFile::from_data(carrier.clone()).unwrap()).unwrap());
+        let projected = EmbeddingMethod::Idat.projected_payload_offset(&PngFile::from_data(carrier).unwrap()).unwrap();
+        let detected = crate::extract::detect_payloads(&polyglot);
+        assert_eq!(detected.iter().find(|p| p.format == "zip").unwrap().offset, projected);
+    }
+
+    #[test]
+    fn test_method_names_parse_back() {
+        for method in EmbeddingMethod::ALL {
+            assert_eq!(method.to_string().parse::<EmbeddingMethod>().unwrap(), method);
+        }
+        assert_eq!(EmbeddingMethod::names()[0], "text");
+        assert!(matches!("IDAT".parse::<EmbeddingMethod>(), Err(PolyglotError::InvalidInput(_))));
+
+        // Methods with other inputs than a PNG and a ZIP are rejected by the PNG+ZIP creator
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        assert!(creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Comment).is_err());
+        assert!(creator.projected_size(EmbeddingMethod::Bidirectional).is_err());
+    }
+
+    #[test]
+    fn test_verify_roundtrip_each_method() {
+        let png_data = minimal_png();
+
+        verify_roundtrip(&png_data, &minimal_zip(), EmbeddingMethod::Text).unwrap();
+        verify_roundtrip(&png_data, &minimal_zip(), EmbeddingMethod::Idat).unwrap();
+        verify_roundtrip(&png_data, &minimal_zip(), EmbeddingMethod::Zip).unwrap();
+        verify_roundtrip(&png_data, &[], EmbeddingMethod::Comment).unwrap();
+        verify_roundtrip(&png_data, &minimal_wav(), EmbeddingMethod::Bidirectional).unwrap();
+    }
+
+    #[test]
+    fn test_verify_roundtrip_detects_mismatch() {
+        // Bytes after the EOCD are embedded but not part of the extracted archive
+        let mut zip_data = minimal_zip();
+        zip_data.extend_from_slice(b"trailing");
+
+        let result = verify_roundtrip(&minimal_png(), &zip_data, EmbeddingMethod::Text);
+        match result {
+            Err(PolyglotError::ValidationFailed(message)) => {
+                assert!(message.starts_with("text round trip mismatch"), "{}", message);
+            }
+            other => panic!("expected mismatch, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_polyglot_creation() {
-        let png_data = create_test_png();
-        let zip_data = create_test_zip();
+        let png_data = minimal_png();
+        let zip_data = minimal_zip();
 
         let mut creator = PolyglotCreator::from_data(png_data, zip_data).unwrap();
 
@@ -558,7 +1909,7 @@ mod tests {
         let polyglot_data = creator.create_polyglot_in_memory().unwrap();
 
         // Should be larger than original PNG
-        assert!(polyglot_data.len() > create_test_png().len());
+        assert!(polyglot_data.len() > minimal_png().len());
 
         // Should still start with PNG signature
         assert_eq!(&polyglot_data[0..8], &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]);
@@ -567,4 +1918,41 @@ mod tests {
         let zip_sig_pos = polyglot_data.windows(4).position(|w| w == [0x50, 0x4B, 0x03, 0x04]);
         assert!(zip_sig_pos.is_some());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_zip_dominant_entry_mode_survives_unzip() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        assert!(creator.set_entry_mode(0o1000).is_err());
+        creator.set_entry_mode(0o755).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).unwrap();
+
+        let out_dir = TempDir::new().unwrap();
+        crate::extract::unzip_all(&polyglot, out_dir.path()).unwrap();
+        let mode = std::fs::metadata(out_dir.path().join("image.png")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_zip_dominant_entry_attributes_are_written() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut creator = PolyglotCreator::from_data(minimal_png(), minimal_zip()).unwrap();
+        creator.set_entry_mode(0o700).unwrap();
+        creator.set_entry_text(true);
+        let polyglot = creator.create_polyglot_in_memory_with_method(EmbeddingMethod::Zip).unwrap();
+
+        let cd = polyglot.windows(4).position(|w| w == [0x50, 0x4B, 0x01, 0x02]).unwrap();
+        assert_eq!(u16::from_le_bytes([polyglot[cd + 36], polyglot[cd + 37]]), 1);
+        let external = u32::from_le_bytes(polyglot[cd + 38..cd + 42].try_into().unwrap());
+        assert_eq!(external >> 16, 0o100700);
+
+        let out_dir = TempDir::new().unwrap();
+        crate::extract::unzip_all(&polyglot, out_dir.path()).unwrap();
+        let mode = std::fs::metadata(out_dir.path().join("image.png")).unwrap().permissions().mode();
+        assert_ne!(mode & 0o100, 0, "owner exec bit should be set");
+    }
 }
@@ -0,0 +1,89 @@
+//! HMAC-SHA256 tags over embedded payloads, for tamper evidence
+//!
+//! The tag is the whole data of a private `plHM` chunk placed before IEND. It covers
+//! the payload exactly as extraction returns it, so a ZIP rebased into the carrier is
+//! signed with its rebased offsets. Anyone holding the key can re-sign, so this shows
+//! the payload is unchanged, not who produced it.
+
+use alloc::{format, string::ToString, vec::Vec};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::png::ParsedPng;
+use crate::{PolyglotError, PolyglotResult};
+
+/// Chunk type of the tag: ancillary, private, not safe-to-copy
+pub const HMAC_CHUNK_TYPE: [u8; 4] = *b"plHM";
+
+/// Length of an HMAC-SHA256 tag
+pub const HMAC_LEN: usize = 32;
+
+/// HMAC-SHA256 of the payload under `key`
+pub fn payload_hmac(key: &[u8], payload: &[u8]) -> [u8; HMAC_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.finalize().into_bytes().into()
+}
+
+/// Check the payload against the tag stored in the PNG's `plHM` chunk
+///
+/// The comparison is constant-time.
+pub fn verify_payload_hmac(png: &ParsedPng, payload: &[u8], key: &[u8]) -> PolyglotResult<()> {
+    let chunk = png.chunks.iter()
+        .find(|chunk| chunk.chunk_type == HMAC_CHUNK_TYPE)
+        .ok_or_else(|| PolyglotError::ValidationFailed("no plHM chunk, the payload is not signed".to_string()))?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.verify_slice(&chunk.data).map_err(|_| PolyglotError::ValidationFailed(
+        "payload HMAC does not match: the payload was modified or the key is wrong".to_string()
+    ))
+}
+
+/// Parse a key given as hex digits, e.g. on the command line
+pub fn parse_hex_key(hex: &str) -> PolyglotResult<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return Err(PolyglotError::InvalidInput(format!("HMAC key must be an even number of hex digits, got {}", hex.len())));
+    }
+    // Only say where the bad digit is: the key itself is a secret and shouldn't end up in logs
+    if let Some((position, byte)) = hex.bytes().enumerate().find(|(_, byte)| !byte.is_ascii_hexdigit()) {
+        let class = if !byte.is_ascii() {
+            "a non-ASCII character"
+        } else if byte.is_ascii_whitespace() {
+            "whitespace"
+        } else {
+            "a non-hex character"
+        };
+        return Err(PolyglotError::InvalidInput(format!("HMAC key has {} at byte {}", class, position)));
+    }
+
+    Ok(hex.as_bytes().chunks(2)
+        .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).expect("ASCII hex digits"), 16).expect("validated hex"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_matches_rfc4231() {
+        // RFC 4231 test case 2
+        let tag = payload_hmac(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(tag[..4], [0x5b, 0xdc, 0xc1, 0x46]);
+        assert_eq!(tag[28..], [0x64, 0xec, 0x38, 0x43]);
+
+        assert_eq!(parse_hex_key("4a656665").unwrap(), b"Jefe");
+        assert!(parse_hex_key("4a6").is_err());
+        assert!(parse_hex_key("zz").is_err());
+        assert!(parse_hex_key("+f").is_err());
+    }
+
+    #[test]
+    fn test_invalid_hex_key_is_not_echoed() {
+        let key = "4a65secret";
+        let message = parse_hex_key(key).unwrap_err().to_string();
+        assert!(message.contains("byte 4"), "{}", message);
+        assert!(!message.contains("secret") && !message.contains("4a65"), "{}", message);
+        assert!(parse_hex_key("4a 6").unwrap_err().to_string().contains("whitespace"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Minimal, valid carrier files for tests (enabled by the `testutil` feature)
+
+use alloc::{format, vec, vec::Vec};
+use crate::png::write_chunk;
+
+/// 1x1 RGB PNG with IHDR, one IDAT and IEND
+pub fn minimal_png() -> Vec<u8> {
+    let mut png = vec![
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
+    ];
+
+    // IHDR chunk
+    let ihdr_data = [
+        0x00, 0x00, 0x00, 0x01, // width = 1
+        0x00, 0x00, 0x00, 0x01, // height = 1
+        0x08, // bit depth = 8
+        0x02, // color type = 2 (RGB)
+        0x00, // compression = 0
+        0x00, // filter = 0
+        0x00, // interlace = 0
+    ];
+    write_chunk(&mut png, b"IHDR", &ihdr_data);
+
+    // IDAT chunk with the zlib-compressed scanline of one black pixel
+    let idat_data = [
+        0x78, 0xDA, 0x63, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01
+    ];
+    write_chunk(&mut png, b"IDAT", &idat_data);
+
+    // IEND chunk
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+/// Stored ZIP archive with one empty file named "test"
+pub fn minimal_zip() -> Vec<u8> {
+    // Local file header
+    let mut zip = vec![0x50, 0x4B, 0x03, 0x04]; // LFHS
+    zip.extend_from_slice(&[0x0A, 0x00]); // Version needed
+    zip.extend_from_slice(&[0x00, 0x00]); // GPB flag
+    zip.extend_from_slice(&[0x00, 0x00]); // Compression method
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Last mod time/date
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // CRC32
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Compressed size
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Uncompressed size
+    zip.extend_from_slice(&[0x04, 0x00]); // Filename length
+    zip.extend_from_slice(&[0x00, 0x00]); // Extra field length
+    zip.extend_from_slice(b"test"); // Filename
+    // Data (empty)
+
+    // Central directory header
+    let cd_offset = zip.len() as u32;
+    zip.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]); // CDHS
+    zip.extend_from_slice(&[0x0A, 0x00]); // Version made by
+    zip.extend_from_slice(&[0x0A, 0x00]); // Version needed
+    zip.extend_from_slice(&[0x00, 0x00]); // GPB flag
+    zip.extend_from_slice(&[0x00, 0x00]); // Compression method
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Last mod time/date
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // CRC32
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Compressed size
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Uncompressed size
+    zip.extend_from_slice(&[0x04, 0x00]); // Filename length
+    zip.extend_from_slice(&[0x00, 0x00]); // Extra field length
+    zip.extend_from_slice(&[0x00, 0x00]); // File comment length
+    zip.extend_from_slice(&[0x00, 0x00]); // Disk number
+    zip.extend_from_slice(&[0x00, 0x00]); // Internal attributes
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // External attributes
+    zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Local header offset
+    zip.extend_from_slice(b"test"); // Filename
+    let cd_size = zip.len() as u32 - cd_offset;
+
+    // End of central directory
+    zip.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06]); // EOCDS
+    zip.extend_from_slice(&[0x00, 0x00]); // Disk number
+    zip.extend_from_slice(&[0x00, 0x00]); // CD disk number
+    zip.extend_from_slice(&[0x01, 0x00]); // Entries on this disk
+    zip.extend_from_slice(&[0x01, 0x00]); // Total entries
+    zip.extend_from_slice(&cd_size.to_le_bytes()); // CD size
+    zip.extend_from_slice(&cd_offset.to_le_bytes()); // CD offset
+    zip.extend_from_slice(&[0x00, 0x00]); // Comment length
+
+    zip
+}
+
+/// Mono 16-bit 44.1 kHz PCM WAV with two silent samples
+pub fn minimal_wav() -> Vec<u8> {
+    let mut wav = vec![];
+
+    // RIFF header
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(40u32).to_le_bytes()); // File size = total - 8 bytes for RIFF header/WAVE signature (48 - 8 = 40)
+    wav.extend_from_slice(b"WAVE");
+
+    // fmt chunk (16 bytes)
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&(16u32).to_le_bytes()); // Chunk size
+    wav.extend_from_slice(&(1u16).to_le_bytes()); // Audio format (PCM)
+    wav.extend_from_slice(&(1u16).to_le_bytes()); // Channels
+    wav.extend_from_slice(&(44100u32).to_le_bytes()); // Sample rate
+    wav.extend_from_slice(&(88200u32).to_le_bytes()); // Byte rate
+    wav.extend_from_slice(&(2u16).to_le_bytes()); // Block align
+    wav.extend_from_slice(&(16u16).to_le_bytes()); // Bits per sample
+
+    // data chunk (minimal audio data)
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(4u32).to_le_bytes()); // Data size
+    wav.extend_from_slice(&(0u16).to_le_bytes()); // Minimal audio data
+    wav.extend_from_slice(&(0u16).to_le_bytes());
+
+    wav
+}
+
+/// FLAC stream with STREAMINFO, an 8-byte PADDING block and a fake audio frame
+pub fn minimal_flac() -> Vec<u8> {
+    let mut flac = b"fLaC".to_vec();
+
+    // STREAMINFO block (not last)
+    flac.push(0x00);
+    flac.extend_from_slice(&[0x00, 0x00, 0x22]); // 34 bytes
+    flac.extend_from_slice(&4096u16.to_be_bytes()); // Min block size
+    flac.extend_from_slice(&4096u16.to_be_bytes()); // Max block size
+    flac.extend_from_slice(&[0x00, 0x00, 0x10]); // Min frame size
+    flac.extend_from_slice(&[0x00, 0x20, 0x00]); // Max frame size
+    // 44100 Hz, 2 channels, 16 bits, 88200 samples
+    let packed: u64 = (44100 << 44) | (1 << 41) | (15 << 36) | 88200;
+    flac.extend_from_slice(&packed.to_be_bytes());
+    flac.extend_from_slice(&[0xAB; 16]); // MD5
+
+    // PADDING block (last)
+    flac.push(0x81);
+    flac.extend_from_slice(&[0x00, 0x00, 0x08]);
+    flac.extend_from_slice(&[0u8; 8]);
+
+    // Fake audio frame data
+    flac.extend_from_slice(&[0xFF, 0xF8, 0x69, 0x08, 0x00, 0x01, 0x02, 0x03]);
+
+    flac
+}
+
+/// 1x1 GIF89a with a two-colour global color table
+pub fn minimal_gif() -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+
+    // Logical screen descriptor: 1x1, 2-color global color table
+    gif.extend_from_slice(&[0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00]);
+    gif.extend_from_slice(&[0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF]);
+
+    // Image descriptor, LZW minimum code size, one sub-block, terminator
+    gif.extend_from_slice(&[0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00]);
+    gif.push(0x02);
+    gif.extend_from_slice(&[0x02, 0x44, 0x3B]);
+    gif.push(0x00);
+
+    // Trailer
+    gif.push(0x3B);
+    gif
+}
+
+/// Single blank page PDF with a classic xref table whose offsets are exact
+pub fn minimal_pdf() -> Vec<u8> {
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>",
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 1 1] >>",
+    ];
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).as_bytes());
+    }
+
+    // Entries are exactly 20 bytes: 10-digit offset, 5-digit generation, type, two-byte EOL
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f\r\n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{offset:010} 00000 n\r\n").as_bytes());
+    }
+    pdf.extend_from_slice(format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n", objects.len() + 1
+    ).as_bytes());
+
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assets_parse_with_their_loaders() {
+        crate::png::PngFile::from_data(minimal_png()).unwrap();
+        crate::zip::ZipArchive::from_data(minimal_zip()).unwrap();
+        crate::wav::WavFile::from_data(minimal_wav()).unwrap();
+        crate::flac::FlacFile::from_data(minimal_flac()).unwrap();
+        crate::gif::GifFile::from_data(minimal_gif()).unwrap();
+
+        // The ZIP is also readable by a standard archive reader
+        let archive = ::zip::ZipArchive::new(std::io::Cursor::new(minimal_zip())).unwrap();
+        assert_eq!(archive.len(), 1);
+
+        // And the WAV plays
+        let reader = hound::WavReader::new(std::io::Cursor::new(minimal_wav())).unwrap();
+        assert_eq!(reader.spec().sample_rate, 44100);
+    }
+}
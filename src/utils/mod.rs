@@ -1,14 +1,93 @@
 //! Utility functions for PNG/ZIP polyglot operations
 
-use crc32fast::Hasher;
+use alloc::{format, string::{String, ToString}};
+use core::fmt::Write;
+
+/// Incremental CRC32 (IEEE), backed by `crc32fast` or by `crc32_portable`
+///
+/// The `crc-fast` feature selects `crc32fast`; `crc-portable`, or neither feature,
+/// selects the lookup table.
+#[derive(Debug, Clone, Default)]
+pub struct Crc32Hasher {
+    #[cfg(all(feature = "crc-fast", not(feature = "crc-portable")))]
+    inner: crc32fast::Hasher,
+    #[cfg(any(not(feature = "crc-fast"), feature = "crc-portable"))]
+    crc: u32,
+}
+
+impl Crc32Hasher {
+    /// Start a CRC32 over no data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed more data into the checksum
+    pub fn update(&mut self, data: &[u8]) {
+        #[cfg(all(feature = "crc-fast", not(feature = "crc-portable")))]
+        self.inner.update(data);
+        #[cfg(any(not(feature = "crc-fast"), feature = "crc-portable"))]
+        { self.crc = crc32_portable_update(self.crc, data); }
+    }
+
+    /// CRC32 of all the data fed in so far
+    pub fn finalize(self) -> u32 {
+        #[cfg(all(feature = "crc-fast", not(feature = "crc-portable")))]
+        return self.inner.finalize();
+        #[cfg(any(not(feature = "crc-fast"), feature = "crc-portable"))]
+        return self.crc;
+    }
+}
 
 /// Calculate CRC32 checksum for given data
 pub fn calculate_crc32(data: &[u8]) -> u32 {
-    let mut hasher = Hasher::new();
+    let mut hasher = Crc32Hasher::new();
     hasher.update(data);
     hasher.finalize()
 }
 
+/// Lookup table for the reflected CRC32 polynomial 0xEDB88320
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC32 in pure Rust, one table lookup per byte; always available regardless of features
+pub fn crc32_portable(data: &[u8]) -> u32 {
+    crc32_portable_update(0, data)
+}
+
+/// Continue a finished CRC32 over more data
+fn crc32_portable_update(crc: u32, data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!crc, |crc, &byte| CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8));
+    !crc
+}
+
+/// Calculate the Adler-32 checksum used by zlib streams
+pub fn calculate_adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for block in data.chunks(5552) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
 /// Read a big-endian u32 from byte slice
 pub fn read_u32_be(bytes: &[u8], offset: usize) -> u32 {
     u32::from_be_bytes(bytes[offset..offset + 4].try_into().expect("slice too short"))
@@ -44,15 +123,188 @@ pub fn calculate_offset_adjustment(idat_start_offset: u64, original_idat_length:
     idat_start_offset + original_idat_length
 }
 
+/// PNG file signature
+pub const PNG_SIGNATURE: [u8; 8] = *b"\x89PNG\r\n\x1a\n";
+
+/// ZIP local file header signature, the first bytes of a non-empty archive
+pub const ZIP_LOCAL_SIGNATURE: [u8; 4] = *b"PK\x03\x04";
+
+/// ZIP central directory file header signature
+pub const ZIP_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = *b"PK\x01\x02";
+
+/// ZIP end of central directory signature, the first bytes of an empty archive
+pub const ZIP_EOCD_SIGNATURE: [u8; 4] = *b"PK\x05\x06";
+
+/// ZIP64 end of central directory record signature
+pub const ZIP64_EOCD_SIGNATURE: [u8; 4] = *b"PK\x06\x06";
+
+/// RIFF container signature; a WAV file also has `WAVE` at offset 8
+pub const RIFF_SIGNATURE: [u8; 4] = *b"RIFF";
+
+/// FLAC stream marker
+pub const FLAC_SIGNATURE: [u8; 4] = *b"fLaC";
+
+/// GIF signature, followed by the version `87a` or `89a`
+pub const GIF_SIGNATURE: [u8; 3] = *b"GIF";
+
+/// PDF header, which readers accept anywhere in the first kilobyte
+pub const PDF_SIGNATURE: [u8; 5] = *b"%PDF-";
+
+/// Every signature this crate recognises, by lowercase format name
+pub fn all_signatures() -> &'static [(&'static str, &'static [u8])] {
+    &[
+        ("png", &PNG_SIGNATURE),
+        ("zip", &ZIP_LOCAL_SIGNATURE),
+        ("zip-eocd", &ZIP_EOCD_SIGNATURE),
+        ("riff", &RIFF_SIGNATURE),
+        ("flac", &FLAC_SIGNATURE),
+        ("gif", &GIF_SIGNATURE),
+        ("pdf", &PDF_SIGNATURE),
+    ]
+}
+
 /// Validate PNG signature
 pub fn is_png_signature(data: &[u8]) -> bool {
-    data.len() >= 8 && data[0..8] == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]
+    data.starts_with(&PNG_SIGNATURE)
+}
+
+/// File formats recognised by their leading magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Png,
+    Zip,
+    Wav,
+    Flac,
+    Gif,
+    Pdf,
+    Unknown,
+}
+
+impl FileFormat {
+    /// Short display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileFormat::Png => "PNG",
+            FileFormat::Zip => "ZIP",
+            FileFormat::Wav => "WAV",
+            FileFormat::Flac => "FLAC",
+            FileFormat::Gif => "GIF",
+            FileFormat::Pdf => "PDF",
+            FileFormat::Unknown => "unrecognised format",
+        }
+    }
+
+    /// Format conventionally identified by a file extension (without the dot, any case)
+    pub fn from_extension(extension: &str) -> Option<FileFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(FileFormat::Png),
+            "zip" => Some(FileFormat::Zip),
+            "wav" => Some(FileFormat::Wav),
+            "flac" => Some(FileFormat::Flac),
+            "gif" => Some(FileFormat::Gif),
+            "pdf" => Some(FileFormat::Pdf),
+            _ => None,
+        }
+    }
+}
+
+/// Identify a file's format from its leading magic bytes
+pub fn sniff_format(data: &[u8]) -> FileFormat {
+    if is_png_signature(data) {
+        FileFormat::Png
+    } else if data.len() >= 12 && data.starts_with(&RIFF_SIGNATURE) && &data[8..12] == b"WAVE" {
+        FileFormat::Wav
+    } else if data.starts_with(&FLAC_SIGNATURE) {
+        FileFormat::Flac
+    } else if data.starts_with(&GIF_SIGNATURE) && matches!(data.get(3..6), Some(b"87a" | b"89a")) {
+        FileFormat::Gif
+    } else if data.starts_with(&PDF_SIGNATURE) {
+        FileFormat::Pdf
+    } else if data.starts_with(&ZIP_LOCAL_SIGNATURE) || data.starts_with(&ZIP_EOCD_SIGNATURE) {
+        FileFormat::Zip
+    } else {
+        FileFormat::Unknown
+    }
+}
+
+/// Reject a zero-length payload
+///
+/// Every embedding method would store it as nothing at all, e.g. a tEXt chunk holding
+/// only its keyword or an unchanged IDAT, which extraction can't tell from no payload.
+pub fn ensure_payload_not_empty(payload: &[u8]) -> crate::PolyglotResult<()> {
+    if payload.is_empty() {
+        return Err(crate::PolyglotError::InvalidInput("payload is empty; there is nothing to embed".to_string()));
+    }
+    Ok(())
+}
+
+/// Check that an input given via `--<flag>` has the format its role requires
+pub fn expect_format(data: &[u8], expected: FileFormat, flag: &str, role: &str) -> crate::PolyglotResult<()> {
+    let actual = sniff_format(data);
+    if actual == expected {
+        return Ok(());
+    }
+
+    let found = match actual {
+        FileFormat::Unknown => "not a recognised format".to_string(),
+        format => format!("a {}", format.name()),
+    };
+    Err(crate::PolyglotError::InvalidInput(format!(
+        "--{} expects a {} {} but the file is {}", flag, expected.name(), role, found
+    )))
+}
+
+/// Hex and ASCII dump of the bytes within `radius` of `center`, in `hexdump -C` style
+///
+/// Rows are 16 bytes aligned to multiples of 16 and labelled with their file offset;
+/// positions outside the window are left blank.
+pub fn hexdump(data: &[u8], center: usize, radius: usize) -> String {
+    let start = center.saturating_sub(radius).min(data.len());
+    let end = center.saturating_add(radius).min(data.len());
+
+    let mut output = String::new();
+    let mut row = start - start % 16;
+    while start < end && row < end {
+        let mut ascii = String::new();
+        let _ = write!(output, "{:08x} ", row);
+        for position in row..row + 16 {
+            if position % 8 == 0 {
+                output.push(' ');
+            }
+            if let Some(&byte) = position.checked_sub(start).and_then(|index| data[start..end].get(index)) {
+                let _ = write!(output, "{:02x} ", byte);
+                ascii.push(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' });
+            } else {
+                output.push_str("   ");
+                ascii.push(' ');
+            }
+        }
+        let _ = writeln!(output, " |{}|", ascii);
+        row += 16;
+    }
+
+    output
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_hexdump_format() {
+        let data: Vec<u8> = b"\x89PNG\r\n\x1a\n".iter().copied().chain(0x40..0x60).collect();
+
+        assert_eq!(hexdump(&data, 20, 16), concat!(
+            "00000000              0d 0a 1a 0a  40 41 42 43 44 45 46 47  |    ....@ABCDEFG|\n",
+            "00000010  48 49 4a 4b 4c 4d 4e 4f  50 51 52 53 54 55 56 57  |HIJKLMNOPQRSTUVW|\n",
+            "00000020  58 59 5a 5b                                       |XYZ[            |\n",
+        ));
+
+        // The window is clipped to the data
+        assert_eq!(hexdump(&data, 0, 4), "00000000  89 50 4e 47                                       |.PNG            |\n");
+        assert_eq!(hexdump(&data, 100, 16), "");
+    }
+
     #[test]
     fn test_crc32_calculation() {
         let data = b"Hello, World!";
@@ -61,6 +313,27 @@ mod tests {
         assert_eq!(crc, 3964322768);
     }
 
+    #[test]
+    fn test_crc32_backends_agree() {
+        let data = b"Hello, World!";
+        assert_eq!(crc32_portable(data), 3964322768);
+        #[cfg(feature = "crc-fast")]
+        assert_eq!(crc32fast::hash(data), 3964322768);
+
+        // Split updates continue the same checksum
+        let mut hasher = Crc32Hasher::new();
+        hasher.update(b"Hello, ");
+        hasher.update(b"World!");
+        assert_eq!(hasher.finalize(), 3964322768);
+        assert_eq!(crc32_portable(b""), 0);
+    }
+
+    #[test]
+    fn test_adler32_calculation() {
+        assert_eq!(calculate_adler32(b"Wikipedia"), 0x11E60398);
+        assert_eq!(calculate_adler32(b""), 1);
+    }
+
     #[test]
     fn test_u32_be_operations() {
         let mut buf = vec![0u8; 4];
@@ -83,4 +356,53 @@ mod tests {
         let invalid_sig = [0x00, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
         assert!(!is_png_signature(&invalid_sig));
     }
+
+    #[test]
+    fn test_sniff_format() {
+        assert_eq!(sniff_format(b"\x89PNG\r\n\x1a\n"), FileFormat::Png);
+        assert_eq!(sniff_format(b"RIFF\0\0\0\0WAVE"), FileFormat::Wav);
+        assert_eq!(sniff_format(b"fLaC"), FileFormat::Flac);
+        assert_eq!(sniff_format(b"GIF89a"), FileFormat::Gif);
+        assert_eq!(sniff_format(b"PK\x03\x04"), FileFormat::Zip);
+        assert_eq!(sniff_format(b"RIFF\0\0\0\0AVI "), FileFormat::Unknown);
+    }
+
+    #[test]
+    fn test_loaders_accept_their_signature_constants() {
+        use crate::testutil::*;
+
+        // Each fixture rebuilt from the constant rather than its own leading bytes
+        let with_signature = |signature: &[u8], fixture: Vec<u8>| [signature, &fixture[signature.len()..]].concat();
+        let empty_zip = [ZIP_EOCD_SIGNATURE.as_slice(), &[0u8; 18]].concat();
+
+        crate::png::PngFile::from_data(with_signature(&PNG_SIGNATURE, minimal_png())).unwrap();
+        crate::zip::ZipArchive::from_data(with_signature(&ZIP_LOCAL_SIGNATURE, minimal_zip())).unwrap();
+        assert_eq!(crate::zip::offsets::find_eocd(&empty_zip).unwrap().num_entries_total, 0);
+        crate::wav::WavFile::from_data(with_signature(&RIFF_SIGNATURE, minimal_wav())).unwrap();
+        crate::flac::FlacFile::from_data(with_signature(&FLAC_SIGNATURE, minimal_flac())).unwrap();
+        crate::gif::GifFile::from_data(with_signature(&GIF_SIGNATURE, minimal_gif())).unwrap();
+        crate::pdf::validate_pdf(&with_signature(&PDF_SIGNATURE, minimal_pdf())).unwrap();
+
+        // The table lists every constant once, and sniffing agrees with it
+        let fixtures = [minimal_png(), minimal_zip(), empty_zip, minimal_wav(), minimal_flac(), minimal_gif(), minimal_pdf()];
+        assert_eq!(all_signatures().len(), fixtures.len());
+        for ((name, signature), fixture) in all_signatures().iter().zip(fixtures) {
+            assert!(fixture.starts_with(signature), "{}", name);
+            assert_ne!(sniff_format(&fixture), FileFormat::Unknown, "{}", name);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decoder_errors_convert_to_decode_error() {
+        let garbage = std::io::Cursor::new(b"not an image or audio file".to_vec());
+
+        let png_result = ::png::Decoder::new(garbage.clone()).read_info().map(|_| ());
+        let png_error: crate::PolyglotError = png_result.unwrap_err().into();
+        assert!(matches!(png_error, crate::PolyglotError::DecodeError(_)));
+
+        let wav_result = hound::WavReader::new(garbage).map(|_| ());
+        let wav_error: crate::PolyglotError = wav_result.unwrap_err().into();
+        assert!(matches!(wav_error, crate::PolyglotError::DecodeError(_)));
+    }
 }
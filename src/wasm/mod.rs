@@ -0,0 +1,64 @@
+//! Browser bindings for building and unpacking polyglots in memory (enabled by the `wasm` feature)
+
+use wasm_bindgen::prelude::*;
+use crate::extract::{extract_png_from_zip_comment_data, extract_png_from_zip_data, split_polyglot_data};
+use crate::polyglot::{EmbeddingMethod, PolyglotCreator};
+use crate::PolyglotResult;
+
+/// Build a PNG+ZIP polyglot with the given method
+///
+/// Like the CLI, `idat` on a carrier with no IDAT chunk adds a blank one sized to IHDR,
+/// falling back to `text` when that can't be built. `method` is a `--method` name.
+pub fn build_png_zip_polyglot(png: &[u8], zip: &[u8], method: &str) -> PolyglotResult<Vec<u8>> {
+    let method: EmbeddingMethod = method.parse()?;
+    let mut creator = PolyglotCreator::from_data(png.to_vec(), zip.to_vec())?;
+    if method == EmbeddingMethod::Idat {
+        // On failure the carrier still has no IDAT, so effective_method picks text
+        creator.synthesize_idat().ok();
+    }
+    creator.create_polyglot_in_memory_with_method(creator.effective_method(method))
+}
+
+/// Recover the embedded payload: the ZIP from a PNG-dominant polyglot, or the PNG from a ZIP-dominant one
+pub fn extract_payload_data(data: &[u8]) -> PolyglotResult<Vec<u8>> {
+    if crate::utils::is_png_signature(data) {
+        split_polyglot_data(data).map(|(_, payload)| payload)
+    } else if data.starts_with(&crate::utils::ZIP_EOCD_SIGNATURE) {
+        extract_png_from_zip_comment_data(data)
+    } else {
+        extract_png_from_zip_data(data)
+    }
+}
+
+/// JS entry point for `build_png_zip_polyglot`; errors are thrown with the `PolyglotError` message
+#[wasm_bindgen]
+pub fn create_png_zip_polyglot(png: &[u8], zip: &[u8], method: &str) -> Result<Vec<u8>, JsError> {
+    build_png_zip_polyglot(png, zip, method).map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// JS entry point for `extract_payload_data`; errors are thrown with the `PolyglotError` message
+#[wasm_bindgen]
+pub fn extract_payload(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    extract_payload_data(data).map_err(|err| JsError::new(&err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{minimal_png, minimal_zip};
+
+    #[test]
+    fn test_round_trip_through_binding_functions() {
+        for method in ["text", "idat"] {
+            let polyglot = build_png_zip_polyglot(&minimal_png(), &minimal_zip(), method).unwrap();
+            let payload = extract_payload_data(&polyglot).unwrap();
+            assert_eq!(crate::utils::sniff_format(&payload), crate::utils::FileFormat::Zip, "{method}");
+        }
+
+        let zip_dominant = build_png_zip_polyglot(&minimal_png(), &minimal_zip(), "zip").unwrap();
+        assert_eq!(extract_payload_data(&zip_dominant).unwrap(), minimal_png());
+
+        let error = build_png_zip_polyglot(b"not a png", &minimal_zip(), "text").unwrap_err();
+        assert!(error.to_string().starts_with("PNG parse error"), "{error}");
+    }
+}
@@ -1,16 +1,28 @@
 //! WAV file format support for PNG+WAV parasitic polyglots (PNG embedded in RIFF chunks)
 
+use alloc::{string::ToString, vec::Vec};
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
 use std::fs;
+use crate::utils::{PNG_SIGNATURE, RIFF_SIGNATURE};
 use crate::{PolyglotError, PolyglotResult};
 
-/// RIFF file signature
-const RIFF_SIGNATURE: &[u8; 4] = b"RIFF";
+/// RIFF form type of a WAV file
 const WAVE_SIGNATURE: &[u8; 4] = b"WAVE";
 
 /// FOURCC for PNG embedding chunk (PNG with trailing space for uniqueness)
 const PNG_CHUNK_FOURCC: [u8; 4] = *b"pnG ";
 
+/// FOURCC for the standard padding chunk that every RIFF reader skips
+const JUNK_CHUNK_FOURCC: [u8; 4] = *b"JUNK";
+
+/// FOURCC for the sample count chunk that non-PCM formats must place before `data`
+const FACT_CHUNK_FOURCC: [u8; 4] = *b"fact";
+
+/// Format tag of plain integer PCM, the only format that doesn't need a `fact` chunk
+const WAVE_FORMAT_PCM: u16 = 1;
+
 /// RIFF chunk header (all chunks have this format)
 #[derive(Debug, Clone)]
 pub struct RiffChunkHeader {
@@ -38,6 +50,39 @@ pub struct FmtChunk {
     pub data: Vec<u8>, // Raw fmt data
 }
 
+/// Format tag marking a WAVE_FORMAT_EXTENSIBLE fmt chunk
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Interpreted contents of a fmt chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+    pub extensible: Option<ExtensibleFormat>, // Present when format_tag is WAVE_FORMAT_EXTENSIBLE
+}
+
+/// Extension fields of a WAVE_FORMAT_EXTENSIBLE fmt chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensibleFormat {
+    pub valid_bits_per_sample: u16,
+    pub channel_mask: u32,
+    pub sub_format: [u8; 16], // GUID; first two bytes hold the actual format tag
+}
+
+impl WavFormat {
+    /// Actual audio format tag, looking through the extensible sub-format
+    pub fn effective_format_tag(&self) -> u16 {
+        match &self.extensible {
+            Some(ext) => u16::from_le_bytes([ext.sub_format[0], ext.sub_format[1]]),
+            None => self.format_tag,
+        }
+    }
+}
+
 /// data chunk (mandatory for WAV, contains audio samples)
 #[derive(Debug, Clone)]
 pub struct DataChunk {
@@ -51,7 +96,46 @@ pub struct RiffStructure {
     pub header: RiffHeader,
     pub fmt_chunk: FmtChunk,
     pub data_chunk: DataChunk,
-    pub additional_chunks: Vec<RiffChunk>, // Chunks after data
+    pub additional_chunks: Vec<RiffChunk>, // All other chunks, in file order
+    pub fmt_position: usize,  // Index of fmt among all chunks in file order
+    pub data_position: usize, // Index of data among all chunks in file order
+}
+
+impl FmtChunk {
+    /// Interpret the raw fmt data (raw bytes are kept for re-emission)
+    pub fn format(&self) -> PolyglotResult<WavFormat> {
+        let data = &self.data;
+        if data.len() < 16 {
+            return Err(PolyglotError::WavParse("fmt chunk too short".to_string()));
+        }
+
+        let format_tag = u16::from_le_bytes([data[0], data[1]]);
+        let extensible = if format_tag == WAVE_FORMAT_EXTENSIBLE {
+            // cbSize (2 bytes) followed by 22 bytes of extension
+            if data.len() < 40 {
+                return Err(PolyglotError::WavParse("Extensible fmt chunk too short".to_string()));
+            }
+            let mut sub_format = [0u8; 16];
+            sub_format.copy_from_slice(&data[24..40]);
+            Some(ExtensibleFormat {
+                valid_bits_per_sample: u16::from_le_bytes([data[18], data[19]]),
+                channel_mask: u32::from_le_bytes([data[20], data[21], data[22], data[23]]),
+                sub_format,
+            })
+        } else {
+            None
+        };
+
+        Ok(WavFormat {
+            format_tag,
+            channels: u16::from_le_bytes([data[2], data[3]]),
+            sample_rate: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            byte_rate: u32::from_le_bytes([data[8], data[9], data[10], data[11]]),
+            block_align: u16::from_le_bytes([data[12], data[13]]),
+            bits_per_sample: u16::from_le_bytes([data[14], data[15]]),
+            extensible,
+        })
+    }
 }
 
 /// WAV file handler for parasitic polyglots
@@ -69,7 +153,7 @@ impl WavFile {
         }
 
         // Validate RIFF signature
-        if &raw_data[0..4] != RIFF_SIGNATURE {
+        if raw_data[0..4] != RIFF_SIGNATURE {
             return Err(PolyglotError::InvalidRiffHeader);
         }
 
@@ -83,6 +167,18 @@ impl WavFile {
         Ok(Self { raw_data, structure })
     }
 
+    /// Load WAV file from raw data, recovering from odd-sized chunks written without a pad byte
+    pub fn from_data_tolerant(raw_data: Vec<u8>) -> PolyglotResult<Self> {
+        if raw_data.len() < 12 || raw_data[0..4] != RIFF_SIGNATURE || &raw_data[8..12] != WAVE_SIGNATURE {
+            return Self::from_data(raw_data); // Report the same errors as strict loading
+        }
+
+        let structure = RiffStructure::parse_tolerant(&raw_data)?;
+
+        Ok(Self { raw_data, structure })
+    }
+
+    #[cfg(feature = "std")]
     /// Load WAV file from path
     pub fn from_file(path: &Path) -> PolyglotResult<Self> {
         let raw_data = fs::read(path)?;
@@ -92,7 +188,7 @@ impl WavFile {
         }
 
         // Validate RIFF signature
-        if &raw_data[0..4] != RIFF_SIGNATURE {
+        if raw_data[0..4] != RIFF_SIGNATURE {
             return Err(PolyglotError::InvalidRiffHeader);
         }
 
@@ -111,6 +207,7 @@ impl WavFile {
         &self.raw_data
     }
 
+    #[cfg(feature = "std")]
     /// Write modified WAV to file
     pub fn write_to_file(&self, path: &Path) -> PolyglotResult<()> {
         fs::write(path, &self.raw_data)?;
@@ -120,21 +217,63 @@ impl WavFile {
     /// Embed PNG data as custom RIFF chunk (WAV-dominant polyglot)
     pub fn embed_png_data(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
         self.structure.insert_png_chunk(png_data)?;
+        self.structure.move_fact_before_data();
         // Rebuild raw data with updated structure
         self.raw_data = self.structure.to_bytes()?;
         Ok(())
     }
 
+    /// Embed PNG data in a JUNK chunk placed before the data chunk
+    pub fn embed_png_in_junk(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        self.structure.insert_junk_chunk(png_data)?;
+        self.structure.move_fact_before_data();
+        self.raw_data = self.structure.to_bytes()?;
+        Ok(())
+    }
+
+    /// Embed PNG data in a JUNK chunk right after the WAVE signature, ahead of `fmt `
+    ///
+    /// The PNG signature then sits at offset 20, for tools that look for a second format's
+    /// magic near the start. Players skip the JUNK chunk before reaching `fmt `.
+    pub fn embed_png_in_leading_junk(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        self.structure.insert_leading_junk_chunk(png_data)?;
+        self.raw_data = self.structure.to_bytes()?;
+        Ok(())
+    }
+
+    /// Drop embedded PNG chunks so the file is a plain WAV again, returning whether any were found
+    pub fn strip_png_chunks(&mut self) -> PolyglotResult<bool> {
+        if !self.structure.remove_png_chunks() {
+            return Ok(false);
+        }
+        self.raw_data = self.structure.to_bytes()?;
+        Ok(true)
+    }
+
+    /// Recompute the RIFF `file_size` from the parsed chunks and rewrite the header, returning whether it was wrong
+    pub fn fix_riff_size(&mut self) -> PolyglotResult<bool> {
+        let mut bytes = self.structure.to_bytes()?;
+        let actual = u32::try_from(bytes.len() - 8)
+            .map_err(|_| PolyglotError::WavParse("RIFF data exceeds 4 GB".to_string()))?;
+
+        let changed = actual != self.structure.header.file_size;
+        self.structure.header.file_size = actual;
+        bytes[4..8].copy_from_slice(&actual.to_le_bytes());
+        self.raw_data = bytes;
+
+        Ok(changed)
+    }
+
     /// Load WAV-dominant polyglot and extract PNG data if present
     pub fn extract_png_from_wav_polyglot(wav_data: &[u8]) -> Option<Vec<u8>> {
         // First check if it starts with PNG (PNG-dominant)
-        if wav_data.len() >= 8 && &wav_data[0..8] == b"\x89PNG\r\n\x1a\n" {
+        if wav_data.starts_with(&PNG_SIGNATURE) {
             // This is PNG-dominant, let extract module handle it
             return None;
         }
 
         // Check if this is WAV and parse it
-        if wav_data.len() >= 12 && &wav_data[0..4] == b"RIFF"
+        if wav_data.len() >= 12 && wav_data.starts_with(&RIFF_SIGNATURE)
             && let Ok(structure) = RiffStructure::parse(wav_data) {
                 return structure.extract_png_data();
             }
@@ -146,15 +285,168 @@ impl WavFile {
     pub fn extract_png_data(&self) -> Option<Vec<u8>> {
         self.structure.extract_png_data()
     }
+
+    /// Decode the file with `hound` as a player would, returning its audio format
+    ///
+    /// Every sample is read, so a data chunk cut short by embedding is caught as well as a
+    /// broken header.
+    #[cfg(feature = "std")]
+    pub fn verify_decodes(&self) -> PolyglotResult<WavSpec> {
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(&self.raw_data))?;
+        let spec = reader.spec();
+        match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().try_for_each(|sample| sample.map(drop))?,
+            hound::SampleFormat::Int => reader.samples::<i32>().try_for_each(|sample| sample.map(drop))?,
+        }
+
+        Ok(WavSpec {
+            channels: spec.channels,
+            sample_rate: spec.sample_rate,
+            bits_per_sample: spec.bits_per_sample,
+        })
+    }
+}
+
+/// Audio format reported by `WavFile::verify_decodes`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavSpec {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
 }
 
 impl RiffStructure {
     /// Insert PNG data as custom RIFF chunk after data chunk
     pub fn insert_png_chunk(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        let png_chunk = self.new_chunk(PNG_CHUNK_FOURCC, png_data)?;
+
+        // Insert after data chunk (preserves audio playback compatibility)
+        self.additional_chunks.push(png_chunk);
+
+        Ok(())
+    }
+
+    /// Insert PNG data as a JUNK chunk immediately before the data chunk
+    ///
+    /// An odd-sized PNG is padded inside the chunk like in `insert_leading_junk_chunk`.
+    pub fn insert_junk_chunk(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        let junk_chunk = self.new_png_junk_chunk(png_data)?;
+
+        // Additional chunks preceding data keep their place; the JUNK chunk takes data's slot
+        let fmt_before_data = self.fmt_position < self.data_position;
+        let insert_index = self.data_position - usize::from(fmt_before_data);
+        self.additional_chunks.insert(insert_index, junk_chunk);
+
+        if !fmt_before_data {
+            self.fmt_position += 1;
+        }
+        self.data_position += 1;
+
+        Ok(())
+    }
+
+    /// Insert PNG data as a JUNK chunk ahead of every other chunk
+    ///
+    /// hound doesn't skip the pad byte of unknown chunks before `fmt `, so an odd-sized PNG
+    /// is stored with one zero byte after IEND to keep the chunk even-sized.
+    pub fn insert_leading_junk_chunk(&mut self, png_data: &[u8]) -> PolyglotResult<()> {
+        let junk_chunk = self.new_png_junk_chunk(png_data)?;
+        self.additional_chunks.insert(0, junk_chunk);
+        self.fmt_position += 1;
+        self.data_position += 1;
+
+        Ok(())
+    }
+
+    /// Remove the chunks carrying embedded PNG data (`pnG ` and PNG-bearing JUNK), shrinking the RIFF size
+    ///
+    /// Returns whether anything was removed. Ordinary chunks, including padding JUNK, are kept.
+    pub fn remove_png_chunks(&mut self) -> bool {
+        let mut removed = false;
+        let mut index = 0;
+        let mut position = 0;
+
+        while index < self.additional_chunks.len() {
+            // Skip over fmt and data to find this chunk's place in file order
+            while position == self.fmt_position || position == self.data_position {
+                position += 1;
+            }
+
+            let chunk = &self.additional_chunks[index];
+            let carries_png = chunk.header.fourcc == PNG_CHUNK_FOURCC
+                || (chunk.header.fourcc == JUNK_CHUNK_FOURCC && chunk.data.starts_with(&PNG_SIGNATURE));
+            if !carries_png {
+                index += 1;
+                position += 1;
+                continue;
+            }
+
+            let chunk_size = 8 + chunk.header.data_size + chunk.header.data_size % 2;
+            self.header.file_size = self.header.file_size.saturating_sub(chunk_size);
+            self.additional_chunks.remove(index);
+            if self.fmt_position > position {
+                self.fmt_position -= 1;
+            }
+            if self.data_position > position {
+                self.data_position -= 1;
+            }
+            removed = true;
+        }
+
+        removed
+    }
+
+    /// Move a `fact` chunk that follows `data` to just before it, returning whether it moved
+    ///
+    /// Non-PCM formats (IEEE float, ADPCM, ...) need `fact` ahead of `data`; PCM files and
+    /// chunks already in place are left alone, and the RIFF size is unchanged.
+    pub fn move_fact_before_data(&mut self) -> bool {
+        let is_pcm = self.fmt_chunk.format().is_ok_and(|format| format.effective_format_tag() == WAVE_FORMAT_PCM);
+        let chunks_before_data = self.data_position - usize::from(self.fmt_position < self.data_position);
+        let fact_index = self.additional_chunks.iter().position(|chunk| chunk.header.fourcc == FACT_CHUNK_FOURCC);
+
+        let Some(fact_index) = fact_index.filter(|&index| !is_pcm && index >= chunks_before_data) else {
+            return false;
+        };
+
+        // File position of the fact chunk, skipping over fmt and data
+        let mut fact_position = fact_index;
+        for position in [self.fmt_position.min(self.data_position), self.fmt_position.max(self.data_position)] {
+            if position <= fact_position {
+                fact_position += 1;
+            }
+        }
+
+        let fact = self.additional_chunks.remove(fact_index);
+        self.additional_chunks.insert(chunks_before_data, fact);
+
+        // Everything from data up to the fact chunk's old slot moves one place later
+        if self.fmt_position > self.data_position && self.fmt_position < fact_position {
+            self.fmt_position += 1;
+        }
+        self.data_position += 1;
+
+        true
+    }
+
+    /// JUNK chunk holding PNG data, with a zero byte after an odd-sized PNG so the chunk
+    /// needs no RIFF pad byte (`without_even_padding` drops it again)
+    fn new_png_junk_chunk(&mut self, png_data: &[u8]) -> PolyglotResult<RiffChunk> {
+        if png_data.len() % 2 == 1 {
+            self.new_chunk(JUNK_CHUNK_FOURCC, &[png_data, &[0]].concat())
+        } else {
+            self.new_chunk(JUNK_CHUNK_FOURCC, png_data)
+        }
+    }
+
+    /// Build a chunk and grow the RIFF file size to account for it
+    fn new_chunk(&mut self, fourcc: [u8; 4], data: &[u8]) -> PolyglotResult<RiffChunk> {
+        crate::utils::ensure_payload_not_empty(data)?;
+
         // Check for size overflow
-        let png_size = png_data.len() as u64;
-        let chunk_data_size = 8 + png_size; // 4-byte FOURCC + 4-byte size + data
-        let padding_size = if png_size % 2 == 1 { 1 } else { 0 }; // RIFF padding
+        let data_size = data.len() as u64;
+        let chunk_data_size = 8 + data_size; // 4-byte FOURCC + 4-byte size + data
+        let padding_size = if data_size % 2 == 1 { 1 } else { 0 }; // RIFF padding
         let additional_size = chunk_data_size + padding_size;
 
         if additional_size > u32::MAX as u64 {
@@ -168,31 +460,46 @@ impl RiffStructure {
         }
         self.header.file_size = new_total_size as u32;
 
-        // Create PNG chunk
-        let png_chunk = RiffChunk {
+        Ok(RiffChunk {
             header: RiffChunkHeader {
-                fourcc: PNG_CHUNK_FOURCC,
-                data_size: png_size as u32,
+                fourcc,
+                data_size: data_size as u32,
             },
-            data: png_data.to_vec(),
-        };
-
-        // Insert after data chunk (preserves audio playback compatibility)
-        self.additional_chunks.push(png_chunk);
-
-        Ok(())
+            data: data.to_vec(),
+        })
     }
 
-    /// Get PNG data from embedded chunk if present
+    /// Get PNG data from embedded chunk if present, checking `pnG ` before JUNK
     pub fn extract_png_data(&self) -> Option<Vec<u8>> {
         self.additional_chunks
             .iter()
             .find(|chunk| chunk.header.fourcc == PNG_CHUNK_FOURCC)
             .map(|chunk| chunk.data.clone())
+            .or_else(|| self.extract_junk_png_data())
+    }
+
+    /// Get PNG data from a JUNK chunk, ignoring ordinary padding JUNK chunks
+    pub fn extract_junk_png_data(&self) -> Option<Vec<u8>> {
+        self.additional_chunks
+            .iter()
+            .find(|chunk| chunk.header.fourcc == JUNK_CHUNK_FOURCC && chunk.data.starts_with(&PNG_SIGNATURE))
+            .map(|chunk| without_even_padding(&chunk.data).to_vec())
     }
 
     /// Parse RIFF structure from raw bytes
     pub fn parse(data: &[u8]) -> PolyglotResult<Self> {
+        Self::parse_with_padding(data, false)
+    }
+
+    /// Parse like `parse`, re-syncing after odd-sized chunks that are missing their pad byte
+    ///
+    /// When the header after the pad byte doesn't start with an ASCII FourCC but the one
+    /// without it does, the unpadded position is used. `to_bytes` writes the pad byte back.
+    pub fn parse_tolerant(data: &[u8]) -> PolyglotResult<Self> {
+        Self::parse_with_padding(data, true)
+    }
+
+    fn parse_with_padding(data: &[u8], tolerant: bool) -> PolyglotResult<Self> {
         if data.len() < 12 {
             return Err(PolyglotError::WavParse("Data too short for RIFF header".to_string()));
         }
@@ -205,6 +512,10 @@ impl RiffStructure {
         let mut fmt_chunk: Option<FmtChunk> = None;
         let mut data_chunk: Option<DataChunk> = None;
         let mut additional_chunks = Vec::new();
+        let mut fmt_position = 0;
+        let mut data_position = 0;
+        let mut chunk_index = 0;
+        let mut resynced = false;
 
         // Parse chunks until we have the mandatory fmt and data chunks
         while offset + 8 <= data.len() {
@@ -220,12 +531,14 @@ impl RiffStructure {
 
             match &chunk_header.fourcc {
                 b"fmt " => {
+                    fmt_position = chunk_index;
                     fmt_chunk = Some(FmtChunk {
                         header: chunk_header.clone(),
                         data: chunk_data,
                     });
                 }
                 b"data" => {
+                    data_position = chunk_index;
                     data_chunk = Some(DataChunk {
                         header: chunk_header.clone(),
                         data: chunk_data,
@@ -242,17 +555,33 @@ impl RiffStructure {
 
             // Move to next chunk (chunk size is padded to even bytes)
             offset = chunk_data_end + ((chunk_header.data_size % 2) as usize);
+            if tolerant && offset != chunk_data_end && !is_fourcc(&data[offset.min(data.len())..]) && is_fourcc(&data[chunk_data_end..]) {
+                offset = chunk_data_end; // Producer omitted the pad byte
+                resynced = true;
+            }
+            chunk_index += 1;
         }
 
         let fmt_chunk = fmt_chunk.ok_or_else(|| PolyglotError::ChunkNotFound("fmt ".to_string()))?;
         let data_chunk = data_chunk.ok_or_else(|| PolyglotError::ChunkNotFound("data".to_string()))?;
 
-        Ok(RiffStructure {
+        let mut structure = RiffStructure {
             header,
             fmt_chunk,
             data_chunk,
             additional_chunks,
-        })
+            fmt_position,
+            data_position,
+        };
+
+        // `to_bytes` writes the missing pad bytes back, so the size read from the file is short
+        if resynced {
+            let emitted_len = structure.to_bytes()?.len();
+            structure.header.file_size = u32::try_from(emitted_len - 8)
+                .map_err(|_| PolyglotError::WavParse("RIFF data exceeds 4 GB".to_string()))?;
+        }
+
+        Ok(structure)
     }
 
     /// Parse a chunk header from data
@@ -273,19 +602,20 @@ impl RiffStructure {
         let mut result = Vec::new();
 
         // RIFF header
-        result.extend_from_slice(RIFF_SIGNATURE);
+        result.extend_from_slice(&RIFF_SIGNATURE);
         result.extend_from_slice(&self.header.file_size.to_le_bytes());
         result.extend_from_slice(WAVE_SIGNATURE);
 
-        // fmt chunk
-        Self::write_chunk(&mut result, &self.fmt_chunk.header, &self.fmt_chunk.data);
-
-        // data chunk
-        Self::write_chunk(&mut result, &self.data_chunk.header, &self.data_chunk.data);
-
-        // Additional chunks
-        for chunk in &self.additional_chunks {
-            Self::write_chunk(&mut result, &chunk.header, &chunk.data);
+        // Emit chunks in their original order; chunks added later follow at the end
+        let mut additional = self.additional_chunks.iter();
+        for position in 0..self.additional_chunks.len() + 2 {
+            if position == self.fmt_position {
+                Self::write_chunk(&mut result, &self.fmt_chunk.header, &self.fmt_chunk.data);
+            } else if position == self.data_position {
+                Self::write_chunk(&mut result, &self.data_chunk.header, &self.data_chunk.data);
+            } else if let Some(chunk) = additional.next() {
+                Self::write_chunk(&mut result, &chunk.header, &chunk.data);
+            }
         }
 
         Ok(result)
@@ -304,72 +634,94 @@ impl RiffStructure {
     }
 }
 
+/// PNG data without the zero byte `new_png_junk_chunk` adds after an odd-sized PNG
+fn without_even_padding(data: &[u8]) -> &[u8] {
+    let png_end = crate::png::parser::parse_png_chunks(data).ok()
+        .and_then(|parsed| parsed.chunks.last().map(|chunk| chunk.end_offset()));
+    match png_end {
+        Some(end) if end % 2 == 1 && end + 1 == data.len() && data[end] == 0 => &data[..end],
+        _ => data,
+    }
+}
+
+/// Length of the RIFF data at the start of `data`, found by walking its chunk headers
+///
+/// The stored `file_size` isn't trusted. The walk stops at the end of `data` or at bytes that
+/// aren't a chunk ID; `None` means a chunk claims to run past the end of `data`.
+pub fn riff_chunks_end(data: &[u8]) -> Option<usize> {
+    let mut offset = 12; // RIFF header and WAVE form type
+    while offset + 8 <= data.len() && is_fourcc(&data[offset..]) {
+        let size = u32::from_le_bytes([data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]]) as usize;
+        let end = (offset + 8).checked_add(size).filter(|&end| end <= data.len())?;
+        // Chunks are word-aligned; a missing pad byte on the final chunk is tolerated
+        offset = (end + size % 2).min(data.len());
+    }
+    Some(offset.min(data.len()))
+}
+
+/// Whether the data starts with four printable ASCII characters, as every chunk ID does
+fn is_fourcc(data: &[u8]) -> bool {
+    data.len() >= 4 && data[..4].iter().all(|byte| (0x20..=0x7E).contains(byte))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::{minimal_png, minimal_wav};
 
-    fn create_test_wav() -> Vec<u8> {
-        let mut wav = vec![];
 
-        // RIFF header
-        wav.extend_from_slice(b"RIFF");
-        wav.extend_from_slice(&(40u32).to_le_bytes()); // File size = total - 8 bytes for RIFF header/WAVE signature (48 - 8 = 40)
-        wav.extend_from_slice(b"WAVE");
-
-        // fmt chunk (16 bytes)
-        wav.extend_from_slice(b"fmt ");
-        wav.extend_from_slice(&(16u32).to_le_bytes()); // Chunk size
-        wav.extend_from_slice(&(1u16).to_le_bytes()); // Audio format (PCM)
-        wav.extend_from_slice(&(1u16).to_le_bytes()); // Channels
-        wav.extend_from_slice(&(44100u32).to_le_bytes()); // Sample rate
-        wav.extend_from_slice(&(88200u32).to_le_bytes()); // Byte rate
-        wav.extend_from_slice(&(2u16).to_le_bytes()); // Block align
-        wav.extend_from_slice(&(16u16).to_le_bytes()); // Bits per sample
-
-        // data chunk (minimal audio data)
-        wav.extend_from_slice(b"data");
-        wav.extend_from_slice(&(4u32).to_le_bytes()); // Data size
-        wav.extend_from_slice(&(0u16).to_le_bytes()); // Minimal audio data
-        wav.extend_from_slice(&(0u16).to_le_bytes());
-
-        wav
-    }
-
-    fn create_test_png() -> Vec<u8> {
-        let mut png = vec![];
-
-        // PNG signature
-        png.extend_from_slice(b"\x89PNG\r\n\x1A\n");
-
-        // IHDR chunk
-        png.extend_from_slice(&(13u32).to_be_bytes());
-        png.extend_from_slice(b"IHDR");
-        png.extend_from_slice(&(1u32).to_be_bytes()); // width = 1
-        png.extend_from_slice(&(1u32).to_be_bytes()); // height = 1
-        png.push(8); // bit depth = 8
-        png.push(2); // color type = RGB
-        png.push(0); // compression = 0
-        png.push(0); // filter = 0
-        png.push(0); // interlace = 0
-        let ihdr_data = &png[12..29]; // From IHDR to end of data
-        let ihdr_crc = crate::utils::calculate_crc32(&[b"IHDR".as_slice(), ihdr_data].concat());
-        png.extend_from_slice(&ihdr_crc.to_be_bytes());
-
-        // IDAT chunk (minimal compressed data)
-        let idat_data = [0x78, 0x9C, 0x62, 0x60, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01]; // zlib compressed 1x1 RGB
-        png.extend_from_slice(&(idat_data.len() as u32).to_be_bytes());
-        png.extend_from_slice(b"IDAT");
-        png.extend_from_slice(&idat_data);
-        let idat_crc = crate::utils::calculate_crc32(&[b"IDAT".as_slice(), &idat_data].concat());
-        png.extend_from_slice(&idat_crc.to_be_bytes());
-
-        // IEND chunk
-        png.extend_from_slice(&(0u32).to_be_bytes());
-        png.extend_from_slice(b"IEND");
-        let iend_crc = crate::utils::calculate_crc32(b"IEND");
-        png.extend_from_slice(&iend_crc.to_be_bytes());
-
-        png
+
+    #[test]
+    fn test_pcm_fmt_parsing() {
+        let wav_file = WavFile::from_data(minimal_wav()).unwrap();
+        let format = wav_file.structure.fmt_chunk.format().unwrap();
+
+        assert_eq!(format.format_tag, 1);
+        assert_eq!(format.channels, 1);
+        assert_eq!(format.sample_rate, 44100);
+        assert_eq!(format.bits_per_sample, 16);
+        assert!(format.extensible.is_none());
+        assert_eq!(format.effective_format_tag(), 1);
+    }
+
+    #[test]
+    fn test_extensible_fmt_parsing() {
+        // KSDATAFORMAT_SUBTYPE_PCM GUID
+        let pcm_guid = [
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00,
+            0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+        ];
+
+        let mut fmt_data = Vec::new();
+        fmt_data.extend_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes()); // Format tag
+        fmt_data.extend_from_slice(&(2u16).to_le_bytes()); // Channels
+        fmt_data.extend_from_slice(&(48000u32).to_le_bytes()); // Sample rate
+        fmt_data.extend_from_slice(&(288000u32).to_le_bytes()); // Byte rate
+        fmt_data.extend_from_slice(&(6u16).to_le_bytes()); // Block align
+        fmt_data.extend_from_slice(&(24u16).to_le_bytes()); // Bits per sample
+        fmt_data.extend_from_slice(&(22u16).to_le_bytes()); // cbSize
+        fmt_data.extend_from_slice(&(24u16).to_le_bytes()); // Valid bits per sample
+        fmt_data.extend_from_slice(&(3u32).to_le_bytes()); // Channel mask (FL | FR)
+        fmt_data.extend_from_slice(&pcm_guid);
+
+        let fmt_chunk = FmtChunk {
+            header: RiffChunkHeader { fourcc: *b"fmt ", data_size: fmt_data.len() as u32 },
+            data: fmt_data.clone(),
+        };
+        let format = fmt_chunk.format().unwrap();
+
+        assert_eq!(format.format_tag, WAVE_FORMAT_EXTENSIBLE);
+        assert_eq!(format.channels, 2);
+        assert_eq!(format.sample_rate, 48000);
+        assert_eq!(format.bits_per_sample, 24);
+        let ext = format.extensible.as_ref().unwrap();
+        assert_eq!(ext.valid_bits_per_sample, 24);
+        assert_eq!(ext.channel_mask, 3);
+        assert_eq!(ext.sub_format, pcm_guid);
+        assert_eq!(format.effective_format_tag(), 1);
+
+        // Raw bytes are untouched
+        assert_eq!(fmt_chunk.data, fmt_data);
     }
 
     #[test]
@@ -389,10 +741,102 @@ mod tests {
         assert!(matches!(result, Err(PolyglotError::InvalidRiffHeader)));
     }
 
+    #[test]
+    fn test_tolerant_parse_recovers_missing_pad_byte() {
+        // Odd-sized LIST chunk with no pad byte ahead of fmt and data
+        let wav = minimal_wav();
+        let mut data = wav[..12].to_vec();
+        data.extend_from_slice(b"LIST");
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(b"abc");
+        data.extend_from_slice(&wav[12..]);
+
+        assert!(WavFile::from_data(data.clone()).is_err());
+
+        let data_len = data.len();
+        let wav_file = WavFile::from_data_tolerant(data).unwrap();
+        assert_eq!(wav_file.structure.additional_chunks[0].data, b"abc");
+        assert_eq!(wav_file.structure.fmt_chunk.format().unwrap().sample_rate, 44100);
+        assert_eq!(wav_file.structure.data_chunk.data, WavFile::from_data(wav).unwrap().structure.data_chunk.data);
+
+        // The re-emitted file has the pad byte back and a RIFF size that counts it
+        let rebuilt = wav_file.structure.to_bytes().unwrap();
+        assert_eq!(rebuilt.len(), data_len + 1);
+        assert_eq!(rebuilt.len() - 8, wav_file.structure.header.file_size as usize);
+        assert_eq!(crate::utils::read_u32_le(&rebuilt, 4), wav_file.structure.header.file_size);
+        WavFile::from_data(rebuilt).unwrap();
+    }
+
+    #[test]
+    fn test_data_before_fmt_order_preserved() {
+        let wav_data = minimal_wav();
+
+        // Swap the fmt (bytes 12..36) and data (bytes 36..48) chunks
+        let mut reordered = wav_data[0..12].to_vec();
+        reordered.extend_from_slice(&wav_data[36..48]);
+        reordered.extend_from_slice(&wav_data[12..36]);
+
+        let structure = RiffStructure::parse(&reordered).unwrap();
+        assert_eq!(structure.data_position, 0);
+        assert_eq!(structure.fmt_position, 1);
+
+        // Re-emission keeps data ahead of fmt
+        assert_eq!(structure.to_bytes().unwrap(), reordered);
+
+        // Embedded chunks still go after the original chunks
+        let mut wav_file = WavFile::from_data(reordered.clone()).unwrap();
+        wav_file.embed_png_data(&minimal_png()).unwrap();
+        assert_eq!(&wav_file.raw_data[12..48], &reordered[12..48]);
+    }
+
+    #[test]
+    fn test_fact_chunk_stays_before_data() {
+        // IEEE float mono 32-bit: fmt with a zero cbSize, fact with the sample count, one sample
+        let mut float_wav = b"RIFF\0\0\0\0WAVE".to_vec();
+        float_wav.extend_from_slice(b"fmt \x12\0\0\0");
+        float_wav.extend_from_slice(&[3, 0, 1, 0]);
+        float_wav.extend_from_slice(&44100u32.to_le_bytes());
+        float_wav.extend_from_slice(&(44100u32 * 4).to_le_bytes());
+        float_wav.extend_from_slice(&[4, 0, 32, 0, 0, 0]);
+        let fact = [b"fact".as_slice(), &4u32.to_le_bytes(), &1u32.to_le_bytes()].concat();
+        let data = [b"data".as_slice(), &4u32.to_le_bytes(), &0.5f32.to_le_bytes()].concat();
+        let with_riff_size = |mut wav: Vec<u8>| {
+            let size = (wav.len() - 8) as u32;
+            wav[4..8].copy_from_slice(&size.to_le_bytes());
+            wav
+        };
+        let fact_offset = |wav: &[u8]| wav.windows(4).position(|w| w == b"fact").unwrap();
+        let data_offset = |wav: &[u8]| wav.windows(4).rposition(|w| w == b"data").unwrap();
+
+        let in_order = with_riff_size([float_wav.as_slice(), &fact, &data].concat());
+        for embed in [WavFile::embed_png_data, WavFile::embed_png_in_junk] {
+            let mut wav_file = WavFile::from_data(in_order.clone()).unwrap();
+            embed(&mut wav_file, &minimal_png()).unwrap();
+            assert!(fact_offset(&wav_file.raw_data) < data_offset(&wav_file.raw_data));
+            assert_eq!(wav_file.structure.data_chunk.data, 0.5f32.to_le_bytes());
+        }
+
+        // A fact chunk written after data is moved into place
+        let misplaced = with_riff_size([float_wav.as_slice(), &data, &fact].concat());
+        let mut wav_file = WavFile::from_data(misplaced).unwrap();
+        wav_file.embed_png_data(&minimal_png()).unwrap();
+        assert!(fact_offset(&wav_file.raw_data) < data_offset(&wav_file.raw_data));
+        let reparsed = WavFile::from_data(wav_file.raw_data.clone()).unwrap();
+        assert_eq!(reparsed.extract_png_data().unwrap(), minimal_png());
+        assert!(!wav_file.structure.move_fact_before_data());
+
+        // PCM files don't need fact, so its position is theirs to keep
+        let pcm = minimal_wav();
+        let pcm_with_fact = with_riff_size([pcm.as_slice(), &fact].concat());
+        let mut pcm_file = WavFile::from_data(pcm_with_fact.clone()).unwrap();
+        assert!(!pcm_file.structure.move_fact_before_data());
+        assert_eq!(pcm_file.structure.to_bytes().unwrap(), pcm_with_fact);
+    }
+
     #[test]
     fn test_png_embedding_and_extraction() {
-        let wav_data = create_test_wav();
-        let png_data = create_test_png();
+        let wav_data = minimal_wav();
+        let png_data = minimal_png();
 
         // Load WAV
         let mut wav_file = WavFile::from_data(wav_data.clone()).unwrap();
@@ -420,8 +864,8 @@ mod tests {
 
     #[test]
     fn test_polyglot_file_size() {
-        let wav_data = create_test_wav();
-        let png_data = create_test_png();
+        let wav_data = minimal_wav();
+        let png_data = minimal_png();
 
         let original_size = wav_data.len();
         let mut wav_file = WavFile::from_data(wav_data).unwrap();
@@ -445,26 +889,95 @@ mod tests {
     fn test_wav_still_valid_after_embedding() {
         use hound::WavReader;
 
-        let wav_data = create_test_wav();
-        let png_data = create_test_png();
+        let wav_data = minimal_wav();
+        let png_data = minimal_png();
 
         let mut wav_file = WavFile::from_data(wav_data).unwrap();
         wav_file.embed_png_data(&png_data).unwrap();
 
-        // Should still be readable as WAV
+        // Should still decode as WAV
+        let spec = wav_file.verify_decodes().unwrap();
+        assert_eq!(spec, WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 16 });
+
+        // Cutting into the audio data is caught
         let cursor = std::io::Cursor::new(&wav_file.raw_data);
-        let reader = WavReader::new(cursor).unwrap();
-        let spec = reader.spec();
+        assert_eq!(WavReader::new(cursor).unwrap().spec().sample_rate, spec.sample_rate);
+        let mut truncated = wav_file.clone();
+        let data_start = truncated.raw_data.windows(4).position(|w| w == b"data").unwrap();
+        truncated.raw_data.truncate(data_start + 8 + 1);
+        assert!(truncated.verify_decodes().is_err());
+    }
+
+    #[test]
+    fn test_large_png_in_junk_chunk_plays() {
+        use hound::WavReader;
+
+        // hound does not skip the pad byte of unknown chunks, so an odd size must not need one
+        for filler_len in [1024 * 1024, 1024 * 1024 + 1] {
+            // Bulk the PNG up with an ancillary chunk before IEND, giving an odd then an even size
+            let mut png_data = minimal_png();
+            let iend = png_data.split_off(png_data.len() - 12);
+            let filler = vec![0xAB; filler_len];
+            png_data.extend_from_slice(&(filler_len as u32).to_be_bytes());
+            png_data.extend_from_slice(b"fILl");
+            png_data.extend_from_slice(&filler);
+            png_data.extend_from_slice(&crate::png::chunk_crc(b"fILl", &filler).to_be_bytes());
+            png_data.extend_from_slice(&iend);
+            let size = png_data.len();
+
+            let mut wav_file = WavFile::from_data(minimal_wav()).unwrap();
+            wav_file.embed_png_in_junk(&png_data).unwrap();
+
+            // JUNK chunk sits between fmt and data, with data on an even offset
+            let structure = &wav_file.structure;
+            assert_eq!(structure.fmt_position, 0);
+            assert_eq!(structure.data_position, 2);
+            assert_eq!(&wav_file.raw_data[36..40], b"JUNK");
+            let data_offset = wav_file.raw_data.windows(4).rposition(|w| w == b"data").unwrap();
+            assert_eq!(data_offset % 2, 0, "{size}");
+            assert_eq!(wav_file.raw_data.len() - 8, structure.header.file_size as usize);
+
+            // Still plays as a WAV, with audio samples intact
+            let cursor = std::io::Cursor::new(&wav_file.raw_data);
+            let mut reader = WavReader::new(cursor).unwrap();
+            assert_eq!(reader.spec().sample_rate, 44100);
+            let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+            assert_eq!(samples, vec![0, 0]);
+
+            // And the PNG comes back out
+            let extracted = WavFile::extract_png_from_wav_polyglot(&wav_file.raw_data).unwrap();
+            assert_eq!(extracted, png_data);
+        }
+    }
+
+    #[test]
+    fn test_leading_junk_chunk_precedes_fmt() {
+        use hound::WavReader;
+
+        let png_data = minimal_png();
+        let mut wav_file = WavFile::from_data(minimal_wav()).unwrap();
+        wav_file.embed_png_in_leading_junk(&png_data).unwrap();
+
+        assert_eq!(&wav_file.raw_data[12..16], b"JUNK");
+        assert!(wav_file.raw_data[20..].starts_with(&PNG_SIGNATURE));
+        let fmt_offset = wav_file.raw_data.windows(4).position(|w| w == b"fmt ").unwrap();
+        assert_eq!(fmt_offset, 20 + png_data.len() + png_data.len() % 2);
+        assert_eq!(crate::utils::read_u32_le(&wav_file.raw_data, 16) % 2, 0, "JUNK before fmt must not need a pad byte");
+        assert_eq!(wav_file.raw_data.len() - 8, wav_file.structure.header.file_size as usize);
+
+        let reparsed = WavFile::from_data(wav_file.raw_data.clone()).unwrap();
+        assert_eq!((reparsed.structure.fmt_position, reparsed.structure.data_position), (1, 2));
+        assert_eq!(reparsed.extract_png_data().unwrap(), png_data);
+        reparsed.verify_decodes().unwrap();
 
-        // Basic format validation
-        assert_eq!(spec.channels, 1);
-        assert_eq!(spec.sample_rate, 44100);
-        assert_eq!(spec.bits_per_sample, 16);
+        let mut reader = WavReader::new(std::io::Cursor::new(&wav_file.raw_data)).unwrap();
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, vec![0, 0]);
     }
 
     #[test]
     fn test_size_overflow_prevention() {
-        let wav_data = create_test_wav();
+        let wav_data = minimal_wav();
         let large_png = vec![0u8; (u32::MAX as usize) - 7]; // Would cause overflow
 
         let mut wav_file = WavFile::from_data(wav_data).unwrap();
@@ -2,68 +2,100 @@
 
 pub mod offsets;
 
+use alloc::{format, string::ToString, vec::Vec};
+#[cfg(feature = "std")]
 use std::path::Path;
+#[cfg(feature = "std")]
 use std::fs;
-use crate::utils::read_u32_le;
+use crate::utils::{read_u32_le, write_u32_le, ZIP_CENTRAL_DIRECTORY_SIGNATURE, ZIP_EOCD_SIGNATURE, ZIP_LOCAL_SIGNATURE};
+use offsets::read_u16_le;
 use crate::{PolyglotError, PolyglotResult};
 
+/// Unix permissions stored for, and restored to, entries whose own mode isn't known
+pub const DEFAULT_ENTRY_MODE: u32 = 0o644;
+
 /// ZIP archive representation with offset tracking
 #[derive(Debug)]
 pub struct ZipArchive {
     pub data: Vec<u8>,
     pub eocd_offset: usize,
     pub eocd: offsets::EocdRecord,
+    pub base_offset: usize, // Start of the last archive when ZIPs are concatenated
 }
 
 impl ZipArchive {
+    #[cfg(feature = "std")]
     /// Read ZIP file from path
     pub fn read_zip(path: &Path) -> PolyglotResult<Self> {
-        let data = fs::read(path)?;
+        Self::from_data(fs::read(path)?)
+    }
 
+    /// Create from raw data
+    pub fn from_data(data: Vec<u8>) -> PolyglotResult<Self> {
         if !offsets::validate_zip_signature(&data) {
-            return Err(PolyglotError::ZipParse("Invalid ZIP signature".to_string()));
+            return Err(PolyglotError::not_zip(&data));
         }
 
-        let eocd = offsets::find_eocd(&data)?;
+        let eocd_offset = offsets::find_eocd_offset(&data)?;
+        let eocd = offsets::read_eocd(&data, eocd_offset);
 
-        // Find EOCD offset in data
-        let mut eocd_offset = data.len() - 22; // Start search from end
-        while eocd_offset > 0 {
-            if read_u32_le(&data, eocd_offset) == 0x06054B50 {
-                break;
-            }
-            eocd_offset -= 1;
-        }
+        let base_offset = offsets::calculate_base_offset(&data, eocd_offset, &eocd);
 
-        Ok(Self {
+        let archive = Self {
             data,
             eocd_offset,
             eocd,
-        })
+            base_offset,
+        };
+        // ZIP64 archives keep the real count in the ZIP64 end record
+        if !offsets::uses_zip64(&archive.data, &archive.eocd) {
+            archive.check_entry_count()?;
+        }
+
+        Ok(archive)
     }
 
-    /// Create from raw data
-    pub fn from_data(data: Vec<u8>) -> PolyglotResult<Self> {
-        if !offsets::validate_zip_signature(&data) {
-            return Err(PolyglotError::ZipParse("Invalid ZIP signature".to_string()));
+    /// Create an archive with no entries: a bare End of Central Directory record
+    pub fn empty() -> Self {
+        let mut data = ZIP_EOCD_SIGNATURE.to_vec();
+        data.extend_from_slice(&[0u8; 18]); // Disk numbers, entry counts, CD size/offset, comment length
+
+        Self {
+            data,
+            eocd_offset: 0,
+            eocd: offsets::EocdRecord {
+                signature: u32::from_le_bytes(ZIP_EOCD_SIGNATURE),
+                disk_num: 0,
+                cd_disk_num: 0,
+                num_entries_disk: 0,
+                num_entries_total: 0,
+                cd_size: 0,
+                cd_offset: 0,
+                comment_length: 0,
+            },
+            base_offset: 0,
         }
+    }
 
-        let eocd = offsets::find_eocd(&data)?;
+    /// Replace the archive comment stored after the EOCD record
+    pub fn set_comment(&mut self, comment: &[u8]) -> PolyglotResult<()> {
+        let comment_length = u16::try_from(comment.len()).map_err(|_| PolyglotError::InvalidInput(format!(
+            "ZIP comment is limited to 65535 bytes, got {}", comment.len()
+        )))?;
 
-        // Find EOCD offset in data
-        let mut eocd_offset = data.len() - 22; // Start search from end
-        while eocd_offset > 0 {
-            if read_u32_le(&data, eocd_offset) == 0x06054B50 {
-                break;
-            }
-            eocd_offset -= 1;
-        }
+        let comment_start = self.eocd_offset + 22;
+        self.data.truncate(comment_start);
+        self.data.extend_from_slice(comment);
+        self.data[comment_start - 2..comment_start].copy_from_slice(&comment_length.to_le_bytes());
+        self.eocd.comment_length = comment_length;
 
-        Ok(Self {
-            data,
-            eocd_offset,
-            eocd,
-        })
+        Ok(())
+    }
+
+    /// Archive comment stored after the EOCD record
+    pub fn comment(&self) -> &[u8] {
+        let comment_start = self.eocd_offset + 22;
+        &self.data[comment_start..comment_start + self.eocd.comment_length as usize]
     }
 
     /// Calculate required offset adjustments for embedding at the given position
@@ -83,18 +115,157 @@ impl ZipArchive {
             return Err(PolyglotError::ZipParse("ZIP64 format not supported".to_string()));
         }
 
-        offsets::update_central_directory_offsets(&mut self.data, self.eocd.cd_offset, offset_adjustment)?;
+        // Offsets on other disks can't be rebased within this data
+        if offsets::uses_multiple_disks(&self.eocd) {
+            return Err(PolyglotError::ZipParse("multi-disk archives unsupported".to_string()));
+        }
+
+        // Stored offsets are relative to the last archive, which starts at base_offset
+        let offset_adjustment = offset_adjustment + self.base_offset as u64;
+        offsets::update_central_directory_offsets(&mut self.data[self.base_offset..], self.eocd.cd_offset, offset_adjustment)?;
 
         // Update the EOCD central directory offset
         let new_cd_offset = self.eocd.cd_offset + offset_adjustment as u32;
         offsets::update_eocd_cd_offset(&mut self.data, self.eocd_offset, new_cd_offset)?;
 
-        // Update our cached copy
+        // Update our cached copy; offsets are now absolute within the data
+        self.eocd.cd_offset = new_cd_offset;
+        self.base_offset = 0;
+
+        Ok(())
+    }
+
+    /// Rewrite every stored offset for the archive's first byte sitting at `new_base_offset` in the file
+    ///
+    /// Unlike `update_central_directory_offsets`, which adds to whatever the offsets currently
+    /// are, this works out the archive's present base from where its central directory really
+    /// is, so it can be called again after later edits move the archive.
+    pub fn reseat(&mut self, new_base_offset: u64) -> PolyglotResult<()> {
+        if offsets::uses_zip64(&self.data, &self.eocd) {
+            return Err(PolyglotError::ZipParse("ZIP64 format not supported".to_string()));
+        }
+        if offsets::uses_multiple_disks(&self.eocd) {
+            return Err(PolyglotError::ZipParse("multi-disk archives unsupported".to_string()));
+        }
+
+        // The central directory ends where the EOCD starts, whatever its stored offset claims
+        let cd_start = self.eocd_offset.checked_sub(self.eocd.cd_size as usize)
+            .ok_or_else(|| PolyglotError::ZipParse("Central directory size exceeds its position".to_string()))?;
+        let new_cd_offset = new_base_offset.checked_add(cd_start as u64).ok_or(PolyglotError::SizeOverflow)?;
+        let new_cd_offset = u32::try_from(new_cd_offset)
+            .map_err(|_| PolyglotError::ZipParse("Offset adjustment too large for ZIP format".to_string()))?;
+
+        let delta = new_cd_offset as i64 - self.eocd.cd_offset as i64;
+        offsets::shift_central_directory_offsets(&mut self.data, cd_start, delta)?;
+        offsets::update_eocd_cd_offset(&mut self.data, self.eocd_offset, new_cd_offset)?;
+
         self.eocd.cd_offset = new_cd_offset;
+        self.base_offset = 0;
+
+        Ok(())
+    }
+
+    /// Rename an entry in both its local and central directory headers
+    ///
+    /// Everything after a header whose name changes length moves, so later local header
+    /// offsets and the EOCD's central directory offset and size are adjusted to match.
+    pub fn rename_entry(&mut self, old: &str, new: &str) -> PolyglotResult<()> {
+        if offsets::uses_zip64(&self.data, &self.eocd) || offsets::uses_multiple_disks(&self.eocd) {
+            return Err(PolyglotError::ZipParse("renaming is only supported in single-disk, non-ZIP64 archives".to_string()));
+        }
+        let new_name_len = u16::try_from(new.len())
+            .map_err(|_| PolyglotError::InvalidInput(format!("entry name of {} bytes is too long", new.len())))?;
+
+        // Central directory entries: (position, stored local header offset, name)
+        let cd_start = self.base_offset + self.eocd.cd_offset as usize;
+        let mut entries = Vec::new();
+        let mut offset = cd_start;
+        while offset + 46 <= self.data.len() && self.data[offset..].starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE) {
+            let name_len = read_u16_le(&self.data, offset + 28) as usize;
+            let extra_len = read_u16_le(&self.data, offset + 30) as usize;
+            let comment_len = read_u16_le(&self.data, offset + 32) as usize;
+            let name = self.data.get(offset + 46..offset + 46 + name_len)
+                .ok_or_else(|| PolyglotError::ZipParse("central directory entry name is truncated".to_string()))?;
+            entries.push((offset, read_u32_le(&self.data, offset + 42), name.to_vec()));
+            offset += 46 + name_len + extra_len + comment_len;
+        }
+
+        if old != new && entries.iter().any(|(_, _, name)| name == new.as_bytes()) {
+            return Err(PolyglotError::InvalidInput(format!("an entry named {:?} already exists", new)));
+        }
+        let (cd_entry, local_offset, _) = entries.iter()
+            .find(|(_, _, name)| name == old.as_bytes())
+            .cloned()
+            .ok_or_else(|| PolyglotError::InvalidInput(format!("no entry named {:?}", old)))?;
+
+        let local_header = self.base_offset + local_offset as usize;
+        if local_header + 30 + old.len() > self.data.len()
+            || !self.data[local_header..].starts_with(&ZIP_LOCAL_SIGNATURE)
+            || self.data[local_header + 30..local_header + 30 + old.len()] != *old.as_bytes() {
+            return Err(PolyglotError::ZipParse(format!("local header for {:?} does not match its central directory entry", old)));
+        }
+        if cd_entry < local_header {
+            return Err(PolyglotError::ZipParse("central directory precedes the local headers".to_string()));
+        }
+
+        let delta = new.len() as i64 - old.len() as i64;
+        let shift = |value: u32| (value as i64 + delta) as u32;
+
+        let local_name = local_header + 30;
+        let cd_name = cd_entry + 46;
+        let mut data = Vec::with_capacity((self.data.len() as i64 + 2 * delta) as usize);
+        data.extend_from_slice(&self.data[..local_name]);
+        data.extend_from_slice(new.as_bytes());
+        data.extend_from_slice(&self.data[local_name + old.len()..cd_name]);
+        data.extend_from_slice(new.as_bytes());
+        data.extend_from_slice(&self.data[cd_name + old.len()..]);
+
+        // Central directory entries move by delta, and by twice that after the renamed one
+        let moved = |position: usize| (position as i64 + if position > cd_entry { 2 * delta } else { delta }) as usize;
+        data[local_header + 26..local_header + 28].copy_from_slice(&new_name_len.to_le_bytes());
+        data[moved(cd_entry) + 28..moved(cd_entry) + 30].copy_from_slice(&new_name_len.to_le_bytes());
+
+        // Local headers stored after the renamed one moved by delta
+        for (position, stored_offset, _) in &entries {
+            if *stored_offset > local_offset {
+                write_u32_le(&mut data, moved(*position) + 42, shift(*stored_offset));
+            }
+        }
+
+        self.eocd_offset = (self.eocd_offset as i64 + 2 * delta) as usize;
+        self.eocd.cd_offset = shift(self.eocd.cd_offset);
+        self.eocd.cd_size = shift(self.eocd.cd_size);
+        write_u32_le(&mut data, self.eocd_offset + 12, self.eocd.cd_size);
+        write_u32_le(&mut data, self.eocd_offset + 16, self.eocd.cd_offset);
+        self.data = data;
 
         Ok(())
     }
 
+    /// Check the EOCD's total entry count against the central directory headers actually present
+    ///
+    /// A mismatch means the archive is truncated, corrupted or crafted to mislead readers
+    /// that trust one count over the other. Returns the number of entries.
+    pub fn check_entry_count(&self) -> PolyglotResult<usize> {
+        let walked = self.local_header_offsets().len();
+        let declared = self.eocd.num_entries_total as usize;
+        if walked != declared {
+            return Err(PolyglotError::ZipParse(format!(
+                "EOCD declares {} entries, but the central directory holds {}", declared, walked
+            )));
+        }
+        Ok(walked)
+    }
+
+    /// Absolute offsets of each entry's local file header within the data
+    pub fn local_header_offsets(&self) -> Vec<usize> {
+        let cd_start = self.base_offset + self.eocd.cd_offset as usize;
+        offsets::read_local_header_offsets(&self.data, cd_start)
+            .into_iter()
+            .map(|offset| self.base_offset + offset as usize)
+            .collect()
+    }
+
     /// Get the ZIP data as bytes
     pub fn as_bytes(&self) -> &[u8] {
         &self.data
@@ -105,6 +276,7 @@ impl ZipArchive {
         &mut self.data
     }
 
+    #[cfg(feature = "std")]
     /// Write the modified ZIP to a file
     pub fn write_to_file(&self, path: &Path) -> PolyglotResult<()> {
         fs::write(path, &self.data)?;
@@ -117,94 +289,240 @@ impl ZipArchive {
     }
 }
 
+#[cfg(feature = "std")]
 /// Create a ZIP archive from a directory
+///
+/// Entries are named relative to `dir_path` with `/` separators and added in sorted
+/// order, so the same tree always gives the same archive layout.
 pub fn create_zip_from_directory(dir_path: &Path) -> PolyglotResult<ZipArchive> {
-    use std::process::Command;
+    let mut writer = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    add_directory_entries(&mut writer, dir_path, "")?;
 
-    // Use the system's zip utility to create the archive
-    let temp_dir = tempfile::tempdir()?;
-    let temp_zip = temp_dir.path().join("temp.zip");
-
-    let status = Command::new("zip")
-        .args(["-r", temp_zip.to_str().unwrap(), "."])
-        .current_dir(dir_path)
-        .status()
-        .map_err(|e| PolyglotError::CreationFailed(format!("Failed to run zip command: {}", e)))?;
+    let data = writer.finish()?.into_inner();
+    if data.len() <= 22 {
+        return Err(PolyglotError::CreationFailed(format!("{} has no files to archive", dir_path.display())));
+    }
+    ZipArchive::from_data(data)
+}
 
-    if !status.success() {
-        return Err(PolyglotError::CreationFailed("zip command failed".to_string()));
+#[cfg(feature = "std")]
+/// Add every file and subdirectory under `dir`, naming them after `prefix`
+fn add_directory_entries(
+    writer: &mut ::zip::ZipWriter<std::io::Cursor<Vec<u8>>>,
+    dir: &Path,
+    prefix: &str,
+) -> PolyglotResult<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        // Follows symlinks, so a link to a directory is archived as one rather than read as a file
+        let metadata = fs::metadata(entry.path())?;
+        let options = ::zip::write::SimpleFileOptions::default().unix_permissions(file_mode(&metadata));
+        if metadata.is_dir() {
+            writer.add_directory(name.as_str(), options)?;
+            add_directory_entries(writer, &entry.path(), &format!("{}/", name))?;
+        } else {
+            writer.start_file(name.as_str(), options)?;
+            std::io::Write::write_all(writer, &fs::read(entry.path())?)?;
+        }
     }
 
-    ZipArchive::read_zip(&temp_zip)
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+/// Permission bits of a file on disk, or `DEFAULT_ENTRY_MODE` where the platform has none
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o777
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        DEFAULT_ENTRY_MODE
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::minimal_zip;
     use tempfile::TempDir;
     use std::fs;
 
-    fn create_test_zip() -> Vec<u8> {
-        // Minimal ZIP file with one empty file
+    #[test]
+    fn test_set_comment_on_empty_archive() {
+        let mut archive = ZipArchive::empty();
+        archive.set_comment(b"hello").unwrap();
+        assert_eq!(archive.comment(), b"hello");
+        assert_eq!(archive.size(), 27);
+
+        // Replacing shrinks the data back down
+        archive.set_comment(b"").unwrap();
+        assert_eq!(archive.size(), 22);
+
+        assert!(archive.set_comment(&vec![0u8; 65536]).is_err());
+    }
+
+    #[test]
+    fn test_zip_archive_from_data() {
+        let zip_data = minimal_zip();
+        let archive = ZipArchive::from_data(zip_data).unwrap();
 
+        assert_eq!(archive.eocd.num_entries_total, 1);
+        assert!(archive.eocd.cd_offset > 0);
+    }
+
+    /// Build a ZIP whose central directory offset and size are consistent
+    fn create_consistent_test_zip(name: &[u8]) -> Vec<u8> {
         // Local file header
         let mut zip = vec![0x50, 0x4B, 0x03, 0x04]; // LFHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(b"test"); // Filename
-        // Data (empty)
+        zip.extend_from_slice(&[0x0A, 0x00]); // Version needed
+        zip.extend_from_slice(&[0u8; 20]); // Flags, method, time/date, CRC, sizes
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes()); // Filename length
+        zip.extend_from_slice(&[0x00, 0x00]); // Extra field length
+        zip.extend_from_slice(name);
 
         // Central directory header
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x01, 0x02]); // CDHS
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version made by
-        zip.extend_from_slice(&vec![0x0A, 0x00]); // Version needed
-        zip.extend_from_slice(&vec![0x00, 0x00]); // GPB flag
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Compression method
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Last mod time/date
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // CRC32
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Compressed size
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Uncompressed size
-        zip.extend_from_slice(&vec![0x04, 0x00]); // Filename length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Extra field length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // File comment length
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Internal attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // External attributes
-        zip.extend_from_slice(&vec![0x00, 0x00, 0x00, 0x00]); // Local header offset
-        zip.extend_from_slice(b"test"); // Filename
+        let cd_offset = zip.len();
+        zip.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02]); // CDHS
+        zip.extend_from_slice(&[0x0A, 0x00, 0x0A, 0x00]); // Version made by / needed
+        zip.extend_from_slice(&[0u8; 20]); // Flags, method, time/date, CRC, sizes
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes()); // Filename length
+        zip.extend_from_slice(&[0u8; 12]); // Extra/comment length, disk, attributes
+        zip.extend_from_slice(&0u32.to_le_bytes()); // Local header offset
+        zip.extend_from_slice(name);
+        let cd_size = zip.len() - cd_offset;
 
         // End of central directory
-        zip.extend_from_slice(&vec![0x50, 0x4B, 0x05, 0x06]); // EOCDS
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Disk number
-        zip.extend_from_slice(&vec![0x00, 0x00]); // CD disk number
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Entries on this disk
-        zip.extend_from_slice(&vec![0x01, 0x00]); // Total entries
-        zip.extend_from_slice(&vec![0x16, 0x00, 0x00, 0x00]); // CD size (0x16 = 22 bytes)
-        zip.extend_from_slice(&vec![0x1A, 0x00, 0x00, 0x00]); // CD offset (0x1A = 26 bytes from start)
-        zip.extend_from_slice(&vec![0x00, 0x00]); // Comment length
+        zip.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06]); // EOCDS
+        zip.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Disk numbers
+        zip.extend_from_slice(&[0x01, 0x00, 0x01, 0x00]); // Entries
+        zip.extend_from_slice(&(cd_size as u32).to_le_bytes()); // CD size
+        zip.extend_from_slice(&(cd_offset as u32).to_le_bytes()); // CD offset
+        zip.extend_from_slice(&[0x00, 0x00]); // Comment length
 
         zip
     }
 
     #[test]
-    fn test_zip_archive_from_data() {
-        let zip_data = create_test_zip();
-        let archive = ZipArchive::from_data(zip_data).unwrap();
+    fn test_non_zip_data_names_its_format() {
+        let error = ZipArchive::from_data(crate::testutil::minimal_png()).unwrap_err().to_string();
+        assert!(error.contains("expected ZIP, found PNG"), "{}", error);
 
-        assert_eq!(archive.eocd.num_entries_total, 1);
-        assert!(archive.eocd.cd_offset > 0);
+        let error = ZipArchive::from_data(b"plain text".to_vec()).unwrap_err().to_string();
+        assert!(error.contains("found unrecognised data"), "{}", error);
+    }
+
+    #[test]
+    fn test_concatenated_zip_entries_resolve() {
+        let first = create_consistent_test_zip(b"first");
+        let second = create_consistent_test_zip(b"second.txt");
+        let concatenated = [first.as_slice(), &second].concat();
+
+        let archive = ZipArchive::from_data(concatenated).unwrap();
+        assert_eq!(archive.base_offset, first.len());
+
+        // Entries from the last archive resolve to its local headers
+        let entry_offsets = archive.local_header_offsets();
+        assert_eq!(entry_offsets, vec![first.len()]);
+        let header = entry_offsets[0];
+        assert_eq!(read_u32_le(&archive.data, header), 0x04034B50);
+        assert_eq!(&archive.data[header + 30..header + 40], b"second.txt");
+    }
+
+    fn read_entry(data: &[u8], name: &str) -> Vec<u8> {
+        use std::io::Read;
+        let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let mut contents = Vec::new();
+        archive.by_name(name).unwrap().read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_rename_entry_in_zip_dominant_polyglot() {
+        let png_data = crate::testutil::minimal_png();
+        let mut creator = crate::polyglot::PolyglotCreator::from_data(png_data.clone(), minimal_zip()).unwrap();
+        let polyglot = creator.create_polyglot_in_memory_with_method(crate::polyglot::EmbeddingMethod::Zip).unwrap();
+
+        let mut archive = ZipArchive::from_data(polyglot).unwrap();
+        archive.rename_entry("image.png", "secret.bin").unwrap();
+        assert_eq!(read_entry(archive.as_bytes(), "secret.bin"), png_data);
+        assert!(archive.rename_entry("image.png", "other").is_err());
+
+        // Shorter name in a two-entry archive: the second entry's offset moves back
+        let mut writer = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, contents) in [("first-long-name.txt", b"one"), ("second.txt", b"two")] {
+            writer.start_file(name, ::zip::write::SimpleFileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut writer, contents).unwrap();
+        }
+        let mut archive = ZipArchive::from_data(writer.finish().unwrap().into_inner()).unwrap();
+        archive.rename_entry("first-long-name.txt", "1.txt").unwrap();
+        assert_eq!(read_entry(archive.as_bytes(), "1.txt"), b"one");
+        assert_eq!(read_entry(archive.as_bytes(), "second.txt"), b"two");
+        assert!(archive.rename_entry("1.txt", "second.txt").is_err());
+    }
+
+    #[test]
+    fn test_reseat_twice_resolves_against_final_layout() {
+        let mut writer = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, contents) in [("first.txt", b"one"), ("second.txt", b"two")] {
+            writer.start_file(name, ::zip::write::SimpleFileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut writer, contents).unwrap();
+        }
+        let mut archive = ZipArchive::from_data(writer.finish().unwrap().into_inner()).unwrap();
+
+        // Embedded once, then moved back when the data before it shrank
+        archive.reseat(100).unwrap();
+        archive.reseat(40).unwrap();
+
+        let layout = [vec![0u8; 40], archive.data.clone()].concat();
+        let cd_offset = archive.eocd.cd_offset as usize;
+        assert_eq!(read_u32_le(&layout, cd_offset), 0x02014B50);
+        let local_offsets = offsets::read_local_header_offsets(&layout, cd_offset);
+        assert_eq!(local_offsets.len(), 2);
+        for offset in local_offsets {
+            assert_eq!(read_u32_le(&layout, offset as usize), 0x04034B50);
+        }
+        assert_eq!(read_entry(&layout, "second.txt"), b"two");
+
+        // Offsets past 4 GiB need ZIP64
+        assert!(ZipArchive::from_data(minimal_zip()).unwrap().reseat(u32::MAX as u64).is_err());
+        assert!(matches!(ZipArchive::from_data(minimal_zip()).unwrap().reseat(u64::MAX), Err(PolyglotError::SizeOverflow)));
+    }
+
+    #[test]
+    fn test_eocd_entry_count_must_match_central_directory() {
+        let mut zip_data = create_consistent_test_zip(b"only.txt");
+        let eocd_offset = offsets::find_eocd_offset(&zip_data).unwrap();
+        zip_data[eocd_offset + 8..eocd_offset + 12].copy_from_slice(&[0x02, 0x00, 0x02, 0x00]); // Entries on disk, total
+
+        match ZipArchive::from_data(zip_data) {
+            Err(PolyglotError::ZipParse(message)) => assert_eq!(message, "EOCD declares 2 entries, but the central directory holds 1"),
+            other => panic!("expected an entry count error, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(ZipArchive::from_data(create_consistent_test_zip(b"only.txt")).unwrap().check_entry_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_multi_disk_archive_is_rejected() {
+        let mut zip_data = minimal_zip();
+        let eocd_offset = offsets::find_eocd_offset(&zip_data).unwrap();
+        zip_data[eocd_offset + 4..eocd_offset + 8].copy_from_slice(&[0x01, 0x00, 0x01, 0x00]); // This disk, CD disk
+
+        let mut archive = ZipArchive::from_data(zip_data.clone()).unwrap();
+        let result = archive.update_central_directory_offsets(100);
+
+        assert!(matches!(result, Err(PolyglotError::ZipParse(ref msg)) if msg == "multi-disk archives unsupported"));
+        assert_eq!(archive.as_bytes(), zip_data.as_slice());
     }
 
     #[test]
     fn test_offset_adjustment() {
-        let zip_data = create_test_zip();
+        let zip_data = minimal_zip();
         let mut archive = ZipArchive::from_data(zip_data).unwrap();
 
         let original_cd_offset = archive.eocd.cd_offset;
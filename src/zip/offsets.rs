@@ -1,6 +1,7 @@
 //! ZIP central directory offset calculation and updating
 
-use crate::utils::{read_u32_le, write_u32_le};
+use alloc::{format, string::ToString, vec::Vec};
+use crate::utils::{read_u32_le, read_u64_le, write_u32_le, write_u64_le, ZIP_CENTRAL_DIRECTORY_SIGNATURE, ZIP_EOCD_SIGNATURE, ZIP_LOCAL_SIGNATURE};
 use crate::{PolyglotError, PolyglotResult};
 
 /// ZIP End of Central Directory record
@@ -40,38 +41,119 @@ pub struct Zip64EocdRecord {
     pub cd_offset: u64,       // Offset of central directory from start of archive
 }
 
+/// Furthest the EOCD record can start from the end of a ZIP: its 22 bytes plus a maximal comment
+pub const MAX_EOCD_SEARCH: usize = 22 + u16::MAX as usize;
+
 /// Locate the End of Central Directory record in ZIP data
 pub fn find_eocd(data: &[u8]) -> PolyglotResult<EocdRecord> {
+    Ok(read_eocd(data, find_eocd_offset(data)?))
+}
+
+/// Read the End of Central Directory record at `offset`, as returned by `find_eocd_offset`
+pub fn read_eocd(data: &[u8], offset: usize) -> EocdRecord {
+    EocdRecord {
+        signature: read_u32_le(data, offset),
+        disk_num: read_u16_le(data, offset + 4),
+        cd_disk_num: read_u16_le(data, offset + 6),
+        num_entries_disk: read_u16_le(data, offset + 8),
+        num_entries_total: read_u16_le(data, offset + 10),
+        cd_size: read_u32_le(data, offset + 12),
+        cd_offset: read_u32_le(data, offset + 16),
+        comment_length: read_u16_le(data, offset + 20),
+    }
+}
+
+/// Find the offset of the End of Central Directory record in ZIP data
+///
+/// Only the last `MAX_EOCD_SEARCH` bytes are scanned, so a large file without an EOCD
+/// fails after at most 64 KiB instead of a scan back to offset 0.
+pub fn find_eocd_offset(data: &[u8]) -> PolyglotResult<usize> {
     if data.len() < 22 {
         return Err(PolyglotError::ZipParse("ZIP data too short for EOCD".to_string()));
     }
 
-    // Start from the end and search backwards for EOCD signature
-    let mut offset = data.len() - 22; // EOCD is at least 22 bytes
-
-    while offset > 0 {
-        if read_u32_le(data, offset) == 0x06054B50 {
-            // Found EOCD
-            let record = EocdRecord {
-                signature: read_u32_le(data, offset),
-                disk_num: read_u16_le(data, offset + 4),
-                cd_disk_num: read_u16_le(data, offset + 6),
-                num_entries_disk: read_u16_le(data, offset + 8),
-                num_entries_total: read_u16_le(data, offset + 10),
-                cd_size: read_u32_le(data, offset + 12),
-                cd_offset: read_u32_le(data, offset + 16),
-                comment_length: read_u16_le(data, offset + 20),
-            };
-
+    // Search backwards from the last position a 22-byte record fits
+    let window_start = data.len().saturating_sub(MAX_EOCD_SEARCH);
+    for offset in (window_start..=data.len() - 22).rev() {
+        if data[offset..].starts_with(&ZIP_EOCD_SIGNATURE) {
             // Validate comment length doesn't exceed remaining data
-            if (record.comment_length as usize) <= data.len() - offset - 22 {
-                return Ok(record);
+            let comment_length = read_u16_le(data, offset + 20);
+            if (comment_length as usize) <= data.len() - offset - 22 {
+                return Ok(offset);
             }
         }
-        offset -= 1;
     }
 
-    Err(PolyglotError::ZipParse("EOCD record not found".to_string()))
+    Err(PolyglotError::ZipParse(format!("EOCD record not found in the last {} bytes", MAX_EOCD_SEARCH)))
+}
+
+/// Locate the EOCD of an archive that starts at `data[0]` and may be followed by other data
+///
+/// Scans forward with no window limit and takes the first EOCD whose central directory
+/// lies inside `data` ahead of it. `base_offset` is where `data` sits in the enclosing
+/// file, so archives whose offsets were rebased to that file are recognised too.
+pub fn find_embedded_eocd_offset(data: &[u8], base_offset: usize) -> Option<usize> {
+    let mut offset = 0;
+    while offset + 22 <= data.len() {
+        let candidate = offset + data[offset..].windows(4).position(|w| w == crate::utils::ZIP_EOCD_SIGNATURE)?;
+        if candidate + 22 > data.len() {
+            return None;
+        }
+        let eocd = read_eocd(data, candidate);
+        let cd_size = eocd.cd_size as usize;
+        let fits = (eocd.comment_length as usize) <= data.len() - candidate - 22;
+        let cd_found = [Some(eocd.cd_offset as usize), (eocd.cd_offset as usize).checked_sub(base_offset)]
+            .into_iter()
+            .flatten()
+            .any(|cd_start| {
+                cd_start.checked_add(cd_size).is_some_and(|cd_end| cd_end <= candidate)
+                    && (cd_size == 0 || data[cd_start..].starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE))
+            });
+        if fits && cd_found {
+            return Some(candidate);
+        }
+        offset = candidate + 1;
+    }
+    None
+}
+
+/// Compute the base offset of the archive whose EOCD sits at `eocd_offset`
+///
+/// Central directory offsets are relative to the start of the archive. When other
+/// data precedes it (e.g. two concatenated ZIPs), the central directory ends before
+/// the EOCD and the gap is the base offset to add to every stored offset.
+pub fn calculate_base_offset(data: &[u8], eocd_offset: usize, eocd: &EocdRecord) -> usize {
+    let cd_end = eocd.cd_offset as usize + eocd.cd_size as usize;
+    if cd_end >= eocd_offset {
+        return 0; // Central directory reaches the EOCD, no prefix
+    }
+
+    // Only trust the gap if a central directory entry actually starts there
+    let base = eocd_offset - cd_end;
+    let cd_start = base + eocd.cd_offset as usize;
+    if data.get(cd_start..).is_some_and(|cd| cd.starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE)) {
+        base
+    } else {
+        0
+    }
+}
+
+/// Read the local header offset stored in each central directory entry
+pub fn read_local_header_offsets(data: &[u8], cd_start: usize) -> Vec<u32> {
+    let mut offsets = Vec::new();
+    let mut offset = cd_start;
+
+    while offset + 46 <= data.len() && data[offset..].starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE) {
+        offsets.push(read_u32_le(data, offset + 42));
+
+        let name_len = read_u16_le(data, offset + 28) as usize;
+        let extra_len = read_u16_le(data, offset + 30) as usize;
+        let comment_len = read_u16_le(data, offset + 32) as usize;
+
+        offset += 46 + name_len + extra_len + comment_len;
+    }
+
+    offsets
 }
 
 /// Check if ZIP uses ZIP64 format
@@ -83,51 +165,94 @@ pub fn uses_zip64(data: &[u8], eocd: &EocdRecord) -> bool {
     eocd.cd_offset == 0xFFFFFFFF
 }
 
+/// Check if the archive is split across disks (spanned or split ZIP)
+pub fn uses_multiple_disks(eocd: &EocdRecord) -> bool {
+    eocd.disk_num != 0 || eocd.cd_disk_num != 0 || eocd.num_entries_disk != eocd.num_entries_total
+}
+
 /// Read little-endian u16
-fn read_u16_le(data: &[u8], offset: usize) -> u16 {
+pub(crate) fn read_u16_le(data: &[u8], offset: usize) -> u16 {
     u16::from_le_bytes(data[offset..offset + 2].try_into().expect("slice too short"))
 }
 
+/// Value of a 32-bit central directory field whose real value is in the ZIP64 extra field
+const ZIP64_SENTINEL: u32 = 0xFFFFFFFF;
+
+/// Header ID of the ZIP64 extended information extra field
+const ZIP64_EXTRA_TAG: u16 = 0x0001;
+
+/// Absolute position of the 64-bit local header offset in a central directory entry's ZIP64 extra field
+///
+/// The field only holds the values whose 32-bit counterparts are the sentinel, in the order
+/// uncompressed size, compressed size, local header offset.
+fn zip64_local_offset_position(data: &[u8], entry_offset: usize, extra_start: usize, extra_len: usize) -> Option<usize> {
+    let extra_end = (extra_start + extra_len).min(data.len());
+    let mut field = extra_start;
+
+    while field + 4 <= extra_end {
+        let tag = read_u16_le(data, field);
+        let size = read_u16_le(data, field + 2) as usize;
+        if tag == ZIP64_EXTRA_TAG {
+            let skipped = [24, 20].iter().filter(|&&at| read_u32_le(data, entry_offset + at) == ZIP64_SENTINEL).count();
+            let position = field + 4 + 8 * skipped;
+            return (position + 8 <= field + 4 + size && position + 8 <= extra_end).then_some(position);
+        }
+        field += 4 + size;
+    }
+
+    None
+}
+
 /// Update all central directory entry offsets in ZIP data
+///
+/// Entries whose 32-bit local header offset is `0xFFFFFFFF` have the 64-bit offset in
+/// their ZIP64 extra field adjusted instead.
 pub fn update_central_directory_offsets(
     data: &mut [u8],
     original_cd_offset: u32,
     offset_adjustment: u64
 ) -> PolyglotResult<()> {
-    if offset_adjustment == 0 {
-        return Ok(()); // No adjustment needed
+    // A zero adjustment still goes through the shift, which validates `original_cd_offset`
+    if offset_adjustment > u32::MAX as u64 {
+        return Err(PolyglotError::ZipParse("Offset adjustment too large for ZIP format".to_string()));
     }
 
-    let adjustment = if offset_adjustment <= u32::MAX as u64 {
-        offset_adjustment as u32
-    } else {
-        return Err(PolyglotError::ZipParse("Offset adjustment too large for ZIP format".to_string()));
-    };
+    shift_central_directory_offsets(data, original_cd_offset as usize, offset_adjustment as i64)
+}
 
-    let mut offset = original_cd_offset as usize;
+/// Move the local header offset of every central directory entry starting at `cd_start` by `delta`
+///
+/// Like `update_central_directory_offsets`, but the shift may be negative, for archives
+/// that move towards the start of the file.
+pub fn shift_central_directory_offsets(data: &mut [u8], cd_start: usize, delta: i64) -> PolyglotResult<()> {
+    check_cd_start(data, cd_start)?;
+    let out_of_range = || PolyglotError::ZipParse("Local header offset overflows after adjustment".to_string());
+    let mut offset = cd_start;
 
     while offset + 46 <= data.len() { // Central directory header is at least 46 bytes
-        // Check if this is a central directory entry (signature: 0x02014B50)
-        if read_u32_le(data, offset) == 0x02014B50 {
+        if data[offset..].starts_with(&ZIP_CENTRAL_DIRECTORY_SIGNATURE) {
             // Local file header offset is at offset + 42 in central directory entry
             let local_offset_offset = offset + 42;
 
-            if local_offset_offset + 4 <= data.len() {
-                let current_offset = read_u32_le(data, local_offset_offset);
-
-                if current_offset >= original_cd_offset {
-                    // This file is after the central directory, need to adjust
-                    let new_offset = current_offset + adjustment;
-                    write_u32_le(data, local_offset_offset, new_offset);
-                }
-            }
-
-            // Move to next central directory entry
             // File name length is at offset + 28, extra field length at offset + 30, comment length at offset + 32
             let name_len = read_u16_le(data, offset + 28) as usize;
             let extra_len = read_u16_le(data, offset + 30) as usize;
             let comment_len = read_u16_le(data, offset + 32) as usize;
 
+            // Every local header moves with the archive
+            let current_offset = read_u32_le(data, local_offset_offset);
+            if current_offset == ZIP64_SENTINEL {
+                // The real offset lives in the entry's ZIP64 extra field
+                let position = zip64_local_offset_position(data, offset, offset + 46 + name_len, extra_len)
+                    .ok_or_else(|| PolyglotError::ZipParse("Entry has a ZIP64 offset sentinel but no ZIP64 offset field".to_string()))?;
+                let new_offset = read_u64_le(data, position).checked_add_signed(delta).ok_or_else(out_of_range)?;
+                write_u64_le(data, position, new_offset);
+            } else {
+                let new_offset = u32::try_from(current_offset as i64 + delta).map_err(|_| out_of_range())?;
+                write_u32_le(data, local_offset_offset, new_offset);
+            }
+
+            // Move to next central directory entry
             offset += 46 + name_len + extra_len + comment_len;
         } else {
             break; // Not a central directory entry
@@ -137,6 +262,18 @@ pub fn update_central_directory_offsets(
     Ok(())
 }
 
+/// Reject a central directory start past the end of the data, e.g. from a corrupt EOCD `cd_offset`
+///
+/// Walking from there would find no entries and silently leave every offset unchanged.
+fn check_cd_start(data: &[u8], cd_start: usize) -> PolyglotResult<()> {
+    if cd_start > data.len() {
+        return Err(PolyglotError::ZipParse(format!(
+            "central directory offset {} is beyond the end of the data ({} bytes)", cd_start, data.len()
+        )));
+    }
+    Ok(())
+}
+
 /// Update the central directory offset in the EOCD record
 pub fn update_eocd_cd_offset(data: &mut [u8], eocd_offset: usize, new_cd_offset: u32) -> PolyglotResult<()> {
     // EOCD central directory offset is at position 16 from EOCD start
@@ -152,12 +289,7 @@ pub fn update_eocd_cd_offset(data: &mut [u8], eocd_offset: usize, new_cd_offset:
 
 /// Validate that the data looks like a valid ZIP file
 pub fn validate_zip_signature(data: &[u8]) -> bool {
-    if data.len() < 4 {
-        return false;
-    }
-
-    // Check for local file header signature (PK\x03\x04)
-    read_u32_le(data, 0) == 0x04034B50
+    data.starts_with(&ZIP_LOCAL_SIGNATURE)
 }
 
 #[cfg(test)]
@@ -173,6 +305,19 @@ mod tests {
         assert!(!validate_zip_signature(&data));
     }
 
+    #[test]
+    fn test_cd_offset_past_end_is_an_error() {
+        let mut data = vec![0u8; 64];
+        let result = update_central_directory_offsets(&mut data, 1000, 16);
+        assert!(matches!(result, Err(PolyglotError::ZipParse(ref msg)) if msg.contains("offset 1000 is beyond the end")));
+        assert!(update_central_directory_offsets(&mut data, 1000, 0).is_err());
+        assert!(shift_central_directory_offsets(&mut data, 65, -4).is_err());
+        assert_eq!(data, vec![0u8; 64]);
+
+        // An empty central directory right at the end is fine
+        assert!(update_central_directory_offsets(&mut data, 64, 16).is_ok());
+    }
+
     #[test]
     fn test_eocd_locate() {
         // Create minimal ZIP with EOCD
@@ -189,4 +334,57 @@ mod tests {
         let eocd = find_eocd(&zip_data).unwrap();
         assert_eq!(eocd.signature, 0x06054B50);
     }
+
+    #[test]
+    fn test_zip64_extra_field_offset_is_adjusted() {
+        // Central directory entry with sentinel sizes and offset, all three in the ZIP64 extra field
+        let name = b"big.bin";
+        let mut entry = Vec::new();
+        entry.extend_from_slice(&0x02014B50u32.to_le_bytes());
+        entry.extend_from_slice(&[0u8; 16]); // Versions, flags, method, time, date, CRC
+        entry.extend_from_slice(&ZIP64_SENTINEL.to_le_bytes()); // Compressed size
+        entry.extend_from_slice(&ZIP64_SENTINEL.to_le_bytes()); // Uncompressed size
+        entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(4u16 + 4 + 28).to_le_bytes()); // An unrelated field, then ZIP64
+        entry.extend_from_slice(&[0u8; 10]); // Comment length, disk, attributes
+        entry.extend_from_slice(&ZIP64_SENTINEL.to_le_bytes()); // Local header offset
+        entry.extend_from_slice(name);
+        entry.extend_from_slice(&[0x55, 0x54, 4, 0, 1, 2, 3, 4]); // Extended timestamp
+        entry.extend_from_slice(&ZIP64_EXTRA_TAG.to_le_bytes());
+        entry.extend_from_slice(&28u16.to_le_bytes());
+        entry.extend_from_slice(&100u64.to_le_bytes());
+        entry.extend_from_slice(&90u64.to_le_bytes());
+        entry.extend_from_slice(&0x1234u64.to_le_bytes());
+        entry.extend_from_slice(&0u32.to_le_bytes()); // Disk number start
+
+        let mut data = entry.clone();
+        update_central_directory_offsets(&mut data, 0, 0x100).unwrap();
+
+        let offset_position = 46 + name.len() + 8 + 4 + 16;
+        assert_eq!(read_u64_le(&data, offset_position), 0x1334);
+        // The sizes and the 32-bit sentinel are untouched
+        assert_eq!(read_u32_le(&data, 42), ZIP64_SENTINEL);
+        assert_eq!(&data[..offset_position], &entry[..offset_position]);
+        assert_eq!(&data[offset_position + 8..], &entry[offset_position + 8..]);
+
+        // A sentinel without a ZIP64 field to back it is an error
+        let mut missing = entry.clone();
+        missing[46 + name.len() + 8..46 + name.len() + 10].copy_from_slice(&0x9999u16.to_le_bytes());
+        assert!(update_central_directory_offsets(&mut missing, 0, 0x100).is_err());
+    }
+
+    #[test]
+    fn test_eocd_search_is_bounded() {
+        let mut data = vec![0u8; 1024 * 1024];
+        assert!(find_eocd_offset(&data).is_err());
+
+        // A record just past the window is never reached, one at its edge still is
+        let outside = data.len() - MAX_EOCD_SEARCH - 1;
+        data[outside..outside + 4].copy_from_slice(&0x06054B50u32.to_le_bytes());
+        assert!(find_eocd_offset(&data).is_err());
+
+        let edge = data.len() - MAX_EOCD_SEARCH;
+        data[edge..edge + 4].copy_from_slice(&0x06054B50u32.to_le_bytes());
+        assert_eq!(find_eocd_offset(&data).unwrap(), edge);
+    }
 }
@@ -0,0 +1,56 @@
+//! Exit codes of the `rust-polyglot` binary
+
+#![cfg(feature = "std")]
+
+mod common;
+
+use rust_polyglot::cli::{EXIT_FAILURE, EXIT_INVALID_USAGE, EXIT_IO_ERROR, EXIT_VALIDATION_FAILED};
+use tempfile::TempDir;
+
+#[test]
+fn unparsable_command_line_exits_with_usage_code() {
+    assert_eq!(common::run_cli(&["validate", "--no-such-flag"]), EXIT_INVALID_USAGE);
+}
+
+#[test]
+fn invalid_options_exit_with_usage_code() {
+    let dir = TempDir::new().unwrap();
+    let png = dir.path().join("image.png");
+    let output = dir.path().join("out.png");
+    std::fs::write(&png, b"unused").unwrap();
+
+    // Neither --zip nor --wav
+    let code = common::run_cli(&["create", "--png", png.to_str().unwrap(), "--output", output.to_str().unwrap()]);
+    assert_eq!(code, EXIT_INVALID_USAGE);
+}
+
+#[test]
+fn unusable_input_exits_with_failure_code() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("archive.bin");
+    let output = dir.path().join("out.zip");
+    std::fs::write(&input, b"just some bytes, neither PNG nor ZIP").unwrap();
+
+    // The options are fine; the file is what --verify-hmac can't handle
+    let code = common::run_cli(&["extract", "--input", input.to_str().unwrap(), "--output", output.to_str().unwrap(), "--verify-hmac", "00"]);
+    assert_eq!(code, EXIT_FAILURE);
+}
+
+#[test]
+fn failed_validation_exits_with_validation_code() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("not-a-polyglot.bin");
+    std::fs::write(&input, b"just some bytes, neither PNG nor ZIP").unwrap();
+
+    let code = common::run_cli(&["validate", "--input", input.to_str().unwrap()]);
+    assert_eq!(code, EXIT_VALIDATION_FAILED);
+}
+
+#[test]
+fn missing_file_exits_with_io_code() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.png");
+
+    let code = common::run_cli(&["validate", "--input", missing.to_str().unwrap()]);
+    assert_eq!(code, EXIT_IO_ERROR);
+}
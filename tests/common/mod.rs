@@ -0,0 +1,65 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test file compiles its own copy of this module and uses only part of it.
+
+#![allow(dead_code)]
+
+use std::process::Command;
+
+/// Run cargo on this crate with `args`, building into `target/<target_subdir>`, and return stdout
+///
+/// A separate target directory keeps these builds from invalidating the main one. Panics
+/// with cargo's output if the command fails.
+pub fn run_cargo_in_own_target(target_subdir: &str, args: &[&str]) -> String {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let output = Command::new(env!("CARGO"))
+        .args(args)
+        .env("CARGO_TARGET_DIR", own_target_dir(target_subdir))
+        .current_dir(manifest_dir)
+        .output()
+        .expect("failed to run cargo");
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(output.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&output.stderr));
+    stdout
+}
+
+/// Target directory `run_cargo_in_own_target` builds into for `target_subdir`
+pub fn own_target_dir(target_subdir: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join(target_subdir)
+}
+
+/// Run the `rust-polyglot` binary with `args` and return its exit code
+///
+/// Its stderr is passed through, so the test harness shows it when an assertion fails.
+#[cfg(feature = "std")]
+pub fn run_cli(args: &[&str]) -> i32 {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-polyglot"))
+        .args(args)
+        .output()
+        .expect("failed to run rust-polyglot");
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    output.status.code().expect("terminated by signal")
+}
+
+/// Write a 1×1 black RGB PNG to `path`, for use as a carrier
+#[cfg(feature = "compression")]
+pub fn write_carrier_png(path: &std::path::Path) {
+    let mut png_data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_data, 1, 1);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.write_header().unwrap().write_image_data(&[0, 0, 0]).unwrap();
+    std::fs::write(path, png_data).unwrap();
+}
+
+/// Write `carrier.png` and a `tree` directory holding `notes.txt` into `dir`, returning both paths
+#[cfg(feature = "compression")]
+pub fn write_cli_inputs(dir: &std::path::Path, notes: &[u8]) -> (std::path::PathBuf, std::path::PathBuf) {
+    let carrier = dir.join("carrier.png");
+    write_carrier_png(&carrier);
+
+    let tree = dir.join("tree");
+    std::fs::create_dir_all(&tree).unwrap();
+    std::fs::write(tree.join("notes.txt"), notes).unwrap();
+    (carrier, tree)
+}
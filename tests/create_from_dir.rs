@@ -0,0 +1,74 @@
+//! `create --dir` archives a directory and embeds it in one step
+
+#![cfg(feature = "compression")]
+
+mod common;
+
+use tempfile::TempDir;
+
+#[test]
+fn directory_tree_is_embedded_as_zip() {
+    let dir = TempDir::new().unwrap();
+    let carrier = dir.path().join("carrier.png");
+    common::write_carrier_png(&carrier);
+
+    let tree = dir.path().join("tree");
+    std::fs::create_dir_all(tree.join("docs/nested")).unwrap();
+    std::fs::write(tree.join("readme.txt"), b"top level").unwrap();
+    std::fs::write(tree.join("docs/nested/deep.txt"), b"two levels down").unwrap();
+
+    for method in ["text", "chunk", "idat"] {
+        let output = dir.path().join(format!("{method}.png"));
+        let code = common::run_cli(&[
+            "create", "--png", carrier.to_str().unwrap(), "--dir", tree.to_str().unwrap(),
+            "--output", output.to_str().unwrap(), "--method", method,
+        ]);
+        assert_eq!(code, 0, "{method} failed");
+
+        let polyglot = std::fs::read(&output).unwrap();
+        // chunk and idat rebase the archive's offsets so the polyglot itself opens as a ZIP
+        let zip_data = match method {
+            "text" => rust_polyglot::extract::extract_zip_from_png_data(&polyglot).unwrap(),
+            _ => polyglot,
+        };
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip_data)).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, ["docs/", "docs/nested/", "docs/nested/deep.txt", "readme.txt"], "{method}");
+
+        let mut deep = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("docs/nested/deep.txt").unwrap(), &mut deep).unwrap();
+        assert_eq!(deep, "two levels down");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_directory_is_archived_as_directory() {
+    let dir = TempDir::new().unwrap();
+    let carrier = dir.path().join("carrier.png");
+    common::write_carrier_png(&carrier);
+
+    let target = dir.path().join("target");
+    std::fs::create_dir(&target).unwrap();
+    std::fs::write(target.join("inner.txt"), b"behind the link").unwrap();
+    let tree = dir.path().join("tree");
+    std::fs::create_dir(&tree).unwrap();
+    std::os::unix::fs::symlink(&target, tree.join("linked")).unwrap();
+
+    let output = dir.path().join("linked.png");
+    let code = common::run_cli(&[
+        "create", "--png", carrier.to_str().unwrap(), "--dir", tree.to_str().unwrap(),
+        "--output", output.to_str().unwrap(), "--method", "chunk",
+    ]);
+    assert_eq!(code, 0);
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(std::fs::read(&output).unwrap())).unwrap();
+    let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    names.sort();
+    assert_eq!(names, ["linked/", "linked/inner.txt"]);
+
+    let mut inner = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("linked/inner.txt").unwrap(), &mut inner).unwrap();
+    assert_eq!(inner, "behind the link");
+}
@@ -0,0 +1,42 @@
+//! `create --verify` re-reads the written polyglot and removes it when the payload does not come back
+
+#![cfg(feature = "compression")]
+
+mod common;
+
+use std::path::Path;
+use rust_polyglot::cli::EXIT_VALIDATION_FAILED;
+use tempfile::TempDir;
+
+fn create(carrier: &Path, tree: &Path, output: &Path, method: &str) -> i32 {
+    common::run_cli(&[
+        "create", "--png", carrier.to_str().unwrap(), "--dir", tree.to_str().unwrap(),
+        "--output", output.to_str().unwrap(), "--method", method, "--verify",
+    ])
+}
+
+#[test]
+fn verify_passes_for_a_good_create() {
+    let dir = TempDir::new().unwrap();
+    let (carrier, tree) = common::write_cli_inputs(dir.path(), b"verified on write");
+
+    for (method, name) in [("text", "out.png"), ("idat", "idat.png"), ("chunk", "chunk.png"), ("zip", "out.zip")] {
+        let output = dir.path().join(name);
+        assert_eq!(create(&carrier, &tree, &output, method), 0, "{} method failed verification", method);
+        assert!(output.exists());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn verify_failure_removes_the_output() {
+    let dir = TempDir::new().unwrap();
+    let (carrier, tree) = common::write_cli_inputs(dir.path(), b"verified on write");
+
+    // Writes through this link are discarded, so the re-read finds no payload
+    let output = dir.path().join("lost.png");
+    std::os::unix::fs::symlink("/dev/null", &output).unwrap();
+
+    assert_eq!(create(&carrier, &tree, &output, "text"), EXIT_VALIDATION_FAILED);
+    assert!(std::fs::symlink_metadata(&output).is_err(), "output should be removed after a failed verification");
+}
@@ -0,0 +1,27 @@
+//! `extract --output-dir` names the output after the input and the payload's format
+
+#![cfg(feature = "compression")]
+
+mod common;
+
+use tempfile::TempDir;
+
+#[test]
+fn output_name_is_derived_from_input_and_payload_format() {
+    let dir = TempDir::new().unwrap();
+    let (carrier, tree) = common::write_cli_inputs(dir.path(), b"batch extracted");
+
+    let polyglot = dir.path().join("holiday.png");
+    assert_eq!(common::run_cli(&[
+        "create", "--png", carrier.to_str().unwrap(), "--dir", tree.to_str().unwrap(),
+        "--output", polyglot.to_str().unwrap(), "--method", "text",
+    ]), 0);
+
+    let out_dir = dir.path().join("extracted");
+    assert_eq!(common::run_cli(&["extract", "--input", polyglot.to_str().unwrap(), "--output-dir", out_dir.to_str().unwrap()]), 0);
+
+    let entries: Vec<_> = std::fs::read_dir(&out_dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(entries, ["holiday.zip"]);
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(out_dir.join("holiday.zip")).unwrap()).unwrap();
+    assert_eq!(archive.by_name("notes.txt").unwrap().size(), 15);
+}
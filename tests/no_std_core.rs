@@ -0,0 +1,8 @@
+//! The byte-level parsers build without the `std` feature
+
+mod common;
+
+#[test]
+fn core_builds_with_no_default_features() {
+    common::run_cargo_in_own_target("no-default-features", &["build", "--lib", "--no-default-features"]);
+}
@@ -0,0 +1,10 @@
+//! The `wasm` bindings build for the browser target (needs `rustup target add wasm32-unknown-unknown`)
+
+#![cfg(feature = "wasm")]
+
+mod common;
+
+#[test]
+fn bindings_build_for_wasm32() {
+    common::run_cargo_in_own_target("wasm", &["build", "--lib", "--target", "wasm32-unknown-unknown", "--features", "wasm"]);
+}
@@ -0,0 +1,60 @@
+//! The zlib and decoding APIs still build without the `compression` feature, and fail at runtime
+//! without taking the rest of the CLI down with them
+
+mod common;
+
+#[test]
+fn compression_apis_report_disabled_feature() {
+    let stdout = common::run_cargo_in_own_target("no-compression", &[
+        "test", "--lib", "--no-default-features", "--features", "std,crc-fast",
+        "--", "test_compression_apis_report_disabled_feature",
+    ]);
+    assert!(stdout.contains("1 passed"), "{}", stdout);
+}
+
+#[test]
+fn cli_extracts_text_polyglots_without_compression() {
+    common::run_cargo_in_own_target("no-compression", &[
+        "build", "--bin", "rust-polyglot", "--no-default-features", "--features", "std,crc-fast",
+    ]);
+    let binary = common::own_target_dir("no-compression")
+        .join("debug")
+        .join(format!("rust-polyglot{}", std::env::consts::EXE_SUFFIX));
+    let run = |args: &[&str]| {
+        let output = std::process::Command::new(&binary).args(args).output().expect("failed to run rust-polyglot");
+        assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // Streaming from stdin and blank-IDAT synthesis need zlib, so the help doesn't offer them
+    assert!(!run(&["extract", "--help"]).contains("stdin"));
+    assert!(!run(&["create", "--help"]).contains("blank IDAT"));
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let carrier = dir.path().join("carrier.png");
+    std::fs::write(&carrier, carrier_png()).unwrap();
+    let tree = dir.path().join("tree");
+    std::fs::create_dir_all(&tree).unwrap();
+    std::fs::write(tree.join("notes.txt"), b"no zlib needed").unwrap();
+
+    let polyglot = dir.path().join("out.png");
+    let extracted = dir.path().join("out.zip");
+    let path = |path: &std::path::Path| path.to_str().unwrap().to_string();
+    run(&["create", "--png", &path(&carrier), "--dir", &path(&tree), "--output", &path(&polyglot), "--method", "text"]);
+    run(&["extract", "--input", &path(&polyglot), "--output", &path(&extracted)]);
+    assert!(std::fs::read(&extracted).unwrap().starts_with(b"PK\x03\x04"));
+}
+
+/// A 1×1 black RGB PNG, built by hand since `png::Encoder` needs the `compression` feature
+fn carrier_png() -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0];
+    let idat = [0x78, 0xDA, 0x63, 0x60, 0x60, 0x60, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01];
+    for (chunk_type, data) in [(b"IHDR", ihdr.as_slice()), (b"IDAT", idat.as_slice()), (b"IEND", [].as_slice())] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        png.extend_from_slice(chunk_type);
+        png.extend_from_slice(data);
+        png.extend_from_slice(&rust_polyglot::utils::calculate_crc32(&[chunk_type.as_slice(), data].concat()).to_be_bytes());
+    }
+    png
+}